@@ -1,93 +1,597 @@
 use std::sync::Arc;
 
 use crate::{
-    camera::Camera,
-    input_state,
+    camera::NavigationSettings,
+    input_state::{self, ButtonGesture, InputState},
+    keymap::{Action, ActionContext, Keymap},
     math::vector::Vector2,
-    meshes::MeshBank,
-    rendering::{drawing_stuff::DrawingStuff, render_resources::RenderResources},
+    operators::{OperatorCategory, OperatorError, OperatorParameters, OperatorRegistry},
+    panels::{
+        self, command_palette::CommandPaletteState, keymap_panel::KeymapPanelState,
+        objects_panel::ObjectsPanelState, validation_panel::ValidationPanelState,
+    },
+    rendering::{
+        grid::GridSettings,
+        overlays::OverlaySettings,
+        render_resources::RenderResourcesPerViewport,
+        viewport::{Lighting, ShadingMode},
+    },
+    scene::{Scene, SelectMode},
+    tools::{fly::FlyNavigation, knife::KnifeTool, select},
+    viewports::{Viewport, ViewportLayout},
 };
 
 pub struct ApplicationState {
-    pub camera: Camera,
-    pub mesh_bank: MeshBank,
-    pub drawing_stuff: DrawingStuff,
-    pub doing_turntable: bool,
+    /// All the viewports there can be, of which the layout shows the first few.
+    pub viewports: Vec<Viewport>,
+    pub layout: ViewportLayout,
+    /// The viewport last clicked in, whose settings the header shows. Tools work in it.
+    pub active_viewport: usize,
+    /// Shown for the object in edit mode, in all viewports.
+    pub overlays: OverlaySettings,
+    pub scene: Scene,
+    pub objects_panel_state: ObjectsPanelState,
+    pub validation_panel_state: ValidationPanelState,
+    /// Set while the knife tool is in use. It cuts the active object.
+    pub knife_tool: Option<KnifeTool>,
+    /// What the keyboard and mouse are doing, updated at the start of each frame.
+    pub input_state: InputState,
+    /// Which shortcuts do what.
+    pub keymap: Keymap,
+    pub keymap_panel_state: KeymapPanelState,
+    /// Everything that can be run from the menus and the command palette.
+    pub operators: OperatorRegistry,
+    pub command_palette_state: CommandPaletteState,
+    /// How the viewport cameras orbit and zoom.
+    pub navigation: NavigationSettings,
+    /// Set while flying through the scene with the camera of one of the viewports.
+    pub fly_navigation: Option<FlyNavigation>,
+    /// The sample counts the viewport can be drawn with on this device.
+    pub supported_sample_counts: Vec<u32>,
 }
 
 impl ApplicationState {
-    pub fn initialize() -> Self {
-        let camera = Camera::initialize(0.0, 0.0);
-        let mesh_bank = MeshBank::initialize();
-        let drawing_stuff = DrawingStuff::initialize(&camera);
+    /// - `supported_sample_counts`: see `get_supported_sample_counts()`. The viewports are
+    ///   drawn with the highest of them if the default one isn't among them.
+    pub fn initialize(supported_sample_counts: Vec<u32>) -> Self {
+        let mut viewports = Viewport::create_all();
+        for viewport in &mut viewports {
+            let sample_count = &mut viewport.drawing_stuff.sample_count;
+            if !supported_sample_counts.contains(sample_count) {
+                *sample_count = supported_sample_counts.iter().copied().max().unwrap_or(1);
+            }
+        }
+
+        let mut scene = Scene::new();
+        scene.grid = GridSettings::load_or_default();
+        let operators = OperatorRegistry::new();
+        let keymap = Keymap::load_or_default(&operators);
 
         Self {
-            camera,
-            mesh_bank,
-            drawing_stuff,
-            doing_turntable: false,
+            viewports,
+            layout: ViewportLayout::Single,
+            active_viewport: 0,
+            overlays: OverlaySettings::new(),
+            scene,
+            objects_panel_state: ObjectsPanelState::new(),
+            validation_panel_state: ValidationPanelState::new(),
+            knife_tool: None,
+            input_state: InputState::new(),
+            keymap,
+            keymap_panel_state: KeymapPanelState::new(),
+            operators,
+            command_palette_state: CommandPaletteState::new(),
+            navigation: NavigationSettings::new(),
+            fly_navigation: None,
+            supported_sample_counts,
         }
     }
 
-    /// This is where everything on the CPU side should be updated. Updating will be done in the
-    /// `prepare()` function once we get the render resources from eframe. This function will call
-    /// that as well, after having updated everything CPU side.
+    /// Regenerates what gets drawn from the objects in the scene. Call this after changing any
+    /// of them.
+    pub fn refresh_meshes_to_draw(&mut self) {
+        let (first, others) = self.viewports.split_first_mut().unwrap();
+        first
+            .drawing_stuff
+            .refresh_from_scene(&mut self.scene, &self.overlays);
+        for viewport in others {
+            viewport
+                .drawing_stuff
+                .share_meshes_from(&first.drawing_stuff);
+        }
+    }
+
+    /// Runs the operator with the id, with its default parameters unless given others, and
+    /// refreshes what gets drawn.
+    pub fn run_operator(
+        &mut self,
+        id: &str,
+        parameters: Option<OperatorParameters>,
+    ) -> Result<(), OperatorError> {
+        let Some(operator) = self.operators.get(id).copied() else {
+            return Err(format!("There is no operator {}", id));
+        };
+        if !operator.poll(self) {
+            return Err(format!("{} can't run right now", operator.name));
+        }
+
+        let parameters = parameters.unwrap_or_else(|| operator.get_default_parameters());
+        let result = operator.execute(self, &parameters);
+        self.refresh_meshes_to_draw();
+        result
+    }
+
+    /// The ids of the operators that can run right now (see `Operator::poll()`).
+    fn get_available_operators(&self) -> Vec<&'static str> {
+        self.operators
+            .get_all()
+            .iter()
+            .filter(|operator| operator.poll(self))
+            .map(|operator| operator.id)
+            .collect()
+    }
+
+    /// The objects and cameras in the scene, and the settings of the active object: symmetry,
+    /// normals and modifiers, and any problems with its mesh.
+    pub fn object_panel(&mut self, ui: &mut eframe::egui::Ui) {
+        let rng = &mut rand::thread_rng();
+        let (mut changed, operator_to_run) =
+            panels::objects_panel::show(ui, &mut self.objects_panel_state, &mut self.scene);
+        ui.separator();
+        changed |= panels::cameras_panel::show(
+            ui,
+            &mut self.scene,
+            &mut self.viewports[self.active_viewport].camera,
+        );
+        ui.separator();
+
+        match self.scene.active_object {
+            Some(active_object) => {
+                panels::symmetry_panel::show(ui, &mut self.scene.objects[active_object]);
+                ui.separator();
+                changed |= panels::normals_panel::show(ui, &mut self.scene.objects[active_object]);
+                ui.separator();
+                changed |= panels::modifier_panel::show(ui, &mut self.scene, active_object, rng);
+                ui.separator();
+                changed |= panels::validation_panel::show(
+                    ui,
+                    &mut self.validation_panel_state,
+                    &mut self.scene.objects[active_object],
+                );
+            }
+            None => {
+                ui.label("No object selected");
+            }
+        }
+
+        if changed {
+            self.refresh_meshes_to_draw();
+        }
+        if let Some((id, parameters)) = operator_to_run {
+            if let Err(error) = self.run_operator(id, parameters) {
+                log::error!("{}", error);
+            }
+        }
+    }
+
+    /// How the canvas is split, and settings for how the active viewport draws the scene, shown
+    /// above them.
+    pub fn viewport_header(&mut self, ui: &mut eframe::egui::Ui) {
+        let available_operators = self.get_available_operators();
+        let mut operator_to_run = None;
+        ui.horizontal(|ui| {
+            ui.label("Viewports");
+            for layout in [
+                ViewportLayout::Single,
+                ViewportLayout::SideBySide,
+                ViewportLayout::Quad,
+            ] {
+                ui.selectable_value(&mut self.layout, layout, layout.get_name());
+            }
+            self.active_viewport = self
+                .active_viewport
+                .min(self.layout.get_viewport_count() - 1);
+
+            ui.separator();
+            if self.layout.get_viewport_count() > 1 {
+                ui.label(format!("Viewport {}", self.active_viewport + 1));
+            }
+            for category in OperatorCategory::ALL {
+                operator_to_run = operator_to_run.or(panels::command_palette::operator_menu(
+                    ui,
+                    &self.operators,
+                    category,
+                    &available_operators,
+                ));
+            }
+            if ui
+                .button("Search")
+                .on_hover_text(self.keymap.describe(Action::CommandPalette))
+                .clicked()
+            {
+                self.command_palette_state.open();
+            }
+        });
+
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            let scene = &mut self.scene;
+            changed |= ui
+                .selectable_value(&mut scene.edit_mode, false, "Object mode")
+                .changed();
+            changed |= ui
+                .selectable_value(&mut scene.edit_mode, true, "Edit mode")
+                .changed();
+            if !scene.edit_mode {
+                return;
+            }
+
+            ui.separator();
+            for mode in [SelectMode::Vertex, SelectMode::Edge, SelectMode::Face] {
+                changed |= ui
+                    .selectable_value(&mut scene.select_mode, mode, mode.get_name())
+                    .changed();
+            }
+
+            ui.separator();
+            let overlays = &mut self.overlays;
+            ui.label("Normals");
+            changed |= ui.checkbox(&mut overlays.face_normals, "Face").changed();
+            changed |= ui
+                .checkbox(&mut overlays.vertex_normals, "Vertex")
+                .changed();
+            if overlays.face_normals || overlays.vertex_normals {
+                changed |= ui
+                    .add(
+                        eframe::egui::DragValue::new(&mut overlays.normal_length)
+                            .clamp_range(0.01..=10.0)
+                            .speed(0.01),
+                    )
+                    .changed();
+            }
+        });
+
+        // The sample count is the same for all the viewports.
+        let mut sample_count = self.viewports[0].drawing_stuff.sample_count;
+        let shading = &mut self.viewports[self.active_viewport].drawing_stuff.shading;
+        ui.horizontal(|ui| {
+            for mode in [
+                ShadingMode::Wireframe,
+                ShadingMode::Solid,
+                ShadingMode::SolidWithWireframe,
+            ] {
+                ui.selectable_value(&mut shading.mode, mode, mode.get_name());
+            }
+
+            if shading.mode.draws_faces() {
+                ui.separator();
+                ui.selectable_value(&mut shading.smooth, false, "Flat");
+                ui.selectable_value(&mut shading.smooth, true, "Smooth");
+
+                ui.separator();
+                ui.label("Lighting");
+                for lighting in [Lighting::Studio, Lighting::Matcap] {
+                    ui.selectable_value(&mut shading.lighting, lighting, lighting.get_name());
+                }
+
+                ui.separator();
+                ui.toggle_value(&mut shading.x_ray, "X-ray");
+                if shading.x_ray {
+                    ui.add(
+                        eframe::egui::Slider::new(&mut shading.x_ray_opacity, 0.0..=1.0)
+                            .text("Opacity"),
+                    );
+                }
+            }
+
+            if shading.mode.draws_edges() {
+                ui.separator();
+                ui.label("Line width");
+                ui.add(
+                    eframe::egui::DragValue::new(&mut shading.line_width)
+                        .clamp_range(0.5..=8.0)
+                        .speed(0.05)
+                        .suffix(" px"),
+                );
+            }
+
+            ui.separator();
+            let grid = &mut self.scene.grid;
+            let operators = &self.operators;
+            ui.menu_button("Grid", |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Spacing");
+                    ui.add(
+                        eframe::egui::DragValue::new(&mut grid.spacing)
+                            .clamp_range(0.001..=1000.0)
+                            .speed(0.01),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Subdivisions");
+                    ui.add(
+                        eframe::egui::DragValue::new(&mut grid.minor_subdivisions)
+                            .clamp_range(1..=100)
+                            .prefix("minor "),
+                    );
+                    ui.add(
+                        eframe::egui::DragValue::new(&mut grid.major_subdivisions)
+                            .clamp_range(1..=100)
+                            .prefix("major "),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Fade distance");
+                    ui.add(
+                        eframe::egui::DragValue::new(&mut grid.fade_distance)
+                            .clamp_range(0.1..=10000.0)
+                            .speed(0.1),
+                    );
+                });
+                ui.checkbox(&mut grid.show_axes, "Axes");
+                ui.separator();
+                ui.horizontal(|ui| {
+                    for id in ["file.save_grid", "file.load_grid"] {
+                        let operator = operators.get(id).unwrap();
+                        if ui
+                            .add_enabled(
+                                available_operators.contains(&id),
+                                eframe::egui::Button::new(operator.name),
+                            )
+                            .on_hover_text(operator.description)
+                            .clicked()
+                        {
+                            operator_to_run = Some(id);
+                            ui.close_menu();
+                        }
+                    }
+                });
+            });
+
+            ui.separator();
+            if ui.button("Keymap").clicked() {
+                self.keymap_panel_state.open = true;
+            }
+
+            ui.separator();
+            let navigation = &mut self.navigation;
+            ui.menu_button("Navigation", |ui| {
+                ui.checkbox(
+                    &mut navigation.orbit_around_selection,
+                    "Orbit around selection",
+                );
+                ui.checkbox(
+                    &mut navigation.zoom_to_mouse_position,
+                    "Zoom to mouse position",
+                );
+            });
+
+            ui.separator();
+            let name = |count: u32| match count {
+                1 => "Off".to_string(),
+                _ => format!("{}x", count),
+            };
+            eframe::egui::ComboBox::from_label("Anti-aliasing")
+                .selected_text(name(sample_count))
+                .show_ui(ui, |ui| {
+                    for count in &self.supported_sample_counts {
+                        ui.selectable_value(&mut sample_count, *count, name(*count));
+                    }
+                });
+        });
+        for viewport in &mut self.viewports {
+            viewport.drawing_stuff.sample_count = sample_count;
+        }
+
+        if changed {
+            self.refresh_meshes_to_draw();
+        }
+        if let Some(id) = operator_to_run {
+            if let Err(error) = self.run_operator(id, None) {
+                log::error!("{}", error);
+            }
+        }
+    }
+
+    /// The window to change the shortcuts in, if it is open.
+    pub fn keymap_window(&mut self, ctx: &eframe::egui::Context) {
+        let mut open = self.keymap_panel_state.open;
+        eframe::egui::Window::new("Keymap")
+            .open(&mut open)
+            .show(ctx, |ui| {
+                panels::keymap_panel::show(
+                    ui,
+                    &mut self.keymap_panel_state,
+                    &mut self.keymap,
+                    &self.operators,
+                    &self.input_state,
+                );
+            });
+        self.keymap_panel_state.open = open;
+    }
+
+    /// The window to search for operators and run them in, if it is open.
+    pub fn command_palette_window(&mut self, ctx: &eframe::egui::Context) {
+        if !self.command_palette_state.open {
+            return;
+        }
+
+        let available_operators = self.get_available_operators();
+        let mut open = true;
+        let mut operator_to_run = None;
+        eframe::egui::Window::new("Command palette")
+            .open(&mut open)
+            .collapsible(false)
+            .anchor(eframe::egui::Align2::CENTER_TOP, [0.0, 40.0])
+            .show(ctx, |ui| {
+                operator_to_run = panels::command_palette::show(
+                    ui,
+                    &mut self.command_palette_state,
+                    &self.operators,
+                    &available_operators,
+                );
+            });
+        self.command_palette_state.open &= open;
+
+        if let Some((id, parameters)) = operator_to_run {
+            let result = self.run_operator(id, Some(parameters));
+            self.command_palette_state.report(result);
+        }
+    }
+
+    /// Splits the canvas into the viewports of the layout, and draws each of them.
     pub fn custom_painting(&mut self, ui: &mut eframe::egui::Ui) {
         let (id, rect) = ui.allocate_space(ui.available_size());
+        let rects = self.layout.split(rect);
+
+        // Take user input and update camera accordingly. Flying captures all of it, including
+        // the click that ends it.
+        ui.input(|i| self.input_state.update(i));
+        let recording_shortcut = self.keymap_panel_state.is_recording();
+        // Typing in the command palette shouldn't trigger shortcuts either.
+        if !recording_shortcut && !self.command_palette_state.open {
+            self.handle_shortcut_command_palette();
+            self.handle_shortcuts_operators();
+            self.handle_shortcuts_fly_navigation(&rects);
+            self.handle_shortcut_viewport_camera_rotate(&rects);
+            self.handle_shortcuts_knife_tool();
+        }
+        if self.fly_navigation.is_some() {
+            // The mouse is only used to look around, and the camera keeps moving while keys
+            // are held.
+            ui.ctx()
+                .output_mut(|o| o.cursor_icon = eframe::egui::CursorIcon::None);
+            ui.ctx().request_repaint();
+        }
+        if let Some(time) = self.input_state.get_time_until_held() {
+            // Nothing else may happen to repaint when a button has been held long enough.
+            ui.ctx()
+                .request_repaint_after(std::time::Duration::from_secs_f64(time));
+        }
+
+        let interactive =
+            !recording_shortcut && self.input_state.is_available_to(ActionContext::Viewport);
+        for (index, rect) in rects.into_iter().enumerate() {
+            self.paint_viewport(ui, index, id.with(index), rect, interactive);
+        }
+    }
+
+    /// This is where everything on the CPU side should be updated. Updating will be done in the
+    /// `prepare()` function once we get the render resources from eframe. This function will call
+    /// that as well, after having updated everything CPU side. Clicks are ignored unless
+    /// `interactive`.
+    fn paint_viewport(
+        &mut self,
+        ui: &mut eframe::egui::Ui,
+        index: usize,
+        id: eframe::egui::Id,
+        rect: eframe::egui::Rect,
+        interactive: bool,
+    ) {
+        let viewport = &mut self.viewports[index];
+        let drawing_stuff = &mut viewport.drawing_stuff;
 
         let rect_size = Vector2::from(rect.size());
-        if !Vector2::are_approximately_equal(&rect_size, &self.drawing_stuff.drawing_region_size) {
-            self.drawing_stuff.drawing_region_size = rect_size.clone();
-            self.drawing_stuff.drawing_region_size_updated = true;
+        drawing_stuff.pixels_per_point = ui.ctx().pixels_per_point();
+        if !Vector2::are_approximately_equal(&rect_size, &drawing_stuff.drawing_region_size) {
+            drawing_stuff.drawing_region_size = rect_size.clone();
+            drawing_stuff.drawing_region_size_updated = true;
         }
 
-        if self.drawing_stuff.drawing_region_size_updated {
+        if drawing_stuff.drawing_region_size_updated {
             // update camera width/height and update depth texture
-            self.camera.handle_window_resize(
-                self.drawing_stuff.drawing_region_size.x(),
-                self.drawing_stuff.drawing_region_size.y(),
+            viewport.camera.handle_window_resize(
+                drawing_stuff.drawing_region_size.x(),
+                drawing_stuff.drawing_region_size.y(),
             );
-            self.drawing_stuff.drawing_region_size_updated = false;
+            drawing_stuff.drawing_region_size_updated = false;
         }
 
-        // Take user input and update camera accordingly
-        ui.input(|i| self.handle_shortcut_viewport_camera_rotate(i));
-
-        if self.doing_turntable {
+        if let Some(start) = viewport.turntable_origin {
             // Tell the UI to listen for drags.
             let _response = ui.interact(rect, id, eframe::egui::Sense::drag());
 
-            let p_start = ui.input(|i| i.pointer.press_origin());
-            let p_end = ui.input(|i| i.pointer.hover_pos());
-
-            // Need to be careful that these values are not `None`, which may occur will panning
-            // and the cursor leaves the screen, for example.
-            if let (Some(start), Some(end)) = (p_start, p_end) {
+            // Need to be careful that this is not `None`, which may occur when the cursor
+            // leaves the window, for example.
+            if let Some(end) = self.input_state.mouse_state.current_position {
                 let delta_mouse = Vector2::new(end.x - start.x, end.y - start.y);
 
-                self.camera
+                viewport
+                    .camera
                     .turntable_rotate(delta_mouse, (rect_size.x(), rect_size.y()));
             }
         }
 
+        let response = ui.interact(rect, id, eframe::egui::Sense::click());
+        let clicked = response.hovered()
+            && self
+                .input_state
+                .mouse_state
+                .get_button(eframe::egui::PointerButton::Primary)
+                .happened(ButtonGesture::Click);
+        if !interactive {
+            // E.g. the click ends flying, and does nothing else.
+        } else if clicked && index != self.active_viewport {
+            // The first click only makes the viewport the active one.
+            self.active_viewport = index;
+        } else if let (Some(knife_tool), Some(object), true) = (
+            &mut self.knife_tool,
+            self.scene.get_active_object(),
+            index == self.active_viewport,
+        ) {
+            let cursor = response.hover_pos().map(|p| Vector2::from(p - rect.min));
+            knife_tool.update_hovered(&object.mesh, &viewport.camera, cursor, &rect_size);
+
+            if clicked {
+                knife_tool.add_hovered_point();
+            }
+        } else if self.scene.edit_mode && self.knife_tool.is_none() {
+            // Clicking picks the element under the cursor, holding shift adds it to the
+            // selection.
+            let cursor = response.hover_pos().filter(|_| clicked);
+            let select_mode = self.scene.select_mode;
+            if let (Some(cursor), Some(object)) = (cursor, self.scene.get_active_object_mut()) {
+                let picked = select::pick(
+                    &object.mesh,
+                    &viewport.camera,
+                    &Vector2::from(cursor - rect.min),
+                    &rect_size,
+                    select_mode,
+                    viewport.drawing_stuff.shading.sees_through(),
+                );
+                let extend = self.input_state.keyboard_state.modifiers.shift;
+                select::select_picked(&mut object.mesh, picked, extend);
+                self.refresh_meshes_to_draw();
+            }
+        }
+
+        let scroll_notches = self.input_state.mouse_state.get_scroll_notches();
+        if let (true, true, Some(cursor)) =
+            (interactive, scroll_notches != 0.0, response.hover_pos())
+        {
+            self.zoom_viewport(index, scroll_notches, Vector2::from(cursor - rect.min));
+        }
+
         // Update CPU side uniforms
-        self.drawing_stuff
-            .camera_uniform
-            .update_view_projection_matrix(&self.camera);
-        self.drawing_stuff.grid_uniform.update_matrix(&self.camera);
+        let viewport = &mut self.viewports[index];
+        viewport
+            .drawing_stuff
+            .update_uniforms(&viewport.camera, &self.scene.grid);
 
         // Handle GPU side things TODO
-        let meshes_to_draw = self.drawing_stuff.clone();
+        let meshes_to_draw = viewport.drawing_stuff.clone();
 
         let cb = eframe::egui_wgpu::CallbackFn::new()
-            .prepare(move |device, queue, _encoder, paint_callback_resources| {
-                let resources: &mut RenderResources = paint_callback_resources.get_mut().unwrap();
-                resources.prepare(device, queue, meshes_to_draw.clone());
+            .prepare(move |device, queue, encoder, paint_callback_resources| {
+                let resources: &mut RenderResourcesPerViewport =
+                    paint_callback_resources.get_mut().unwrap();
+                resources.prepare(index, device, queue, encoder, meshes_to_draw.clone());
                 Vec::new()
             })
             .paint(move |_info, render_pass, paint_callback_resources| {
-                let resources: &RenderResources = paint_callback_resources.get().unwrap();
-                resources.paint(render_pass);
+                let resources: &RenderResourcesPerViewport =
+                    paint_callback_resources.get().unwrap();
+                resources.paint(index, render_pass);
             });
 
         let callback = eframe::egui::PaintCallback {
@@ -96,21 +600,142 @@ impl ApplicationState {
         };
 
         ui.painter().add(callback);
+
+        if let (Some(knife_tool), true) = (&self.knife_tool, index == self.active_viewport) {
+            knife_tool.paint_preview(ui.painter(), &viewport.camera, rect);
+        }
     }
 
-    pub fn handle_shortcut_viewport_camera_rotate(
-        &mut self,
-        egui_input_state: &eframe::egui::InputState,
-    ) {
-        if input_state::viewport_camera_rotate(egui_input_state) {
-            self.doing_turntable = true;
+    /// Zooms towards what is under the cursor, if the preference is set.
+    fn zoom_viewport(&mut self, index: usize, scroll_notches: f32, cursor: Vector2) {
+        let viewport = &mut self.viewports[index];
+        let toward = self.navigation.zoom_to_mouse_position.then(|| {
+            let ray = viewport
+                .camera
+                .get_ray_through_screen_point(&cursor, &viewport.drawing_stuff.drawing_region_size);
+            self.scene
+                .ray_cast(&ray, &mut rand::thread_rng())
+                .unwrap_or_else(|| viewport.camera.get_point_at_orbit_depth(&ray))
+        });
+        viewport.camera.zoom(scroll_notches, toward);
+    }
+
+    /// The camera turns in the viewport the drag started in, around the selection if the
+    /// preference is set.
+    pub fn handle_shortcut_viewport_camera_rotate(&mut self, rects: &[eframe::egui::Rect]) {
+        if self
+            .keymap
+            .is_active(Action::ViewportOrbit, &self.input_state)
+        {
+            if self.viewports.iter().any(|v| v.turntable_origin.is_some()) {
+                return;
+            }
+            let origin = self.input_state.mouse_state.current_position;
+            let index = rects
+                .iter()
+                .position(|rect| origin.is_some_and(|p| rect.contains(p)));
+            if let Some(index) = index {
+                let viewport = &mut self.viewports[index];
+                viewport.turntable_origin = origin;
+                self.active_viewport = index;
+                if let (true, Some(center)) = (
+                    self.navigation.orbit_around_selection,
+                    self.scene.get_selection_center(),
+                ) {
+                    viewport.camera.set_orbit_pivot(center);
+                }
+            }
+            return;
+        }
+
+        for viewport in &mut self.viewports {
+            if viewport.turntable_origin.is_some() {
+                viewport.camera.solidify_view_info();
+            }
+
+            viewport.turntable_origin = None;
+        }
+    }
+
+    pub fn handle_shortcut_command_palette(&mut self) {
+        if self
+            .keymap
+            .is_active(Action::CommandPalette, &self.input_state)
+        {
+            self.command_palette_state.open();
+        }
+    }
+
+    /// Runs the operators whose shortcuts were pressed, if they can run right now.
+    pub fn handle_shortcuts_operators(&mut self) {
+        for id in self.keymap.get_active_operators(&self.input_state) {
+            let can_run = self
+                .operators
+                .get(id)
+                .is_some_and(|operator| operator.poll(self));
+            if can_run {
+                if let Err(error) = self.run_operator(id, None) {
+                    log::error!("{}", error);
+                }
+            }
+        }
+    }
+
+    /// Flying starts in the viewport under the mouse, and ends with the camera where it got to,
+    /// or back where it started when cancelled.
+    pub fn handle_shortcuts_fly_navigation(&mut self, rects: &[eframe::egui::Rect]) {
+        let input_state = &mut self.input_state;
+        let Some(fly_navigation) = &mut self.fly_navigation else {
+            if self.keymap.is_active(Action::FlyNavigation, input_state) {
+                let hover_pos = input_state.mouse_state.current_position;
+                let index = rects
+                    .iter()
+                    .position(|rect| hover_pos.is_some_and(|p| rect.contains(p)))
+                    .unwrap_or(self.active_viewport);
+                self.active_viewport = index;
+                self.fly_navigation =
+                    Some(FlyNavigation::new(index, &mut self.viewports[index].camera));
+                input_state.capture(ActionContext::Fly);
+            }
             return;
+        };
+
+        let camera = &mut self.viewports[fly_navigation.viewport].camera;
+        if self.keymap.is_active(Action::FlyCancel, input_state) {
+            self.fly_navigation.take().unwrap().cancel(camera);
+            input_state.release();
+        } else if self.keymap.is_active(Action::FlyConfirm, input_state) {
+            self.fly_navigation = None;
+            input_state.release();
+        } else {
+            fly_navigation.update(
+                camera,
+                input_state::fly_navigation_movement(input_state, &self.keymap),
+                input_state.mouse_state.get_delta(),
+                input_state.mouse_state.get_scroll_notches(),
+                input_state.dt,
+            );
         }
+    }
 
-        if self.doing_turntable {
-            self.camera.solidify_view_info();
+    pub fn handle_shortcuts_knife_tool(&mut self) {
+        let input_state = &self.input_state;
+        if self.knife_tool.is_none() {
+            if self.keymap.is_active(Action::KnifeTool, input_state) {
+                self.knife_tool = Some(KnifeTool::new());
+            }
+            return;
         }
 
-        self.doing_turntable = false;
+        if self.keymap.is_active(Action::KnifeCancel, input_state) {
+            self.knife_tool = None;
+        } else if self.keymap.is_active(Action::KnifeConfirm, input_state) {
+            let knife_tool = self.knife_tool.take().unwrap();
+            let camera = &self.viewports[self.active_viewport].camera;
+            if let Some(object) = self.scene.get_active_object_mut() {
+                knife_tool.confirm(&mut object.mesh, camera, &mut rand::thread_rng());
+                self.refresh_meshes_to_draw();
+            }
+        }
     }
 }
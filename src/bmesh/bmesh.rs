@@ -1,99 +1,409 @@
-use crate::math::point::Point3;
+use crate::math::{point::Point3, Float};
 use log::error;
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+    sync::atomic::{AtomicU64, Ordering},
+};
 
 use super::{
     bmesh_edge::{BMeshEdge, BMeshEdgeLookupTable, BMeshEdgeLoop},
     bmesh_face::{BMeshFace, TesselationStrategy},
+    bmesh_normals::BMeshNormals,
     bmesh_vertex::BMeshVertex,
     BMeshTesselation,
 };
 
+/// Shared between all meshes, so that a mesh that replaces another one never ends up with the
+/// same revision.
+static NEXT_REVISION: AtomicU64 = AtomicU64::new(0);
+
+fn next_revision() -> u64 {
+    NEXT_REVISION.fetch_add(1, Ordering::Relaxed)
+}
+
 pub struct BMesh {
-    vertices: Vec<Rc<RefCell<BMeshVertex>>>,
-    edges: Vec<Rc<RefCell<BMeshEdge>>>,
-    faces: Vec<Rc<RefCell<BMeshFace>>>,
+    pub(super) vertices: Vec<Rc<RefCell<BMeshVertex>>>,
+    pub(super) edges: Vec<Rc<RefCell<BMeshEdge>>>,
+    pub(super) faces: Vec<Rc<RefCell<BMeshFace>>>,
+
+    pub(super) edge_lookup_table: BMeshEdgeLookupTable,
+    /// The same vertices as `vertices`, by id.
+    pub(super) vertex_lookup_table: HashMap<u32, Rc<RefCell<BMeshVertex>>>,
+
+    /// Ids of the selected vertices, in the order they were selected. Operators such as merging
+    /// at the first/last selected vertex rely on this order.
+    pub(super) selection_history: Vec<u32>,
+    /// The same ids as `selection_history`, for checking whether a vertex is in it.
+    pub(super) selection_set: HashSet<u32>,
+
+    pub(super) tesselation: BMeshTesselation,
 
-    edge_lookup_table: BMeshEdgeLookupTable,
+    pub(super) normals: BMeshNormals,
+    /// See `get_auto_smooth_angle()`.
+    pub(super) auto_smooth_angle: Option<Float>,
 
-    tesselation: BMeshTesselation,
+    /// Changes whenever the mesh does (see `rebuild_tesselation()`), so that anything derived
+    /// from the mesh can tell whether it is out of date.
+    revision: u64,
 }
 
 impl BMesh {
-    /// Assumes points go around the n-gon in a CCW fashion.
-    fn from_ngon(rng: &mut impl rand::Rng, points: Vec<&Point3>) -> Self {
-        let tesselation_strategy = match points.len() {
-            3 => TesselationStrategy::Triangle,
-            4 => TesselationStrategy::Quad,
-            n => {
-                error!("{}gon tesselation not implemented!", n);
+    /// Builds a mesh out of a list of points and polygons indexing into it. Each polygon is
+    /// assumed to go around its face in a CCW fashion. Points that coincide are kept as separate
+    /// vertices, which is what we get from formats like STL (see `merge_vertices_by_distance()`).
+    pub fn from_polygons(
+        rng: &mut impl rand::Rng,
+        points: &[Point3],
+        polygons: &[Vec<usize>],
+    ) -> Self {
+        let vertices: Vec<Rc<RefCell<BMeshVertex>>> = points
+            .iter()
+            .map(|p| BMeshVertex::create_from_position(*p, rng))
+            .collect();
+        let vertex_lookup_table = vertices
+            .iter()
+            .map(|v| (v.as_ref().borrow().get_id(), v.clone()))
+            .collect();
+
+        let mut mesh = Self {
+            vertices,
+            edges: Vec::new(),
+            faces: Vec::new(),
+            edge_lookup_table: BMeshEdgeLookupTable::new(),
+            vertex_lookup_table,
+            selection_history: Vec::new(),
+            selection_set: HashSet::new(),
+            tesselation: BMeshFace::aggregate_tesselations(&Vec::new()),
+            normals: BMeshNormals::default(),
+            auto_smooth_angle: None,
+            revision: next_revision(),
+        };
+
+        for polygon in polygons {
+            let face_vertices: Vec<Rc<RefCell<BMeshVertex>>> =
+                polygon.iter().map(|i| mesh.vertices[*i].clone()).collect();
+            mesh.create_face(face_vertices);
+        }
+        mesh.rebuild_tesselation();
+
+        mesh
+    }
+
+    /// The opposite of `from_polygons()`: the positions of all vertices, and the faces as lists
+    /// of indices into them. Edges that are not the side of any face are left out.
+    pub fn to_polygons(&self) -> (Vec<Point3>, Vec<Vec<usize>>) {
+        let mut index_of: HashMap<u32, usize> = HashMap::with_capacity(self.vertices.len());
+        let points: Vec<Point3> = self
+            .vertices
+            .iter()
+            .enumerate()
+            .map(|(i, v)| {
+                let v = v.as_ref().borrow();
+                index_of.insert(v.get_id(), i);
+                v.get_position()
+            })
+            .collect();
+
+        let polygons: Vec<Vec<usize>> = self
+            .faces
+            .iter()
+            .map(|f| {
+                f.as_ref()
+                    .borrow()
+                    .get_vertex_ids()
+                    .iter()
+                    .map(|id| index_of[id])
+                    .collect()
+            })
+            .collect();
+
+        (points, polygons)
+    }
+
+    pub fn get_revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn get_vertex(&self, vertex_id: u32) -> Option<Rc<RefCell<BMeshVertex>>> {
+        self.vertex_lookup_table.get(&vertex_id).cloned()
+    }
+
+    pub fn get_vertices(&self) -> &Vec<Rc<RefCell<BMeshVertex>>> {
+        &self.vertices
+    }
+
+    pub fn get_edges(&self) -> &Vec<Rc<RefCell<BMeshEdge>>> {
+        &self.edges
+    }
+
+    pub fn get_faces(&self) -> &Vec<Rc<RefCell<BMeshFace>>> {
+        &self.faces
+    }
+
+    /// Adds a vertex that isn't connected to anything yet.
+    pub(super) fn add_vertex(&mut self, vertex: Rc<RefCell<BMeshVertex>>) {
+        let vertex_id = vertex.as_ref().borrow().get_id();
+        self.vertex_lookup_table.insert(vertex_id, vertex.clone());
+        self.vertices.push(vertex);
+    }
+
+    /// Returns the edge between the two vertices, creating it if there is none yet.
+    pub(super) fn get_or_create_edge(
+        &mut self,
+        v0: Rc<RefCell<BMeshVertex>>,
+        v1: Rc<RefCell<BMeshVertex>>,
+    ) -> Rc<RefCell<BMeshEdge>> {
+        let v0_id = v0.as_ref().borrow().get_id();
+        let v1_id = v1.as_ref().borrow().get_id();
+        if let Some(edge) = self.edge_lookup_table.get_edge(v0_id, v1_id) {
+            return edge;
+        }
+
+        let edge = BMeshEdge::create(v0, v1, &mut self.edge_lookup_table);
+        self.edges.push(edge.clone());
+        edge
+    }
+
+    /// Creates a face going around the vertices, along with any of its edges that don't exist
+    /// yet. The tesselation of the whole mesh is not updated (see `rebuild_tesselation()`).
+    pub(super) fn create_face(
+        &mut self,
+        face_vertices: Vec<Rc<RefCell<BMeshVertex>>>,
+    ) -> Rc<RefCell<BMeshFace>> {
+        let tesselation_strategy = match TesselationStrategy::from_vertex_count(face_vertices.len())
+        {
+            Some(strategy) => strategy,
+            None => {
+                error!("{}gon tesselation not implemented!", face_vertices.len());
                 panic!();
             }
         };
 
-        // Create vertices
-        let vertices: Vec<Rc<RefCell<BMeshVertex>>> = points
+        for i in 0..face_vertices.len() {
+            let v0 = face_vertices[i].clone();
+            let v1 = face_vertices[(i + 1) % face_vertices.len()].clone();
+            self.get_or_create_edge(v0, v1);
+        }
+
+        let face = BMeshFace::create(BMeshEdgeLoop::new(face_vertices), tesselation_strategy);
+        self.faces.push(face.clone());
+        face
+    }
+
+    /// Public operators call this once they are done changing the mesh, which also marks the
+    /// mesh as changed (see `get_revision()`) and updates the normals. In debug builds the mesh
+    /// is validated too.
+    pub(super) fn rebuild_tesselation(&mut self) {
+        self.tesselation = BMeshFace::aggregate_tesselations(&self.faces);
+        self.recalculate_normals();
+        self.revision = next_revision();
+
+        #[cfg(debug_assertions)]
+        self.log_problems();
+    }
+
+    /// All faces that have the edge between the two vertices as one of their sides.
+    pub fn get_faces_of_edge(&self, v0_id: u32, v1_id: u32) -> Vec<Rc<RefCell<BMeshFace>>> {
+        self.faces
             .iter()
-            .map(|p| BMeshVertex::create_from_position((*p).clone(), rng))
-            .collect();
+            .filter(|f| f.as_ref().borrow().has_edge(v0_id, v1_id))
+            .cloned()
+            .collect()
+    }
+
+    /// All faces that have the vertex as one of their corners.
+    pub fn get_faces_of_vertex(&self, vertex_id: u32) -> Vec<Rc<RefCell<BMeshFace>>> {
+        self.faces
+            .iter()
+            .filter(|f| f.as_ref().borrow().has_vertex(vertex_id))
+            .cloned()
+            .collect()
+    }
+}
+
+// Removing elements. Removing an element also removes everything that depends on it, e.g.
+// removing an edge removes the faces it is a side of. None of these update the tesselation.
+impl BMesh {
+    pub(super) fn kill_face(&mut self, face: &Rc<RefCell<BMeshFace>>) {
+        self.faces.retain(|f| !Rc::ptr_eq(f, face));
+    }
+
+    pub(super) fn kill_edge(&mut self, v0_id: u32, v1_id: u32) {
+        for face in self.get_faces_of_edge(v0_id, v1_id) {
+            self.kill_face(&face);
+        }
+
+        self.unlink_edge(v0_id, v1_id);
+    }
 
-        // Create edges
-        let mut edge_lookup_table = BMeshEdgeLookupTable::new();
-        let mut edges = Vec::with_capacity(vertices.len());
-
-        for i in 0..vertices.len() {
-            let v0 = i;
-            let v1 = (i + 1) % vertices.len();
-            let edge = BMeshEdge::create(
-                vertices.get(v0).unwrap().clone(),
-                vertices.get(v1).unwrap().clone(),
-                &mut edge_lookup_table,
-                rng,
-            );
-
-            edges.push(edge);
+    /// Removes the edge without touching the faces using it, for when the faces are about to
+    /// be changed so that they don't anymore.
+    pub(super) fn unlink_edge(&mut self, v0_id: u32, v1_id: u32) {
+        if let Some(edge) = self.edge_lookup_table.remove_edge(v0_id, v1_id) {
+            self.edges.retain(|e| !Rc::ptr_eq(e, &edge));
         }
+    }
 
-        // Create face
-        let face_edge_loop = BMeshEdgeLoop::new(vertices.clone());
-        let face = BMeshFace::create(face_edge_loop, tesselation_strategy);
+    pub(super) fn kill_vertex(&mut self, vertex_id: u32) {
+        for edge in self.edge_lookup_table.get_edges_of_vertex(vertex_id) {
+            let (v0_id, v1_id) = {
+                let edge = edge.as_ref().borrow();
+                (edge.get_v0_id(), edge.get_v1_id())
+            };
+            self.kill_edge(v0_id, v1_id);
+        }
+        // Faces are killed through their edges, but a face could still reference the vertex if
+        // the mesh is inconsistent.
+        for face in self.get_faces_of_vertex(vertex_id) {
+            self.kill_face(&face);
+        }
 
-        let faces = vec![face];
+        self.vertices
+            .retain(|v| v.as_ref().borrow().get_id() != vertex_id);
+        self.vertex_lookup_table.remove(&vertex_id);
+        self.remove_from_selection_history(vertex_id);
+    }
 
-        // Create tesselation
-        let tesselation = BMeshFace::aggregate_tesselations(&faces);
+    /// Removes the vertices that are not an endpoint of any edge.
+    pub(super) fn kill_isolated_vertices(&mut self, vertex_ids: &[u32]) {
+        for vertex_id in vertex_ids {
+            if self
+                .edge_lookup_table
+                .get_edges_of_vertex(*vertex_id)
+                .is_empty()
+            {
+                self.kill_vertex(*vertex_id);
+            }
+        }
+    }
 
-        Self {
-            vertices,
-            edges,
-            faces,
-            edge_lookup_table,
-            tesselation,
+    /// A unit cube centered at the origin.
+    #[rustfmt::skip]
+    pub fn create_cube(rng: &mut impl rand::Rng) -> Self {
+        let points = [
+            Point3::new(-0.5, -0.5, -0.5), // back  bottom left
+            Point3::new( 0.5, -0.5, -0.5), // back  bottom right
+            Point3::new( 0.5,  0.5, -0.5), // back  top    right
+            Point3::new(-0.5,  0.5, -0.5), // back  top    left
+            Point3::new(-0.5, -0.5,  0.5), // front bottom left
+            Point3::new( 0.5, -0.5,  0.5), // front bottom right
+            Point3::new( 0.5,  0.5,  0.5), // front top    right
+            Point3::new(-0.5,  0.5,  0.5), // front top    left
+        ];
+        let polygons = [
+            vec![0, 3, 2, 1], // back
+            vec![4, 5, 6, 7], // front
+            vec![0, 4, 7, 3], // left
+            vec![1, 2, 6, 5], // right
+            vec![0, 1, 5, 4], // bottom
+            vec![3, 7, 6, 2], // top
+        ];
+
+        Self::from_polygons(rng, &points, &polygons)
+    }
+}
+
+// Selection
+impl BMesh {
+    /// Selecting a vertex that is already selected makes it the last selected one.
+    pub fn select_vertex(&mut self, vertex_id: u32) {
+        if let Some(vertex) = self.get_vertex(vertex_id) {
+            vertex.as_ref().borrow_mut().set_selected(true);
+            self.remove_from_selection_history(vertex_id);
+            self.selection_history.push(vertex_id);
+            self.selection_set.insert(vertex_id);
         }
     }
 
-    //    #[rustfmt::skip]
-    //    pub fn create_cube() -> Self {
-    //        let p0 = Point3::new(-0.5, -0.5, -0.5); // front bottom left
-    //        let p1 = Point3::new(-0.5, -0.5,  0.5); // back  bottom left
-    //        let p2 = Point3::new( 0.5, -0.5,  0.5); // back  bottom right
-    //        let p3 = Point3::new( 0.5, -0.5, -0.5); // fron  bottom right
-    //
-    //        let p4 = Point3::new(-0.5,  0.5,  0.5); // front top left
-    //        let p5 = Point3::new( 0.5,  0.5,  0.5); // front top right
-    //        let p6 = Point3::new( 0.5,  0.5, -0.5); // back  top right
-    //        let p7 = Point3::new(-0.5,  0.5,  0.5); // back  top left
-    //
-    //        let p0_vertex = Rc::new(RefCell::new(BMeshVertex::new_from_position(p0)));
-    //        let p1_vertex = Rc::new(RefCell::new(BMeshVertex::new_from_position(p1)));
-    //        let p2_vertex = Rc::new(RefCell::new(BMeshVertex::new_from_position(p2)));
-    //        let p3_vertex = Rc::new(RefCell::new(BMeshVertex::new_from_position(p3)));
-    //
-    //        let p4_vertex = Rc::new(RefCell::new(BMeshVertex::new_from_position(p4)));
-    //        let p5_vertex = Rc::new(RefCell::new(BMeshVertex::new_from_position(p5)));
-    //        let p6_vertex = Rc::new(RefCell::new(BMeshVertex::new_from_position(p6)));
-    //        let p7_vertex = Rc::new(RefCell::new(BMeshVertex::new_from_position(p7)));
-    //
-    //        todo!()
-    //    }
+    pub fn deselect_vertex(&mut self, vertex_id: u32) {
+        if let Some(vertex) = self.get_vertex(vertex_id) {
+            vertex.as_ref().borrow_mut().set_selected(false);
+        }
+        self.remove_from_selection_history(vertex_id);
+    }
+
+    /// Vertices that are already selected keep their place in the selection order.
+    pub fn select_all(&mut self) {
+        let ids: Vec<u32> = self
+            .vertices
+            .iter()
+            .map(|v| v.as_ref().borrow().get_id())
+            .filter(|id| !self.selection_set.contains(id))
+            .collect();
+        for id in ids {
+            self.select_vertex(id);
+        }
+    }
+
+    pub fn deselect_all(&mut self) {
+        for vertex in &self.vertices {
+            vertex.as_ref().borrow_mut().set_selected(false);
+        }
+        self.selection_history.clear();
+        self.selection_set.clear();
+    }
+
+    /// Only goes through the history if the vertex is in it.
+    fn remove_from_selection_history(&mut self, vertex_id: u32) {
+        if self.selection_set.remove(&vertex_id) {
+            self.selection_history.retain(|id| *id != vertex_id);
+        }
+    }
+
+    /// The selected vertices, in the order they were selected.
+    pub fn get_selected_vertices(&self) -> Vec<Rc<RefCell<BMeshVertex>>> {
+        self.selection_history
+            .iter()
+            .filter_map(|id| self.get_vertex(*id))
+            .collect()
+    }
+
+    /// The vertex selected last, which some operators treat specially.
+    pub fn get_active_vertex(&self) -> Option<Rc<RefCell<BMeshVertex>>> {
+        self.selection_history
+            .last()
+            .and_then(|id| self.get_vertex(*id))
+    }
+
+    /// A selected face of the active vertex, if there is one.
+    pub fn get_active_face(&self) -> Option<Rc<RefCell<BMeshFace>>> {
+        let active_id = *self.selection_history.last()?;
+        self.get_faces_of_vertex(active_id).into_iter().find(|f| {
+            f.as_ref()
+                .borrow()
+                .get_vertices()
+                .iter()
+                .all(|v| v.as_ref().borrow().is_selected())
+        })
+    }
+
+    /// Edges count as selected when both of their vertices are.
+    pub fn get_selected_edges(&self) -> Vec<Rc<RefCell<BMeshEdge>>> {
+        self.edges
+            .iter()
+            .filter(|e| {
+                let e = e.as_ref().borrow();
+                e.get_v0().as_ref().borrow().is_selected()
+                    && e.get_v1().as_ref().borrow().is_selected()
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Faces count as selected when all of their vertices are.
+    pub fn get_selected_faces(&self) -> Vec<Rc<RefCell<BMeshFace>>> {
+        self.faces
+            .iter()
+            .filter(|f| {
+                f.as_ref()
+                    .borrow()
+                    .get_vertices()
+                    .iter()
+                    .all(|v| v.as_ref().borrow().is_selected())
+            })
+            .cloned()
+            .collect()
+    }
 }
@@ -0,0 +1,1018 @@
+//! Boolean operations (union, difference and intersection) between two closed meshes.
+//!
+//! Each face of either mesh is cut along the curves where it intersects the other mesh. Every
+//! piece then lies either inside or outside of the other mesh (or on its surface, where faces
+//! of the two meshes overlap), which decides whether the operation keeps it. The kept pieces of
+//! both meshes share the points along the intersection curves, which is what stitches them
+//! back together into one mesh.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::math::{
+    point::Point3,
+    vector::{Vector2, Vector3},
+    Float,
+};
+
+use super::{
+    bmesh::BMesh,
+    bmesh_face::{ear_clip, newell_normal},
+    spatial_hash::SpatialHash,
+};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum BooleanOperation {
+    Union,
+    /// The first mesh with the second one cut out of it.
+    Difference,
+    Intersect,
+}
+
+impl BooleanOperation {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Self::Union => "Union",
+            Self::Difference => "Difference",
+            Self::Intersect => "Intersect",
+        }
+    }
+}
+
+/// Where a piece of one mesh is relative to the other mesh.
+#[derive(Clone, Copy, PartialEq)]
+enum Location {
+    Inside,
+    Outside,
+    /// On a face of the other mesh that faces the same way.
+    CoplanarSame,
+    /// On a face of the other mesh that faces the other way.
+    CoplanarOpposite,
+}
+
+/// The points of both meshes and of the intersections between them. Points closer together
+/// than `tolerance` are treated as the same point, so that pieces cut from neighbouring faces
+/// end up sharing the points along the cut even if they were computed slightly differently.
+struct PointPool {
+    positions: Vec<Point3>,
+    spatial_hash: SpatialHash,
+    tolerance: Float,
+}
+
+impl PointPool {
+    fn new(tolerance: Float) -> Self {
+        Self {
+            positions: Vec::new(),
+            spatial_hash: SpatialHash::new(tolerance),
+            tolerance,
+        }
+    }
+
+    /// Returns the index of the point, adding it if there is no point close enough yet.
+    fn insert(&mut self, position: Point3) -> usize {
+        let existing = self
+            .spatial_hash
+            .get_candidates(&position)
+            .into_iter()
+            .find(|i| self.positions[*i].distance(&position) <= self.tolerance);
+        if let Some(i) = existing {
+            return i;
+        }
+
+        let i = self.positions.len();
+        self.positions.push(position);
+        self.spatial_hash.insert(i, &position);
+        i
+    }
+
+    fn get_all(&self, ids: &[usize]) -> Vec<Point3> {
+        ids.iter().map(|i| self.positions[*i]).collect()
+    }
+}
+
+/// One of the two meshes going into the operation, with its faces indexing into the
+/// `PointPool`.
+struct BooleanInput {
+    faces: Vec<Vec<usize>>,
+    /// The tesselation of each face.
+    triangles: Vec<Vec<[usize; 3]>>,
+    /// Normalized.
+    normals: Vec<Vector3>,
+    bounds: Vec<(Point3, Point3)>,
+}
+
+impl BooleanInput {
+    fn new(mesh: &BMesh, points: &mut PointPool) -> Self {
+        let (positions, polygons) = mesh.to_polygons();
+        let ids: Vec<usize> = positions.iter().map(|p| points.insert(*p)).collect();
+
+        let faces: Vec<Vec<usize>> = polygons
+            .iter()
+            .map(|polygon| polygon.iter().map(|i| ids[*i]).collect())
+            .collect();
+        let triangles = mesh
+            .get_faces()
+            .iter()
+            .zip(&polygons)
+            .map(|(face, polygon)| {
+                face.as_ref()
+                    .borrow()
+                    .get_triangle_indices()
+                    .iter()
+                    .map(|[a, b, c]| [ids[polygon[*a]], ids[polygon[*b]], ids[polygon[*c]]])
+                    .collect()
+            })
+            .collect();
+        let normals = faces
+            .iter()
+            .map(|face| newell_normal(&points.get_all(face)).normalize())
+            .collect();
+        let bounds = faces
+            .iter()
+            .map(|face| get_bounds(&points.get_all(face)))
+            .collect();
+
+        Self {
+            faces,
+            triangles,
+            normals,
+            bounds,
+        }
+    }
+}
+
+impl BMesh {
+    /// Combines this mesh with `other`. Both are expected to be closed, with their faces facing
+    /// outwards; otherwise what is inside of them isn't well defined and the result won't be
+    /// either.
+    pub fn boolean(
+        &self,
+        other: &BMesh,
+        operation: BooleanOperation,
+        rng: &mut impl rand::Rng,
+    ) -> BMesh {
+        let tolerance = get_tolerance(self, other);
+        let mut points = PointPool::new(tolerance);
+        let inputs = [
+            BooleanInput::new(self, &mut points),
+            BooleanInput::new(other, &mut points),
+        ];
+        let first_intersection_point = points.positions.len();
+
+        // The segments each face needs to be cut along, and the faces of the other mesh lying
+        // in the same plane.
+        let mut segments: [Vec<Vec<(usize, usize)>>; 2] = [
+            vec![Vec::new(); inputs[0].faces.len()],
+            vec![Vec::new(); inputs[1].faces.len()],
+        ];
+        let mut coplanar: [Vec<Vec<usize>>; 2] = [
+            vec![Vec::new(); inputs[0].faces.len()],
+            vec![Vec::new(); inputs[1].faces.len()],
+        ];
+
+        for a in 0..inputs[0].faces.len() {
+            for b in 0..inputs[1].faces.len() {
+                if !do_bounds_overlap(&inputs[0].bounds[a], &inputs[1].bounds[b], tolerance) {
+                    continue;
+                }
+
+                if are_coplanar(&inputs[0], a, &inputs[1], b, &points) {
+                    // Where the faces overlap, each one gets cut along the outline of the
+                    // other, so that the overlap becomes a piece of its own.
+                    coplanar[0][a].push(b);
+                    coplanar[1][b].push(a);
+                    let normal = inputs[0].normals[a];
+                    let cuts_of_a = clip_outline(
+                        &inputs[1].faces[b],
+                        &inputs[0].faces[a],
+                        normal,
+                        &mut points,
+                    );
+                    let cuts_of_b = clip_outline(
+                        &inputs[0].faces[a],
+                        &inputs[1].faces[b],
+                        normal,
+                        &mut points,
+                    );
+                    segments[0][a].extend(cuts_of_a);
+                    segments[1][b].extend(cuts_of_b);
+                    continue;
+                }
+
+                for triangle_a in &inputs[0].triangles[a] {
+                    for triangle_b in &inputs[1].triangles[b] {
+                        let triangle_a = triangle_a.map(|i| points.positions[i]);
+                        let triangle_b = triangle_b.map(|i| points.positions[i]);
+                        if let Some((p, q)) =
+                            intersect_triangles(&triangle_a, &triangle_b, tolerance)
+                        {
+                            let (p, q) = (points.insert(p), points.insert(q));
+                            if p != q {
+                                segments[0][a].push((p, q));
+                                segments[1][b].push((p, q));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut kept: Vec<Vec<usize>> = Vec::new();
+        for (side, input) in inputs.iter().enumerate() {
+            let other_input = &inputs[1 - side];
+            for face in 0..input.faces.len() {
+                let pieces = if segments[side][face].is_empty() {
+                    vec![input.faces[face].clone()]
+                } else {
+                    split_face(
+                        &input.faces[face],
+                        &segments[side][face],
+                        input.normals[face],
+                        &points,
+                    )
+                };
+
+                for mut piece in pieces {
+                    let location = classify(
+                        &piece,
+                        input.normals[face],
+                        &coplanar[side][face],
+                        other_input,
+                        &points,
+                    );
+                    if let Some(is_reversed) = get_kept_orientation(operation, side == 0, location)
+                    {
+                        if is_reversed {
+                            piece.reverse();
+                        }
+                        kept.push(piece);
+                    }
+                }
+            }
+        }
+
+        // Pieces of faces that weren't cut themselves may still have cut points along their
+        // sides (e.g. where a face of the other mesh overlaps a neighbour), which they need as
+        // corners to connect to the pieces next to them.
+        let mut candidates: HashSet<usize> =
+            (first_intersection_point..points.positions.len()).collect();
+        for (side, input) in inputs.iter().enumerate() {
+            for (face, coplanar_faces) in input.faces.iter().zip(&coplanar[side]) {
+                if !coplanar_faces.is_empty() {
+                    candidates.extend(face);
+                }
+            }
+        }
+        let candidates: Vec<usize> = candidates.into_iter().collect();
+        for piece in kept.iter_mut() {
+            *piece = insert_points_on_sides(piece, &candidates, &points);
+        }
+
+        // Only the points that are used end up in the mesh.
+        let mut index_of: HashMap<usize, usize> = HashMap::new();
+        let mut result_points: Vec<Point3> = Vec::new();
+        let mut result_polygons: Vec<Vec<usize>> = Vec::new();
+        for piece in kept {
+            let mut polygon: Vec<usize> = Vec::with_capacity(piece.len());
+            for id in piece {
+                let index = *index_of.entry(id).or_insert_with(|| {
+                    result_points.push(points.positions[id]);
+                    result_points.len() - 1
+                });
+                if polygon.last() != Some(&index) {
+                    polygon.push(index);
+                }
+            }
+            if polygon.len() > 1 && polygon.first() == polygon.last() {
+                polygon.pop();
+            }
+            if polygon.len() >= 3 {
+                result_polygons.push(polygon);
+            }
+        }
+
+        BMesh::from_polygons(rng, &result_points, &result_polygons)
+    }
+}
+
+/// Whether a piece ends up in the result, and if so whether it needs to be turned around.
+fn get_kept_orientation(
+    operation: BooleanOperation,
+    is_first: bool,
+    location: Location,
+) -> Option<bool> {
+    use BooleanOperation::*;
+    use Location::*;
+
+    // Where the meshes have overlapping faces, only the face of the first mesh is kept.
+    match (operation, is_first, location) {
+        (Union, true, Outside | CoplanarSame) => Some(false),
+        (Union, false, Outside) => Some(false),
+        (Intersect, true, Inside | CoplanarSame) => Some(false),
+        (Intersect, false, Inside) => Some(false),
+        (Difference, true, Outside | CoplanarOpposite) => Some(false),
+        // The inside of the second mesh becomes the surface of the hole, facing into it.
+        (Difference, false, Inside) => Some(true),
+        _ => None,
+    }
+}
+
+/// What counts as "the same point", relative to the size of the meshes.
+fn get_tolerance(a: &BMesh, b: &BMesh) -> Float {
+    let positions: Vec<Point3> = a
+        .get_vertices()
+        .iter()
+        .chain(b.get_vertices())
+        .map(|v| v.as_ref().borrow().get_position())
+        .collect();
+    if positions.is_empty() {
+        return 1e-6;
+    }
+
+    let (min, max) = get_bounds(&positions);
+    Float::max(min.distance(&max) * 1e-5, 1e-6)
+}
+
+fn get_bounds(positions: &[Point3]) -> (Point3, Point3) {
+    let mut min = positions[0];
+    let mut max = positions[0];
+    for p in positions {
+        min = Point3::new(min.x().min(p.x()), min.y().min(p.y()), min.z().min(p.z()));
+        max = Point3::new(max.x().max(p.x()), max.y().max(p.y()), max.z().max(p.z()));
+    }
+    (min, max)
+}
+
+fn do_bounds_overlap(a: &(Point3, Point3), b: &(Point3, Point3), tolerance: Float) -> bool {
+    a.0.x() <= b.1.x() + tolerance
+        && b.0.x() <= a.1.x() + tolerance
+        && a.0.y() <= b.1.y() + tolerance
+        && b.0.y() <= a.1.y() + tolerance
+        && a.0.z() <= b.1.z() + tolerance
+        && b.0.z() <= a.1.z() + tolerance
+}
+
+fn are_coplanar(
+    a: &BooleanInput,
+    a_face: usize,
+    b: &BooleanInput,
+    b_face: usize,
+    points: &PointPool,
+) -> bool {
+    let (a_normal, b_normal) = (a.normals[a_face], b.normals[b_face]);
+    if Vector3::dot(a_normal, b_normal).abs() < 1.0 - 1e-4 {
+        return false;
+    }
+
+    let origin = points.positions[a.faces[a_face][0]];
+    b.faces[b_face]
+        .iter()
+        .all(|i| Vector3::dot(points.positions[*i] - origin, a_normal).abs() <= points.tolerance)
+}
+
+/// The segment where the two triangles cross, if they do. Triangles that only touch at a
+/// point, or that lie in the same plane, don't count.
+fn intersect_triangles(
+    a: &[Point3; 3],
+    b: &[Point3; 3],
+    tolerance: Float,
+) -> Option<(Point3, Point3)> {
+    let a_normal = Vector3::cross(a[1] - a[0], a[2] - a[0]).normalize();
+    let b_normal = Vector3::cross(b[1] - b[0], b[2] - b[0]).normalize();
+    let direction = Vector3::cross(a_normal, b_normal);
+    if direction.length() < 1e-6 {
+        return None;
+    }
+
+    // The part of each triangle lying in the plane of the other, which is a segment along the
+    // line where the planes meet.
+    let a_on_b = clip_to_plane(a, b[0], b_normal, tolerance)?;
+    let b_on_a = clip_to_plane(b, a[0], a_normal, tolerance)?;
+
+    let along = |p: &Point3| Vector3::dot(p.to_vec3(), direction);
+    let extent = |clipped: &Vec<Point3>| {
+        let mut sorted = clipped.clone();
+        sorted.sort_by(|p, q| along(p).partial_cmp(&along(q)).unwrap());
+        (sorted[0], sorted[sorted.len() - 1])
+    };
+    let (a_start, a_end) = extent(&a_on_b);
+    let (b_start, b_end) = extent(&b_on_a);
+
+    // Taking the actual end points (rather than points computed along the line) keeps them
+    // the same as what the neighbouring triangles get.
+    let start = if along(&a_start) >= along(&b_start) {
+        a_start
+    } else {
+        b_start
+    };
+    let end = if along(&a_end) <= along(&b_end) {
+        a_end
+    } else {
+        b_end
+    };
+    if along(&end) - along(&start) <= tolerance {
+        return None;
+    }
+
+    Some((start, end))
+}
+
+/// The points of the triangle lying on the plane: corners on it, and where sides cross it.
+fn clip_to_plane(
+    triangle: &[Point3; 3],
+    plane_point: Point3,
+    plane_normal: Vector3,
+    tolerance: Float,
+) -> Option<Vec<Point3>> {
+    let distances = triangle.map(|p| Vector3::dot(p - plane_point, plane_normal));
+    if distances.iter().all(|d| *d > tolerance) || distances.iter().all(|d| *d < -tolerance) {
+        return None;
+    }
+
+    let mut clipped = Vec::new();
+    for i in 0..3 {
+        let j = (i + 1) % 3;
+        let (di, dj) = (distances[i], distances[j]);
+        if di.abs() <= tolerance {
+            clipped.push(triangle[i]);
+        }
+        if (di > tolerance && dj < -tolerance) || (di < -tolerance && dj > tolerance) {
+            clipped.push(triangle[i].lerp(&triangle[j], di / (di - dj)));
+        }
+    }
+
+    if clipped.is_empty() {
+        None
+    } else {
+        Some(clipped)
+    }
+}
+
+/// The parts of the sides of `cutter` that lie inside `target`, for two faces in the same
+/// plane.
+fn clip_outline(
+    cutter: &[usize],
+    target: &[usize],
+    normal: Vector3,
+    points: &mut PointPool,
+) -> Vec<(usize, usize)> {
+    let target_positions = points.get_all(target);
+    let target_projected: Vec<Vector2> = target_positions
+        .iter()
+        .map(|p| project_to_plane(p, normal))
+        .collect();
+
+    let mut segments = Vec::new();
+    for i in 0..cutter.len() {
+        let p = points.positions[cutter[i]];
+        let q = points.positions[cutter[(i + 1) % cutter.len()]];
+        let (p_2d, q_2d) = (project_to_plane(&p, normal), project_to_plane(&q, normal));
+
+        // Where the side crosses the outline of the target splits it into parts that are
+        // either entirely inside or entirely outside.
+        let mut ts: Vec<Float> = vec![0.0, 1.0];
+        for j in 0..target_projected.len() {
+            let r = &target_projected[j];
+            let s = &target_projected[(j + 1) % target_projected.len()];
+            if let Some(t) = intersect_segments_2d(&p_2d, &q_2d, r, s) {
+                ts.push(t);
+            }
+        }
+        ts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        for pair in ts.windows(2) {
+            let (start, end) = (p.lerp(&q, pair[0]), p.lerp(&q, pair[1]));
+            let middle = start.lerp(&end, 0.5);
+            if is_on_outline(&middle, &target_positions, points.tolerance)
+                || !is_inside_polygon(&project_to_plane(&middle, normal), &target_projected)
+            {
+                continue;
+            }
+
+            let (start, end) = (points.insert(start), points.insert(end));
+            if start != end {
+                segments.push((start, end));
+            }
+        }
+    }
+
+    segments
+}
+
+/// Cuts the face along the segments, which are expected to lie on it. Returns the pieces,
+/// going around the same way as the face.
+fn split_face(
+    face: &[usize],
+    segments: &[(usize, usize)],
+    normal: Vector3,
+    points: &PointPool,
+) -> Vec<Vec<usize>> {
+    let tolerance = points.tolerance;
+    let position = |i: usize| points.positions[i];
+
+    // The points lying on the sides of the face become corners of it, so that the pieces and
+    // the neighbouring faces share them.
+    let segment_points: Vec<usize> = {
+        let mut unique: Vec<usize> = segments.iter().flat_map(|(a, b)| [*a, *b]).collect();
+        unique.sort();
+        unique.dedup();
+        unique
+    };
+    let outline = insert_points_on_sides(face, &segment_points, points);
+    let outline_positions = points.get_all(&outline);
+
+    // Segments running along the outline don't cut anything.
+    let mut neighbours: HashMap<usize, Vec<usize>> = HashMap::new();
+    let mut unique_segments: HashSet<(usize, usize)> = HashSet::new();
+    for (a, b) in segments {
+        let key = (usize::min(*a, *b), usize::max(*a, *b));
+        if a == b || !unique_segments.insert(key) {
+            continue;
+        }
+        let middle = position(*a).lerp(&position(*b), 0.5);
+        if is_on_outline(&middle, &outline_positions, tolerance) {
+            continue;
+        }
+        neighbours.entry(*a).or_default().push(*b);
+        neighbours.entry(*b).or_default().push(*a);
+    }
+
+    // Break the segments up into chains, which end where they meet the outline or branch.
+    let on_outline: HashSet<usize> = outline.iter().copied().collect();
+    let is_stop = |i: &usize| on_outline.contains(i) || neighbours[i].len() != 2;
+    let edge_key = |a: usize, b: usize| (usize::min(a, b), usize::max(a, b));
+    let mut visited: HashSet<(usize, usize)> = HashSet::new();
+    let mut chains: Vec<Vec<usize>> = Vec::new();
+
+    let mut stops: Vec<usize> = neighbours.keys().copied().filter(is_stop).collect();
+    stops.sort();
+    for start in stops {
+        for first_step in neighbours[&start].clone() {
+            if !visited.insert(edge_key(start, first_step)) {
+                continue;
+            }
+
+            let mut chain = vec![start];
+            let (mut previous, mut current) = (start, first_step);
+            loop {
+                chain.push(current);
+                if is_stop(&current) {
+                    break;
+                }
+                let next = *neighbours[&current]
+                    .iter()
+                    .find(|n| **n != previous)
+                    .unwrap();
+                if !visited.insert(edge_key(current, next)) {
+                    break;
+                }
+                (previous, current) = (current, next);
+            }
+            chains.push(chain);
+        }
+    }
+
+    // Whatever is left are loops that don't touch the outline or any other segment.
+    let mut loops: Vec<Vec<usize>> = Vec::new();
+    let mut loop_starts: Vec<usize> = neighbours.keys().copied().collect();
+    loop_starts.sort();
+    for start in loop_starts {
+        let mut cycle = vec![start];
+        let mut current = start;
+        while let Some(next) = neighbours[&current]
+            .iter()
+            .copied()
+            .find(|n| !visited.contains(&edge_key(current, *n)))
+        {
+            visited.insert(edge_key(current, next));
+            if next == start {
+                break;
+            }
+            cycle.push(next);
+            current = next;
+        }
+        if cycle.len() >= 3 {
+            loops.push(cycle);
+        }
+    }
+
+    let mut pieces = vec![outline];
+    split_along_chains(&mut pieces, chains, normal, points);
+
+    for cycle in loops {
+        let sample = project_to_plane(&position(cycle[0]), normal);
+        let containing = pieces.iter().position(|piece| {
+            let projected: Vec<Vector2> = piece
+                .iter()
+                .map(|i| project_to_plane(&position(*i), normal))
+                .collect();
+            is_inside_polygon(&sample, &projected)
+        });
+        let Some(containing) = containing else {
+            continue;
+        };
+
+        // A face can't have a hole, so the loop gets connected to the outline of the piece
+        // around it in two places. This cuts the piece into the part inside the loop and two
+        // parts around it.
+        let piece = &pieces[containing];
+        let closest = |candidates: &[usize], to: usize, excluded: Option<usize>| -> usize {
+            *candidates
+                .iter()
+                .filter(|c| Some(**c) != excluded)
+                .min_by(|a, b| {
+                    let da = position(**a).distance(&position(to));
+                    let db = position(**b).distance(&position(to));
+                    da.partial_cmp(&db).unwrap()
+                })
+                .unwrap()
+        };
+        let (outline_a, loop_a_index) = piece
+            .iter()
+            .flat_map(|o| (0..cycle.len()).map(move |l| (*o, l)))
+            .min_by(|(o0, l0), (o1, l1)| {
+                let d0 = position(*o0).distance(&position(cycle[*l0]));
+                let d1 = position(*o1).distance(&position(cycle[*l1]));
+                d0.partial_cmp(&d1).unwrap()
+            })
+            .unwrap();
+        let loop_b_index = (loop_a_index + cycle.len() / 2) % cycle.len();
+        let outline_b = closest(piece, cycle[loop_b_index], Some(outline_a));
+
+        let loop_walk = |from: usize, to: usize| -> Vec<usize> {
+            let mut walked = vec![cycle[from]];
+            let mut i = from;
+            while i != to {
+                i = (i + 1) % cycle.len();
+                walked.push(cycle[i]);
+            }
+            walked
+        };
+        let mut connection = vec![outline_a];
+        connection.extend(loop_walk(loop_a_index, loop_b_index));
+        connection.push(outline_b);
+        let rest_of_loop = loop_walk(loop_b_index, loop_a_index);
+
+        split_along_chains(&mut pieces, vec![connection, rest_of_loop], normal, points);
+    }
+
+    pieces.retain(|piece| piece.len() >= 3);
+    pieces
+}
+
+/// Splits the pieces along the chains, each of which should run through a piece from one of
+/// its corners to another. Chains are tried again after each split, since a chain may only
+/// start on a corner that an earlier split made. Chains that don't fit any piece are dropped.
+fn split_along_chains(
+    pieces: &mut Vec<Vec<usize>>,
+    mut chains: Vec<Vec<usize>>,
+    normal: Vector3,
+    points: &PointPool,
+) {
+    let position = |i: usize| points.positions[i];
+
+    loop {
+        let mut has_split = false;
+        let mut chain_index = 0;
+        while chain_index < chains.len() {
+            let chain = &chains[chain_index];
+            let (first, last) = (chain[0], chain[chain.len() - 1]);
+
+            let found = pieces.iter().enumerate().find_map(|(piece_index, piece)| {
+                let k0 = piece.iter().position(|i| *i == first)?;
+                let k1 = piece.iter().position(|i| *i == last)?;
+                if k0 == k1 {
+                    return None;
+                }
+
+                // The chain has to go through the inside of the piece rather than around it.
+                let piece_positions = points.get_all(piece);
+                let projected: Vec<Vector2> = piece_positions
+                    .iter()
+                    .map(|p| project_to_plane(p, normal))
+                    .collect();
+                let middle = chain.windows(2).find_map(|pair| {
+                    let middle = position(pair[0]).lerp(&position(pair[1]), 0.5);
+                    (!is_on_outline(&middle, &piece_positions, points.tolerance)).then_some(middle)
+                })?;
+                is_inside_polygon(&project_to_plane(&middle, normal), &projected).then_some((
+                    piece_index,
+                    k0,
+                    k1,
+                ))
+            });
+
+            let Some((piece_index, k0, k1)) = found else {
+                chain_index += 1;
+                continue;
+            };
+
+            let chain = chains.remove(chain_index);
+            let piece = pieces.swap_remove(piece_index);
+            let walk = |from: usize, to: usize| -> Vec<usize> {
+                let mut walked = vec![piece[from]];
+                let mut i = from;
+                while i != to {
+                    i = (i + 1) % piece.len();
+                    walked.push(piece[i]);
+                }
+                walked
+            };
+            let inner = &chain[1..chain.len() - 1];
+
+            let mut first_half = walk(k0, k1);
+            first_half.extend(inner.iter().rev());
+            let mut second_half = walk(k1, k0);
+            second_half.extend(inner);
+            pieces.push(first_half);
+            pieces.push(second_half);
+            has_split = true;
+        }
+
+        if !has_split || chains.is_empty() {
+            break;
+        }
+    }
+}
+
+/// Adds the candidate points lying on the sides of the polygon as corners, in order along
+/// each side.
+fn insert_points_on_sides(
+    polygon: &[usize],
+    candidates: &[usize],
+    points: &PointPool,
+) -> Vec<usize> {
+    let mut result = Vec::with_capacity(polygon.len());
+    for i in 0..polygon.len() {
+        let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+        result.push(a);
+
+        let (pa, pb) = (points.positions[a], points.positions[b]);
+        let mut on_side: Vec<(Float, usize)> = candidates
+            .iter()
+            .filter(|c| !polygon.contains(c) && !result.contains(c))
+            .filter_map(|c| {
+                get_parameter_on_segment(&points.positions[*c], &pa, &pb, points.tolerance)
+                    .map(|t| (t, *c))
+            })
+            .collect();
+        on_side.sort_by(|(t0, _), (t1, _)| t0.partial_cmp(t1).unwrap());
+        result.extend(on_side.iter().map(|(_, c)| *c));
+    }
+
+    result
+}
+
+/// Where the point is along the segment from `a` to `b` (between 0 and 1, exclusive), if it is
+/// on the segment.
+fn get_parameter_on_segment(p: &Point3, a: &Point3, b: &Point3, tolerance: Float) -> Option<Float> {
+    let side = *b - *a;
+    let length_squared = Vector3::dot(side, side);
+    if length_squared <= tolerance * tolerance {
+        return None;
+    }
+
+    let t = Vector3::dot(*p - *a, side) / length_squared;
+    let margin = tolerance / length_squared.sqrt();
+    if t <= margin || t >= 1.0 - margin {
+        return None;
+    }
+
+    (a.lerp(b, t).distance(p) <= tolerance).then_some(t)
+}
+
+fn is_on_outline(p: &Point3, outline: &[Point3], tolerance: Float) -> bool {
+    (0..outline.len()).any(|i| {
+        let (a, b) = (&outline[i], &outline[(i + 1) % outline.len()]);
+        a.distance(p) <= tolerance
+            || b.distance(p) <= tolerance
+            || get_parameter_on_segment(p, a, b, tolerance).is_some()
+    })
+}
+
+/// Drops the axis the normal is most aligned with. Only good for tests that don't care about
+/// which way the polygon goes around.
+fn project_to_plane(p: &Point3, normal: Vector3) -> Vector2 {
+    let (ax, ay, az) = (normal.x().abs(), normal.y().abs(), normal.z().abs());
+    if az >= ax && az >= ay {
+        Vector2::new(p.x(), p.y())
+    } else if ax >= ay {
+        Vector2::new(p.y(), p.z())
+    } else {
+        Vector2::new(p.z(), p.x())
+    }
+}
+
+/// Even-odd test, counting how many sides a ray from the point crosses.
+fn is_inside_polygon(p: &Vector2, polygon: &[Vector2]) -> bool {
+    let mut is_inside = false;
+    for i in 0..polygon.len() {
+        let (a, b) = (&polygon[i], &polygon[(i + 1) % polygon.len()]);
+        if (a.y() > p.y()) != (b.y() > p.y()) {
+            let x = a.x() + (p.y() - a.y()) / (b.y() - a.y()) * (b.x() - a.x());
+            if p.x() < x {
+                is_inside = !is_inside;
+            }
+        }
+    }
+    is_inside
+}
+
+/// Where the segment from `p` to `q` crosses the one from `r` to `s`, as a parameter along the
+/// first one. Parallel segments don't cross.
+fn intersect_segments_2d(p: &Vector2, q: &Vector2, r: &Vector2, s: &Vector2) -> Option<Float> {
+    let cross = |u: (Float, Float), v: (Float, Float)| u.0 * v.1 - u.1 * v.0;
+    let d1 = (q.x() - p.x(), q.y() - p.y());
+    let d2 = (s.x() - r.x(), s.y() - r.y());
+    let denominator = cross(d1, d2);
+    if denominator.abs() < Float::EPSILON {
+        return None;
+    }
+
+    let offset = (r.x() - p.x(), r.y() - p.y());
+    let t = cross(offset, d2) / denominator;
+    let u = cross(offset, d1) / denominator;
+    ((0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u)).then_some(t)
+}
+
+fn classify(
+    piece: &[usize],
+    normal: Vector3,
+    coplanar_faces: &[usize],
+    other: &BooleanInput,
+    points: &PointPool,
+) -> Location {
+    // The center of the largest triangle of the piece is safely inside it, even if the piece is
+    // concave.
+    let positions = points.get_all(piece);
+    let sample = ear_clip(&positions)
+        .chunks_exact(3)
+        .map(|t| [positions[t[0]], positions[t[1]], positions[t[2]]])
+        .max_by(|t0, t1| {
+            let area = |t: &[Point3; 3]| Vector3::cross(t[1] - t[0], t[2] - t[0]).length();
+            area(t0).partial_cmp(&area(t1)).unwrap()
+        })
+        .map(|t| Point3::centroid(&t))
+        .unwrap_or_else(|| Point3::centroid(&positions));
+
+    for face in coplanar_faces {
+        let outline = points.get_all(&other.faces[*face]);
+        let projected: Vec<Vector2> = outline
+            .iter()
+            .map(|p| project_to_plane(p, normal))
+            .collect();
+        if is_inside_polygon(&project_to_plane(&sample, normal), &projected) {
+            return if Vector3::dot(normal, other.normals[*face]) > 0.0 {
+                Location::CoplanarSame
+            } else {
+                Location::CoplanarOpposite
+            };
+        }
+    }
+
+    if get_winding_number(&sample, other, points) > 0.5 {
+        Location::Inside
+    } else {
+        Location::Outside
+    }
+}
+
+/// How many times the mesh wraps around the point: 1 inside a closed mesh, 0 outside. Adding
+/// up the solid angles of the triangles doesn't depend on picking a ray direction that avoids
+/// edges and corners, unlike counting ray crossings.
+fn get_winding_number(p: &Point3, mesh: &BooleanInput, points: &PointPool) -> Float {
+    let mut total_solid_angle = 0.0;
+    for triangle in mesh.triangles.iter().flatten() {
+        let [a, b, c] = triangle.map(|i| points.positions[i] - *p);
+        let (la, lb, lc) = (a.length(), b.length(), c.length());
+        // Van Oosterom and Strackee's formula for the solid angle of a triangle.
+        let numerator = Vector3::dot(a, Vector3::cross(b, c));
+        let denominator = la * lb * lc
+            + Vector3::dot(a, b) * lc
+            + Vector3::dot(a, c) * lb
+            + Vector3::dot(b, c) * la;
+        total_solid_angle += 2.0 * Float::atan2(numerator, denominator);
+    }
+
+    total_solid_angle / (4.0 * std::f32::consts::PI)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+
+    /// An axis-aligned box between the two corners, laid out like `BMesh::create_cube()`.
+    fn create_box(min: [Float; 3], max: [Float; 3]) -> BMesh {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let corner = |x: usize, y: usize, z: usize| {
+            Point3::new([min, max][x][0], [min, max][y][1], [min, max][z][2])
+        };
+        let points = [
+            corner(0, 0, 0),
+            corner(1, 0, 0),
+            corner(1, 1, 0),
+            corner(0, 1, 0),
+            corner(0, 0, 1),
+            corner(1, 0, 1),
+            corner(1, 1, 1),
+            corner(0, 1, 1),
+        ];
+        let polygons = [
+            vec![0, 3, 2, 1],
+            vec![4, 5, 6, 7],
+            vec![0, 4, 7, 3],
+            vec![1, 2, 6, 5],
+            vec![0, 1, 5, 4],
+            vec![3, 7, 6, 2],
+        ];
+        BMesh::from_polygons(&mut rng, &points, &polygons)
+    }
+
+    /// The volume enclosed by the faces, which is only meaningful for a closed mesh.
+    fn get_volume(mesh: &BMesh) -> Float {
+        let (points, polygons) = mesh.to_polygons();
+        let mut volume = 0.0;
+        for polygon in &polygons {
+            let p0 = points[polygon[0]].to_vec3();
+            for i in 1..polygon.len() - 1 {
+                let p1 = points[polygon[i]].to_vec3();
+                let p2 = points[polygon[i + 1]].to_vec3();
+                volume += Vector3::dot(p0, Vector3::cross(p1, p2)) / 6.0;
+            }
+        }
+        volume
+    }
+
+    fn get_area(mesh: &BMesh) -> Float {
+        let (points, polygons) = mesh.to_polygons();
+        polygons
+            .iter()
+            .map(|polygon| {
+                let positions: Vec<Point3> = polygon.iter().map(|i| points[*i]).collect();
+                newell_normal(&positions).length() / 2.0
+            })
+            .sum()
+    }
+
+    fn apply(a: &BMesh, b: &BMesh, operation: BooleanOperation) -> BMesh {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let result = a.boolean(b, operation, &mut rng);
+        assert!(result.validate().is_empty());
+        result
+    }
+
+    fn assert_volume(mesh: &BMesh, expected: Float) {
+        let volume = get_volume(mesh);
+        assert!(
+            (volume - expected).abs() < 1e-4,
+            "expected a volume of {}, got {}",
+            expected,
+            volume
+        );
+    }
+
+    #[test]
+    fn overlapping_cubes() {
+        let a = create_box([0.0, 0.0, 0.0], [1.0, 1.0, 1.0]);
+        let b = create_box([0.5, 0.5, 0.5], [1.5, 1.5, 1.5]);
+
+        assert_volume(&apply(&a, &b, BooleanOperation::Union), 1.875);
+        assert_volume(&apply(&a, &b, BooleanOperation::Difference), 0.875);
+        assert_volume(&apply(&a, &b, BooleanOperation::Intersect), 0.125);
+    }
+
+    #[test]
+    fn cubes_touching_with_coplanar_faces() {
+        let a = create_box([0.0, 0.0, 0.0], [1.0, 1.0, 1.0]);
+        let b = create_box([1.0, 0.0, 0.0], [2.0, 1.0, 1.0]);
+
+        let union = apply(&a, &b, BooleanOperation::Union);
+        assert_volume(&union, 2.0);
+        // The faces where the cubes touch are inside the result, so they are gone.
+        assert!((get_area(&union) - 10.0).abs() < 1e-4);
+        assert_volume(&apply(&a, &b, BooleanOperation::Difference), 1.0);
+        assert!(apply(&a, &b, BooleanOperation::Intersect)
+            .get_faces()
+            .is_empty());
+    }
+
+    #[test]
+    fn cubes_overlapping_with_coplanar_faces() {
+        let a = create_box([0.0, 0.0, 0.0], [1.0, 1.0, 1.0]);
+        let b = create_box([0.5, 0.0, 0.0], [1.5, 1.0, 1.0]);
+
+        assert_volume(&apply(&a, &b, BooleanOperation::Union), 1.5);
+        assert_volume(&apply(&a, &b, BooleanOperation::Difference), 0.5);
+        assert_volume(&apply(&a, &b, BooleanOperation::Intersect), 0.5);
+    }
+
+    #[test]
+    fn subtracting_a_mesh_from_itself_leaves_nothing() {
+        let a = create_box([0.0, 0.0, 0.0], [1.0, 1.0, 1.0]);
+
+        let result = apply(&a, &a, BooleanOperation::Difference);
+        assert!(result.get_faces().is_empty());
+        assert!(result.get_vertices().is_empty());
+    }
+}
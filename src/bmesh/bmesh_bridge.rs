@@ -0,0 +1,223 @@
+//! Connecting two loops of edges with a strip of faces.
+
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use crate::math::Float;
+
+use super::{bmesh::BMesh, bmesh_vertex::BMeshVertex};
+
+#[derive(Debug)]
+pub enum BridgeError {
+    /// The selected edges don't form exactly two separate loops (or chains).
+    NotTwoLoops,
+    /// One of the loops is closed and the other one isn't.
+    MixedOpenAndClosed,
+    /// The loops don't have the same number of vertices, so they can't be connected with quads.
+    DifferentVertexCounts,
+}
+
+/// A chain of connected vertices, which may or may not loop back to its start.
+pub struct EdgeLoop {
+    pub vertex_ids: Vec<u32>,
+    pub is_closed: bool,
+}
+
+impl BMesh {
+    /// The selected edges (see `get_selected_edges()`), grouped into chains of connected edges.
+    /// Vertices where more than two selected edges meet end the chains going through them.
+    pub fn get_selected_edge_loops(&self) -> Vec<EdgeLoop> {
+        let mut neighbours: HashMap<u32, Vec<u32>> = HashMap::new();
+        for (a, b) in self.get_selected_edge_ids() {
+            neighbours.entry(a).or_default().push(b);
+            neighbours.entry(b).or_default().push(a);
+        }
+        let can_pass_through = |id: &u32| neighbours.get(id).is_some_and(|n| n.len() == 2);
+
+        // Start walking from the ends of open chains first, so that they are walked as a whole.
+        let mut starts: Vec<u32> = neighbours
+            .keys()
+            .filter(|id| !can_pass_through(id))
+            .copied()
+            .collect();
+        starts.extend(neighbours.keys().filter(|id| can_pass_through(id)));
+
+        let mut visited_edges: HashSet<(u32, u32)> = HashSet::new();
+        let edge_key = |a: u32, b: u32| (u32::min(a, b), u32::max(a, b));
+        let mut loops = Vec::new();
+        for start in starts {
+            for first_step in neighbours[&start].clone() {
+                if visited_edges.contains(&edge_key(start, first_step)) {
+                    continue;
+                }
+
+                let mut vertex_ids = vec![start];
+                let (mut previous, mut current) = (start, first_step);
+                visited_edges.insert(edge_key(previous, current));
+                let is_closed = loop {
+                    if current == start {
+                        break true;
+                    }
+                    vertex_ids.push(current);
+                    if !can_pass_through(&current) {
+                        break false;
+                    }
+                    let next = *neighbours[&current]
+                        .iter()
+                        .find(|n| **n != previous)
+                        .unwrap();
+                    if !visited_edges.insert(edge_key(current, next)) {
+                        break false;
+                    }
+                    (previous, current) = (current, next);
+                };
+
+                loops.push(EdgeLoop {
+                    vertex_ids,
+                    is_closed,
+                });
+            }
+        }
+
+        loops
+    }
+
+    /// Connects the two selected edge loops with quads.
+    ///
+    /// - `twist`: how many vertices to rotate the second loop by before connecting the vertices
+    ///   of the two loops. Only used for closed loops.
+    /// - `segments`: how many rows of faces to put between the loops, with the vertices in
+    ///   between placed evenly along straight lines from one loop to the other.
+    pub fn bridge_selected_edge_loops(
+        &mut self,
+        twist: i32,
+        segments: usize,
+        rng: &mut impl rand::Rng,
+    ) -> Result<(), BridgeError> {
+        let loops = self.get_selected_edge_loops();
+        if loops.len() != 2 {
+            return Err(BridgeError::NotTwoLoops);
+        }
+        let (first, second) = (&loops[0], &loops[1]);
+        if first.is_closed != second.is_closed {
+            return Err(BridgeError::MixedOpenAndClosed);
+        }
+        if first.vertex_ids.len() != second.vertex_ids.len() {
+            return Err(BridgeError::DifferentVertexCounts);
+        }
+        let is_closed = first.is_closed;
+
+        let to_vertices = |ids: &[u32]| -> Vec<Rc<RefCell<BMeshVertex>>> {
+            ids.iter().map(|id| self.get_vertex(*id).unwrap()).collect()
+        };
+        let first_vertices = to_vertices(&first.vertex_ids);
+        let mut second_vertices =
+            self.align_loop(&first_vertices, &to_vertices(&second.vertex_ids), is_closed);
+        if is_closed {
+            let n = second_vertices.len() as i32;
+            second_vertices.rotate_left(twist.rem_euclid(n) as usize);
+        }
+
+        // Rows of vertices from the first loop to the second one.
+        let segments = segments.max(1);
+        let mut rows = vec![first_vertices.clone()];
+        for segment in 1..segments {
+            let t = segment as Float / segments as Float;
+            let row: Vec<Rc<RefCell<BMeshVertex>>> = first_vertices
+                .iter()
+                .zip(&second_vertices)
+                .map(|(a, b)| {
+                    let a = a.as_ref().borrow().get_position();
+                    let b = b.as_ref().borrow().get_position();
+                    BMeshVertex::create_from_position(a.lerp(&b, t), rng)
+                })
+                .collect();
+            for vertex in &row {
+                self.add_vertex(vertex.clone());
+            }
+            rows.push(row);
+        }
+        rows.push(second_vertices);
+
+        // If the first loop is the side of existing faces, the new faces have to use its edges
+        // in the opposite direction for the winding to match.
+        let first_ids: Vec<u32> = first_vertices
+            .iter()
+            .map(|v| v.as_ref().borrow().get_id())
+            .collect();
+        let follows_existing_faces = self.faces.iter().any(|f| {
+            f.as_ref()
+                .borrow()
+                .get_half_edges()
+                .contains(&(first_ids[0], first_ids[1]))
+        });
+
+        let n = first_ids.len();
+        let side_count = if is_closed { n } else { n - 1 };
+        for pair in rows.windows(2) {
+            let (near, far) = (&pair[0], &pair[1]);
+            for i in 0..side_count {
+                let j = (i + 1) % n;
+                let mut quad = vec![
+                    near[i].clone(),
+                    near[j].clone(),
+                    far[j].clone(),
+                    far[i].clone(),
+                ];
+                if follows_existing_faces {
+                    quad.reverse();
+                }
+                self.create_face(quad);
+            }
+        }
+
+        self.rebuild_tesselation();
+        Ok(())
+    }
+
+    /// Orders the vertices of `second` so that each one is across from the vertex at the same
+    /// index in `first`, i.e. so that the sum of the distances between them is the smallest.
+    fn align_loop(
+        &self,
+        first: &[Rc<RefCell<BMeshVertex>>],
+        second: &[Rc<RefCell<BMeshVertex>>],
+        is_closed: bool,
+    ) -> Vec<Rc<RefCell<BMeshVertex>>> {
+        let n = first.len();
+        let first_positions: Vec<_> = first
+            .iter()
+            .map(|v| v.as_ref().borrow().get_position())
+            .collect();
+
+        let mut candidates = Vec::new();
+        for reversed in [false, true] {
+            let mut ordered = second.to_vec();
+            if reversed {
+                ordered.reverse();
+            }
+            let offsets = if is_closed { n } else { 1 };
+            for offset in 0..offsets {
+                let mut rotated = ordered.clone();
+                rotated.rotate_left(offset);
+                candidates.push(rotated);
+            }
+        }
+
+        candidates
+            .into_iter()
+            .map(|candidate| {
+                let total_distance: Float = candidate
+                    .iter()
+                    .zip(&first_positions)
+                    .map(|(v, p)| v.as_ref().borrow().get_position().distance(p))
+                    .sum();
+                (total_distance, candidate)
+            })
+            .min_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap())
+            .map(|(_, candidate)| candidate)
+            .unwrap()
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::HashSet;
+
+use super::bmesh::BMesh;
+
+/// What to delete out of the selection. See `BMesh::get_selected_edges()` and
+/// `BMesh::get_selected_faces()` for what counts as a selected edge/face.
+pub enum DeleteMode {
+    /// The selected vertices, along with every edge and face using them.
+    Vertices,
+    /// The selected edges and the faces using them. Vertices left without edges go as well.
+    Edges,
+    /// The selected faces, along with the edges and vertices no other face uses.
+    Faces,
+    /// The selected faces only, leaving their edges and vertices in place.
+    OnlyFaces,
+    /// The selected edges and faces, leaving the vertices in place.
+    OnlyEdgesAndFaces,
+}
+
+impl BMesh {
+    pub fn delete_selected(&mut self, mode: DeleteMode) {
+        match mode {
+            DeleteMode::Vertices => {
+                let vertex_ids: Vec<u32> = self
+                    .get_selected_vertices()
+                    .iter()
+                    .map(|v| v.as_ref().borrow().get_id())
+                    .collect();
+                for vertex_id in vertex_ids {
+                    self.kill_vertex(vertex_id);
+                }
+            }
+
+            DeleteMode::Edges => {
+                let edges = self.get_selected_edge_ids();
+                for (v0_id, v1_id) in &edges {
+                    self.kill_edge(*v0_id, *v1_id);
+                }
+
+                let endpoints: Vec<u32> = edges.iter().flat_map(|(a, b)| [*a, *b]).collect();
+                self.kill_isolated_vertices(&endpoints);
+            }
+
+            DeleteMode::Faces => {
+                let faces = self.get_selected_faces();
+                let mut face_edges: HashSet<(u32, u32)> = HashSet::new();
+                let mut face_vertices: HashSet<u32> = HashSet::new();
+                for face in &faces {
+                    let face = face.as_ref().borrow();
+                    face_edges.extend(face.get_half_edges().iter().map(|(a, b)| {
+                        // Undirected, so that both sides of an edge end up as one entry.
+                        (u32::min(*a, *b), u32::max(*a, *b))
+                    }));
+                    face_vertices.extend(face.get_vertex_ids());
+                }
+
+                for face in &faces {
+                    self.kill_face(face);
+                }
+                for (v0_id, v1_id) in face_edges {
+                    if self.get_faces_of_edge(v0_id, v1_id).is_empty() {
+                        self.kill_edge(v0_id, v1_id);
+                    }
+                }
+                self.kill_isolated_vertices(&face_vertices.into_iter().collect::<Vec<u32>>());
+            }
+
+            DeleteMode::OnlyFaces => {
+                for face in self.get_selected_faces() {
+                    self.kill_face(&face);
+                }
+            }
+
+            DeleteMode::OnlyEdgesAndFaces => {
+                for face in self.get_selected_faces() {
+                    self.kill_face(&face);
+                }
+                for (v0_id, v1_id) in self.get_selected_edge_ids() {
+                    self.kill_edge(v0_id, v1_id);
+                }
+            }
+        }
+
+        self.rebuild_tesselation();
+    }
+
+    /// The selected edges as pairs of vertex ids, which stay valid while edges are removed.
+    pub(super) fn get_selected_edge_ids(&self) -> Vec<(u32, u32)> {
+        self.get_selected_edges()
+            .iter()
+            .map(|e| {
+                let e = e.as_ref().borrow();
+                (e.get_v0_id(), e.get_v1_id())
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,389 @@
+//! Dissolving removes elements without leaving holes behind: the faces around what is removed
+//! get merged into a single n-gon.
+
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use crate::math::{vector::Vector3, Float};
+
+use super::{
+    bmesh::BMesh, bmesh_edge::BMeshEdgeLoop, bmesh_face::BMeshFace, bmesh_vertex::BMeshVertex,
+};
+
+/// How far the two edges of a vertex may bend away from a straight line (as one plus the cosine
+/// of the angle between them) for the vertex to still count as being inside a straight side.
+const STRAIGHT_SIDE_THRESHOLD: Float = 1e-4;
+
+impl BMesh {
+    /// Merges each connected group of selected faces into one face.
+    ///
+    /// - `remove_two_valence_vertices`: afterwards, dissolve the vertices of removed edges that
+    ///   are only left with two edges, since they would just be extra corners along a straight
+    ///   side of the new face.
+    pub fn dissolve_selected_faces(&mut self, remove_two_valence_vertices: bool) {
+        let faces = self.get_selected_faces();
+        let groups = group_faces(&faces, |f0, f1| {
+            let f1 = f1.as_ref().borrow();
+            f0.as_ref()
+                .borrow()
+                .get_half_edges()
+                .iter()
+                .any(|(a, b)| f1.has_edge(*a, *b))
+        });
+
+        let mut touched_vertices = Vec::new();
+        for group in groups.iter().filter(|g| g.len() > 1) {
+            if let Some(removed_edges) = self.merge_faces(group) {
+                touched_vertices.extend(removed_edges.iter().flat_map(|(a, b)| [*a, *b]));
+            }
+        }
+
+        if remove_two_valence_vertices {
+            self.dissolve_two_valence_vertices(&touched_vertices);
+        }
+        self.rebuild_tesselation();
+    }
+
+    /// Removes the selected edges, merging the two faces on either side of each of them.
+    /// Selected edges that are not between exactly two faces are left alone.
+    ///
+    /// - `remove_two_valence_vertices`: see `dissolve_selected_faces()`.
+    pub fn dissolve_selected_edges(&mut self, remove_two_valence_vertices: bool) {
+        let selected_edges: Vec<(u32, u32)> = self
+            .get_selected_edge_ids()
+            .into_iter()
+            .filter(|(a, b)| self.get_faces_of_edge(*a, *b).len() == 2)
+            .collect();
+
+        let faces: Vec<Rc<RefCell<BMeshFace>>> = {
+            let mut faces: Vec<Rc<RefCell<BMeshFace>>> = Vec::new();
+            for (a, b) in &selected_edges {
+                for face in self.get_faces_of_edge(*a, *b) {
+                    if !faces.iter().any(|f| Rc::ptr_eq(f, &face)) {
+                        faces.push(face);
+                    }
+                }
+            }
+            faces
+        };
+        let groups = group_faces(&faces, |f0, f1| {
+            let (f0, f1) = (f0.as_ref().borrow(), f1.as_ref().borrow());
+            selected_edges
+                .iter()
+                .any(|(a, b)| f0.has_edge(*a, *b) && f1.has_edge(*a, *b))
+        });
+
+        let mut touched_vertices = Vec::new();
+        for group in groups.iter().filter(|g| g.len() > 1) {
+            if let Some(removed_edges) = self.merge_faces(group) {
+                touched_vertices.extend(removed_edges.iter().flat_map(|(a, b)| [*a, *b]));
+            }
+        }
+
+        if remove_two_valence_vertices {
+            self.dissolve_two_valence_vertices(&touched_vertices);
+        }
+        self.rebuild_tesselation();
+    }
+
+    /// Removes the selected vertices, merging the faces around each of them into one.
+    ///
+    /// - `remove_two_valence_vertices`: see `dissolve_selected_faces()`.
+    pub fn dissolve_selected_vertices(&mut self, remove_two_valence_vertices: bool) {
+        let vertex_ids: Vec<u32> = self
+            .get_selected_vertices()
+            .iter()
+            .map(|v| v.as_ref().borrow().get_id())
+            .collect();
+
+        let mut touched_vertices = Vec::new();
+        for vertex_id in vertex_ids {
+            // An earlier dissolve may have taken this vertex with it already.
+            if self.get_vertex(vertex_id).is_none() {
+                continue;
+            }
+
+            let neighbours: Vec<u32> = self
+                .edge_lookup_table
+                .get_edges_of_vertex(vertex_id)
+                .iter()
+                .map(|e| e.as_ref().borrow().other_vertex(vertex_id))
+                .map(|v| v.as_ref().borrow().get_id())
+                .collect();
+
+            // A vertex inside a straight side (e.g. one between two quads) only needs to be
+            // taken out of its faces. Otherwise the faces around the vertex get merged, after
+            // which it is either gone (it was surrounded by faces) or it is a corner of the
+            // merged face along a boundary, where only its two edges are left.
+            if !(self.is_inside_straight_side(vertex_id)
+                && self.dissolve_two_valence_vertex(vertex_id))
+            {
+                let faces = self.get_faces_of_vertex(vertex_id);
+                if faces.len() > 1 {
+                    self.merge_faces(&faces);
+                }
+                if self.get_vertex(vertex_id).is_some()
+                    && !self.dissolve_two_valence_vertex(vertex_id)
+                {
+                    self.kill_vertex(vertex_id);
+                }
+            }
+            touched_vertices.extend(neighbours);
+        }
+
+        if remove_two_valence_vertices {
+            self.dissolve_two_valence_vertices(&touched_vertices);
+        }
+        self.rebuild_tesselation();
+    }
+
+    /// Replaces the faces with a single face going around their outside. Edges on the inside of
+    /// the group are removed, along with vertices that end up without edges. Gives up (returning
+    /// `None`) if the faces don't have exactly one boundary, e.g. if they form a ring, since a
+    /// face cannot have holes. On success returns the removed edges (as vertex id pairs).
+    pub(super) fn merge_faces(
+        &mut self,
+        faces: &[Rc<RefCell<BMeshFace>>],
+    ) -> Option<Vec<(u32, u32)>> {
+        let mut half_edges: HashSet<(u32, u32)> = HashSet::new();
+        let mut vertices: HashMap<u32, Rc<RefCell<BMeshVertex>>> = HashMap::new();
+        for face in faces {
+            let face = face.as_ref().borrow();
+            for half_edge in face.get_half_edges() {
+                if !half_edges.insert(half_edge) {
+                    // Neighbouring faces wind in opposite directions, so they can't be merged
+                    // into a single loop.
+                    return None;
+                }
+            }
+            for vertex in face.get_vertices() {
+                let vertex_id = vertex.as_ref().borrow().get_id();
+                vertices.insert(vertex_id, vertex);
+            }
+        }
+
+        let mut boundary: HashMap<u32, u32> = HashMap::new();
+        let mut interior_edges: Vec<(u32, u32)> = Vec::new();
+        for (a, b) in &half_edges {
+            if half_edges.contains(&(*b, *a)) {
+                if a < b {
+                    interior_edges.push((*a, *b));
+                }
+            } else if boundary.insert(*a, *b).is_some() {
+                // The boundary touches itself at a vertex.
+                return None;
+            }
+        }
+
+        let start = *boundary.keys().next()?;
+        let mut boundary_loop = vec![start];
+        let mut current = boundary[&start];
+        while current != start {
+            boundary_loop.push(current);
+            current = *boundary.get(&current)?;
+        }
+        if boundary_loop.len() != boundary.len() || boundary_loop.len() < 3 {
+            return None;
+        }
+
+        for face in faces {
+            self.kill_face(face);
+        }
+        for (a, b) in &interior_edges {
+            self.kill_edge(*a, *b);
+        }
+
+        let loop_vertices: Vec<Rc<RefCell<BMeshVertex>>> = boundary_loop
+            .iter()
+            .map(|id| vertices[id].clone())
+            .collect();
+        self.create_face(loop_vertices);
+
+        let interior_vertices: Vec<u32> =
+            interior_edges.iter().flat_map(|(a, b)| [*a, *b]).collect();
+        self.kill_isolated_vertices(&interior_vertices);
+
+        Some(interior_edges)
+    }
+
+    fn dissolve_two_valence_vertices(&mut self, vertex_ids: &[u32]) {
+        for vertex_id in vertex_ids {
+            if self.get_vertex(*vertex_id).is_some() && self.is_inside_straight_side(*vertex_id) {
+                self.dissolve_two_valence_vertex(*vertex_id);
+            }
+        }
+    }
+
+    /// Whether the vertex has exactly two edges, going in opposite directions. Such a vertex is
+    /// only an extra corner along a side of its faces, so dissolving it doesn't change their
+    /// shape. A vertex with two edges at an angle is a real corner of its faces.
+    fn is_inside_straight_side(&self, vertex_id: u32) -> bool {
+        let edges = self.edge_lookup_table.get_edges_of_vertex(vertex_id);
+        if edges.len() != 2 {
+            return false;
+        }
+
+        let Some(vertex) = self.get_vertex(vertex_id) else {
+            return false;
+        };
+        let center = vertex.as_ref().borrow().get_position();
+        let directions: Vec<Vector3> = edges
+            .iter()
+            .map(|e| {
+                let other = e.as_ref().borrow().other_vertex(vertex_id);
+                let position = other.as_ref().borrow().get_position();
+                (position - center).normalize()
+            })
+            .collect();
+
+        Vector3::dot(directions[0], directions[1]) < -1.0 + STRAIGHT_SIDE_THRESHOLD
+    }
+
+    /// If the vertex has exactly two edges, removes it and joins the two edges into one. Faces
+    /// using the vertex lose that corner, which changes their shape unless the vertex is inside
+    /// a straight side (see `is_inside_straight_side()`). Returns whether the vertex was
+    /// dissolved; it is not if that would leave a face with fewer than three corners or double
+    /// an existing edge.
+    pub(super) fn dissolve_two_valence_vertex(&mut self, vertex_id: u32) -> bool {
+        let edges = self.edge_lookup_table.get_edges_of_vertex(vertex_id);
+        if edges.len() != 2 {
+            return false;
+        }
+
+        let a = edges[0].as_ref().borrow().other_vertex(vertex_id);
+        let b = edges[1].as_ref().borrow().other_vertex(vertex_id);
+        let a_id = a.as_ref().borrow().get_id();
+        let b_id = b.as_ref().borrow().get_id();
+        if self.edge_lookup_table.get_edge(a_id, b_id).is_some() {
+            return false;
+        }
+
+        let faces = self.get_faces_of_vertex(vertex_id);
+        if faces
+            .iter()
+            .any(|f| f.as_ref().borrow().vertex_count() <= 3)
+        {
+            return false;
+        }
+
+        for face in &faces {
+            let remaining: Vec<Rc<RefCell<BMeshVertex>>> = face
+                .as_ref()
+                .borrow()
+                .get_vertices()
+                .into_iter()
+                .filter(|v| v.as_ref().borrow().get_id() != vertex_id)
+                .collect();
+            face.as_ref()
+                .borrow_mut()
+                .set_edge_loop(BMeshEdgeLoop::new(remaining));
+        }
+
+        // No face uses the vertex anymore, so this only removes its two edges and itself.
+        self.kill_vertex(vertex_id);
+        self.get_or_create_edge(a, b);
+
+        true
+    }
+}
+
+/// Splits the faces into groups, where two faces end up in the same group if they are connected
+/// through a chain of faces for which `are_connected` holds.
+fn group_faces(
+    faces: &[Rc<RefCell<BMeshFace>>],
+    are_connected: impl Fn(&Rc<RefCell<BMeshFace>>, &Rc<RefCell<BMeshFace>>) -> bool,
+) -> Vec<Vec<Rc<RefCell<BMeshFace>>>> {
+    let mut group_of: Vec<usize> = (0..faces.len()).collect();
+    for i in 0..faces.len() {
+        for j in (i + 1)..faces.len() {
+            if group_of[i] != group_of[j] && are_connected(&faces[i], &faces[j]) {
+                let (from, to) = (group_of[j], group_of[i]);
+                for group in group_of.iter_mut() {
+                    if *group == from {
+                        *group = to;
+                    }
+                }
+            }
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<Rc<RefCell<BMeshFace>>>> = HashMap::new();
+    for (i, face) in faces.iter().enumerate() {
+        groups.entry(group_of[i]).or_default().push(face.clone());
+    }
+
+    groups.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use crate::math::point::Point3;
+
+    use super::*;
+
+    /// A flat grid of `size` by `size` quads, with the vertices numbered row by row.
+    fn create_grid(size: usize) -> BMesh {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let points: Vec<Point3> = (0..=size)
+            .flat_map(|row| {
+                (0..=size).map(move |column| Point3::new(column as Float, row as Float, 0.0))
+            })
+            .collect();
+        let polygons: Vec<Vec<usize>> = (0..size)
+            .flat_map(|row| {
+                (0..size).map(move |column| {
+                    let i = row * (size + 1) + column;
+                    vec![i, i + 1, i + size + 2, i + size + 1]
+                })
+            })
+            .collect();
+        BMesh::from_polygons(&mut rng, &points, &polygons)
+    }
+
+    #[test]
+    fn dissolving_the_inner_vertices_of_a_grid_leaves_its_border() {
+        let mut mesh = create_grid(3);
+        for i in [5, 6, 9, 10] {
+            let id = mesh.get_vertices()[i].as_ref().borrow().get_id();
+            mesh.select_vertex(id);
+        }
+
+        mesh.dissolve_selected_vertices(false);
+        let faces = mesh.get_faces();
+        assert_eq!(faces.len(), 1);
+        assert_eq!(faces[0].as_ref().borrow().vertex_count(), 12);
+        assert!(mesh.validate().is_empty());
+    }
+
+    #[test]
+    fn dissolving_a_vertex_inside_a_side_keeps_both_faces() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let points = [
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, 0.0, 0.0),
+            Point3::new(1.0, 1.0, 0.0),
+            Point3::new(0.0, 1.0, 0.0),
+            Point3::new(2.0, 0.0, 0.0),
+            Point3::new(2.0, 1.0, 0.0),
+            Point3::new(1.0, 0.5, 0.0),
+        ];
+        // Two quads, with an extra corner in the middle of the side between them.
+        let polygons = [vec![0, 1, 6, 2, 3], vec![1, 4, 5, 2, 6]];
+        let mut mesh = BMesh::from_polygons(&mut rng, &points, &polygons);
+        let id = mesh.get_vertices()[6].as_ref().borrow().get_id();
+        mesh.select_vertex(id);
+
+        mesh.dissolve_selected_vertices(false);
+        let faces = mesh.get_faces();
+        assert_eq!(faces.len(), 2);
+        assert!(faces
+            .iter()
+            .all(|f| f.as_ref().borrow().vertex_count() == 4));
+        assert!(mesh.validate().is_empty());
+    }
+}
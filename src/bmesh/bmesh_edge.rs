@@ -1,5 +1,7 @@
 use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
+use log::warn;
+
 use super::bmesh_vertex::BMeshVertex;
 
 /// There are many situations where purely storing vertices to implicitly define
@@ -9,11 +11,12 @@ use super::bmesh_vertex::BMeshVertex;
 /// The benifit is storing associated data to the edge, which may be useful in
 /// the future, and this is more readable.
 pub struct BMeshEdge {
-    id: u32,
-
     // The vertices the edge is defined between.
     v0: Rc<RefCell<BMeshVertex>>,
     v1: Rc<RefCell<BMeshVertex>>,
+
+    /// Sharp edges split the normals of the faces on either side (see `recalculate_normals()`).
+    sharp: bool,
 }
 
 impl BMeshEdge {
@@ -21,19 +24,26 @@ impl BMeshEdge {
         v0: Rc<RefCell<BMeshVertex>>,
         v1: Rc<RefCell<BMeshVertex>>,
         lookup_table: &mut BMeshEdgeLookupTable,
-        rng: &mut impl rand::Rng,
     ) -> Rc<RefCell<Self>> {
         let to_return = Rc::new(RefCell::new(Self {
-            id: rng.next_u32(),
             v0,
             v1,
+            sharp: false,
         }));
 
-        lookup_table.insert_edge(to_return);
+        lookup_table.insert_edge(to_return.clone());
 
         to_return
     }
 
+    pub fn get_v0(&self) -> Rc<RefCell<BMeshVertex>> {
+        self.v0.clone()
+    }
+
+    pub fn get_v1(&self) -> Rc<RefCell<BMeshVertex>> {
+        self.v1.clone()
+    }
+
     pub fn get_v0_id(&self) -> u32 {
         self.v0.as_ref().borrow().get_id()
     }
@@ -41,11 +51,39 @@ impl BMeshEdge {
     pub fn get_v1_id(&self) -> u32 {
         self.v1.as_ref().borrow().get_id()
     }
+
+    pub fn is_sharp(&self) -> bool {
+        self.sharp
+    }
+
+    pub(super) fn set_sharp(&mut self, sharp: bool) {
+        self.sharp = sharp;
+    }
+
+    /// Moves the edge to be between two other vertices. The edge needs to be taken out of any
+    /// lookup table first, since it is keyed by the vertices.
+    pub(super) fn set_vertices(
+        &mut self,
+        v0: Rc<RefCell<BMeshVertex>>,
+        v1: Rc<RefCell<BMeshVertex>>,
+    ) {
+        self.v0 = v0;
+        self.v1 = v1;
+    }
+
+    /// Given the id of one of the vertices of the edge, returns the other one.
+    pub fn other_vertex(&self, vertex_id: u32) -> Rc<RefCell<BMeshVertex>> {
+        if self.get_v0_id() == vertex_id {
+            self.v1.clone()
+        } else {
+            self.v0.clone()
+        }
+    }
 }
 
 /// Takes vertices as keys.
-/// - Looking up with a single vertex will return all return all edges that share that
-/// vertex.
+/// - Looking up with a single vertex will return all edges that share that
+///   vertex.
 /// - Looking up with two vertices will return the (unique) edge between them.
 pub struct BMeshEdgeLookupTable {
     table: HashMap<u32, HashMap<u32, Rc<RefCell<BMeshEdge>>>>,
@@ -58,16 +96,64 @@ impl BMeshEdgeLookupTable {
         }
     }
 
-    pub fn insert_edge(&mut self, item: Rc<RefCell<BMeshEdge>>) {
+    /// Registers the edge under both of its vertices. If there already is an edge between the two
+    /// vertices, the table is left as it is and the existing edge is returned; an edge is unique
+    /// to its pair of vertices, so callers should reuse that one instead.
+    pub fn insert_edge(&mut self, item: Rc<RefCell<BMeshEdge>>) -> Option<Rc<RefCell<BMeshEdge>>> {
         let key0 = item.as_ref().borrow().get_v0_id();
         let key1 = item.as_ref().borrow().get_v1_id();
 
+        if let Some(existing) = self.get_edge(key0, key1) {
+            warn!(
+                "an edge between vertices {} and {} already exists, not inserting",
+                key0, key1
+            );
+            return Some(existing);
+        }
+
         let v0_table = self.table.entry(key0).or_default();
-        // TODO: warn if value existed already?
         v0_table.insert(key1, item.clone());
 
         let v1_table = self.table.entry(key1).or_default();
         v1_table.insert(key0, item.clone());
+
+        None
+    }
+
+    /// Removes the edge between the two vertices, returning it if there was one.
+    pub fn remove_edge(&mut self, v0_id: u32, v1_id: u32) -> Option<Rc<RefCell<BMeshEdge>>> {
+        let removed = self
+            .table
+            .get_mut(&v0_id)
+            .and_then(|v0_table| v0_table.remove(&v1_id));
+        if let Some(v1_table) = self.table.get_mut(&v1_id) {
+            v1_table.remove(&v0_id);
+        }
+
+        // Don't keep empty tables around for vertices that no longer have edges.
+        for key in [v0_id, v1_id] {
+            if self.table.get(&key).is_some_and(|t| t.is_empty()) {
+                self.table.remove(&key);
+            }
+        }
+
+        removed
+    }
+
+    /// The (unique) edge between the two vertices, if there is one.
+    pub fn get_edge(&self, v0_id: u32, v1_id: u32) -> Option<Rc<RefCell<BMeshEdge>>> {
+        self.table
+            .get(&v0_id)
+            .and_then(|v0_table| v0_table.get(&v1_id))
+            .cloned()
+    }
+
+    /// All edges that have the vertex as one of their endpoints.
+    pub fn get_edges_of_vertex(&self, vertex_id: u32) -> Vec<Rc<RefCell<BMeshEdge>>> {
+        self.table
+            .get(&vertex_id)
+            .map(|vertex_table| vertex_table.values().cloned().collect())
+            .unwrap_or_default()
     }
 }
 
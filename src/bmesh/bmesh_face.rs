@@ -1,10 +1,30 @@
 use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
-use super::{bmesh_edge::BMeshEdgeLoop, BMeshTesselation};
+use crate::math::{
+    point::Point3,
+    vector::{Vector2, Vector3},
+    Float,
+};
+
+use super::{bmesh_edge::BMeshEdgeLoop, bmesh_vertex::BMeshVertex, BMeshTesselation};
 
 pub enum TesselationStrategy {
     Triangle,
     Quad,
+    /// For n-gons, which may be concave. See `ear_clip()`.
+    EarClip,
+}
+
+impl TesselationStrategy {
+    /// The strategy to tesselate a face with the given number of vertices, if we know how to.
+    pub fn from_vertex_count(vertex_count: usize) -> Option<Self> {
+        match vertex_count {
+            3 => Some(Self::Triangle),
+            4 => Some(Self::Quad),
+            n if n > 4 => Some(Self::EarClip),
+            _ => None,
+        }
+    }
 }
 
 pub struct BMeshFace {
@@ -17,9 +37,11 @@ impl BMeshFace {
         defining_edges: BMeshEdgeLoop,
         tesselation_strategy: TesselationStrategy,
     ) -> Rc<RefCell<Self>> {
+        let tesselation = Self::tesselate_edge_loop(&defining_edges, tesselation_strategy);
+
         Rc::new(RefCell::new(Self {
             defining_edges,
-            tesselation: Self::tesselate_edge_loop(&defining_edges, tesselation_strategy),
+            tesselation,
         }))
     }
 
@@ -31,15 +53,93 @@ impl BMeshFace {
         let indices = match tesselation_strategy {
             TesselationStrategy::Triangle => vec![0, 1, 2],
             TesselationStrategy::Quad => vec![0, 1, 2, 2, 3, 0],
+            TesselationStrategy::EarClip => {
+                let positions: Vec<Point3> = vertices
+                    .iter()
+                    .map(|v| v.as_ref().borrow().get_position())
+                    .collect();
+                ear_clip(&positions)
+            }
         };
 
         BMeshTesselation { vertices, indices }
     }
 
+    pub fn get_vertices(&self) -> Vec<Rc<RefCell<BMeshVertex>>> {
+        self.defining_edges.get_vertices()
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.defining_edges.get_vertices().len()
+    }
+
+    /// The (directed) edges going around the face, as pairs of vertex ids.
+    pub fn get_half_edges(&self) -> Vec<(u32, u32)> {
+        let ids = self.get_vertex_ids();
+        (0..ids.len())
+            .map(|i| (ids[i], ids[(i + 1) % ids.len()]))
+            .collect()
+    }
+
+    /// Whether the edge between the two vertices (in either direction) is part of the face.
+    pub fn has_edge(&self, v0_id: u32, v1_id: u32) -> bool {
+        self.get_half_edges()
+            .iter()
+            .any(|(a, b)| (*a == v0_id && *b == v1_id) || (*a == v1_id && *b == v0_id))
+    }
+
+    pub fn has_vertex(&self, vertex_id: u32) -> bool {
+        self.get_vertex_ids().contains(&vertex_id)
+    }
+
+    /// The positions of the triangles the face is tesselated into.
+    pub fn get_triangles(&self) -> Vec<[Point3; 3]> {
+        let positions: Vec<Point3> = self
+            .tesselation
+            .vertices
+            .iter()
+            .map(|v| v.as_ref().borrow().get_position())
+            .collect();
+
+        self.tesselation
+            .indices
+            .chunks_exact(3)
+            .map(|t| [positions[t[0]], positions[t[1]], positions[t[2]]])
+            .collect()
+    }
+
+    /// The triangles the face is tesselated into, as indices into its corners (in the order
+    /// of `get_vertices()`).
+    pub fn get_triangle_indices(&self) -> Vec<[usize; 3]> {
+        self.tesselation
+            .indices
+            .chunks_exact(3)
+            .map(|t| [t[0], t[1], t[2]])
+            .collect()
+    }
+
+    pub fn get_vertex_ids(&self) -> Vec<u32> {
+        self.defining_edges
+            .get_vertices()
+            .iter()
+            .map(|v| v.as_ref().borrow().get_id())
+            .collect()
+    }
+
+    /// Replaces the boundary of the face and tesselates it again. The caller is responsible
+    /// for the new loop having a tesselation strategy.
+    pub(super) fn set_edge_loop(&mut self, defining_edges: BMeshEdgeLoop) {
+        let tesselation_strategy =
+            TesselationStrategy::from_vertex_count(defining_edges.get_vertices().len()).unwrap();
+        self.tesselation = Self::tesselate_edge_loop(&defining_edges, tesselation_strategy);
+        self.defining_edges = defining_edges;
+    }
+
     pub fn aggregate_tesselations(faces: &Vec<Rc<RefCell<BMeshFace>>>) -> BMeshTesselation {
         // for every face
-        //     for every vertex in face.tesselation
-        //        if vertex in index_lookup:
+        //     for every index in face.tesselation
+        //        let v = face.tesselation.vertices[index]
+        //        if v in index_lookup:
         //            indices.push(i)
         //        else:
         //            let i = vertices.len()
@@ -52,11 +152,13 @@ impl BMeshFace {
         let mut index_lookup: HashMap<u32, usize> = HashMap::new();
 
         for face in faces {
-            for vertex in face.as_ref().borrow().tesselation.vertices {
+            let face = face.as_ref().borrow();
+            for face_index in &face.tesselation.indices {
+                let vertex = &face.tesselation.vertices[*face_index];
                 let vertex_id = vertex.as_ref().borrow().get_id();
 
                 if let Some(i) = index_lookup.get(&vertex_id) {
-                    indices.push(i.clone());
+                    indices.push(*i);
                     continue;
                 }
 
@@ -70,3 +172,87 @@ impl BMeshFace {
         BMeshTesselation { vertices, indices }
     }
 }
+
+/// The normal of the polygon using Newell's method, which is robust for n-gons that are
+/// concave or not quite planar. The result is not normalized; its length is twice the area of
+/// the polygon.
+pub fn newell_normal(positions: &[Point3]) -> Vector3 {
+    let mut normal = Vector3::zero();
+    for i in 0..positions.len() {
+        let current = positions[i];
+        let next = positions[(i + 1) % positions.len()];
+        normal = normal
+            + Vector3::new(
+                (current.y() - next.y()) * (current.z() + next.z()),
+                (current.z() - next.z()) * (current.x() + next.x()),
+                (current.x() - next.x()) * (current.y() + next.y()),
+            );
+    }
+
+    normal
+}
+
+/// Triangulates a simple polygon (going around CCW) by repeatedly cutting off "ears", i.e.
+/// convex corners whose triangle contains no other corner. The polygon is projected onto the
+/// plane its normal is most aligned with, so it only needs to be roughly planar. Returns indices
+/// into `positions`, three per triangle.
+pub(super) fn ear_clip(positions: &[Point3]) -> Vec<usize> {
+    let normal = newell_normal(positions);
+
+    // Drop the dominant axis of the normal, keeping the orientation so that CCW stays CCW.
+    let (ax, ay, az) = (normal.x().abs(), normal.y().abs(), normal.z().abs());
+    let projected: Vec<Vector2> = positions
+        .iter()
+        .map(|p| {
+            if az >= ax && az >= ay {
+                Vector2::new(p.x(), p.y() * normal.z().signum())
+            } else if ax >= ay {
+                Vector2::new(p.y(), p.z() * normal.x().signum())
+            } else {
+                Vector2::new(p.z(), p.x() * normal.y().signum())
+            }
+        })
+        .collect();
+
+    fn cross(o: &Vector2, a: &Vector2, b: &Vector2) -> Float {
+        (a.x() - o.x()) * (b.y() - o.y()) - (a.y() - o.y()) * (b.x() - o.x())
+    }
+
+    let mut remaining: Vec<usize> = (0..positions.len()).collect();
+    let mut indices = Vec::with_capacity(3 * (positions.len() - 2));
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let ear = (0..n).find(|i| {
+            let prev = &projected[remaining[(i + n - 1) % n]];
+            let current = &projected[remaining[*i]];
+            let next = &projected[remaining[(i + 1) % n]];
+            if cross(prev, current, next) <= 0.0 {
+                return false;
+            }
+
+            remaining.iter().all(|j| {
+                let p = &projected[*j];
+                let is_corner = *j == remaining[(i + n - 1) % n]
+                    || *j == remaining[*i]
+                    || *j == remaining[(i + 1) % n];
+                is_corner
+                    || cross(prev, current, p) < 0.0
+                    || cross(current, next, p) < 0.0
+                    || cross(next, prev, p) < 0.0
+            })
+        });
+
+        // Degenerate polygons (e.g. collinear corners) might not have an ear, in which case we
+        // cut off whatever corner is first rather than give up.
+        let i = ear.unwrap_or(0);
+        indices.extend_from_slice(&[
+            remaining[(i + n - 1) % n],
+            remaining[i],
+            remaining[(i + 1) % n],
+        ]);
+        remaining.remove(i);
+    }
+    indices.extend_from_slice(&remaining);
+
+    indices
+}
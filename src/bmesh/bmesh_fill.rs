@@ -0,0 +1,199 @@
+//! Finding the holes in a mesh and closing them.
+
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use log::warn;
+
+use crate::math::{point::Point3, vector::Vector3, Float};
+
+use super::{bmesh::BMesh, bmesh_vertex::BMeshVertex};
+
+pub enum FillMode {
+    /// A single n-gon.
+    Ngon,
+    /// Triangles around a new vertex at the center of the hole.
+    TriangleFan,
+    /// A grid of quads. This needs an even number of vertices around the hole, which are split
+    /// into four sides facing each other in pairs.
+    Grid,
+}
+
+impl BMesh {
+    /// The loops of edges that only have a face on one side, i.e. the outlines of the holes in
+    /// the mesh. Each loop is a list of vertex ids going the same way around as the faces along
+    /// it, so a face filling the hole has to go the other way.
+    pub fn get_boundary_loops(&self) -> Vec<Vec<u32>> {
+        let mut half_edges: HashMap<(u32, u32), ()> = HashMap::new();
+        for face in &self.faces {
+            for half_edge in face.as_ref().borrow().get_half_edges() {
+                half_edges.insert(half_edge, ());
+            }
+        }
+
+        let mut next: HashMap<u32, u32> = HashMap::new();
+        for (a, b) in half_edges.keys() {
+            if !half_edges.contains_key(&(*b, *a)) {
+                // If the boundary touches itself at a vertex we can't tell which way to go,
+                // so only one of the ways is kept.
+                next.insert(*a, *b);
+            }
+        }
+
+        let mut loops = Vec::new();
+        while let Some(start) = next.keys().next().copied() {
+            let mut boundary_loop = vec![start];
+            let mut current = next.remove(&start).unwrap();
+            while current != start {
+                boundary_loop.push(current);
+                current = match next.remove(&current) {
+                    Some(following) => following,
+                    None => break,
+                };
+            }
+
+            if current == start && boundary_loop.len() >= 3 {
+                loops.push(boundary_loop);
+            }
+        }
+
+        loops
+    }
+
+    /// Closes every hole whose outline is entirely selected. Returns the number of holes filled.
+    pub fn fill_selected_holes(&mut self, mode: FillMode, rng: &mut impl rand::Rng) -> usize {
+        let selected_loops: Vec<Vec<u32>> = self
+            .get_boundary_loops()
+            .into_iter()
+            .filter(|boundary_loop| {
+                boundary_loop.iter().all(|id| {
+                    self.get_vertex(*id)
+                        .is_some_and(|v| v.as_ref().borrow().is_selected())
+                })
+            })
+            .collect();
+
+        let mut filled = 0;
+        for boundary_loop in selected_loops {
+            // Reversed, so that the new faces wind the same way as the ones around the hole.
+            let hole: Vec<Rc<RefCell<BMeshVertex>>> = boundary_loop
+                .iter()
+                .rev()
+                .map(|id| self.get_vertex(*id).unwrap())
+                .collect();
+
+            let was_filled = match mode {
+                FillMode::Ngon => {
+                    self.create_face(hole);
+                    true
+                }
+                FillMode::TriangleFan => {
+                    self.fill_triangle_fan(&hole, rng);
+                    true
+                }
+                FillMode::Grid => self.fill_grid(&hole, rng),
+            };
+            if was_filled {
+                filled += 1;
+            }
+        }
+
+        self.rebuild_tesselation();
+        filled
+    }
+
+    fn fill_triangle_fan(&mut self, hole: &[Rc<RefCell<BMeshVertex>>], rng: &mut impl rand::Rng) {
+        let positions: Vec<Point3> = hole
+            .iter()
+            .map(|v| v.as_ref().borrow().get_position())
+            .collect();
+        let center = BMeshVertex::create_from_position(Point3::centroid(&positions), rng);
+        self.add_vertex(center.clone());
+
+        for i in 0..hole.len() {
+            let next = hole[(i + 1) % hole.len()].clone();
+            self.create_face(vec![hole[i].clone(), next, center.clone()]);
+        }
+    }
+
+    /// Fills the hole with a grid whose inside is interpolated from the four sides (a Coons
+    /// patch). Returns whether the hole could be filled.
+    fn fill_grid(&mut self, hole: &[Rc<RefCell<BMeshVertex>>], rng: &mut impl rand::Rng) -> bool {
+        let length = hole.len();
+        if length < 4 || !length.is_multiple_of(2) {
+            warn!(
+                "can't grid fill a hole with {} vertices, it needs an even number",
+                length
+            );
+            return false;
+        }
+
+        // Going around the hole: bottom (left to right), right (bottom to top), top (right to
+        // left) and left (top to bottom), with `columns` and `rows` edges along them.
+        let columns = length / 4;
+        let rows = length / 2 - columns;
+        let boundary_at = |column: usize, row: usize| -> Option<Rc<RefCell<BMeshVertex>>> {
+            let index = if row == 0 {
+                column
+            } else if column == columns {
+                columns + row
+            } else if row == rows {
+                2 * columns + rows - column
+            } else if column == 0 {
+                (2 * columns + 2 * rows - row) % length
+            } else {
+                return None;
+            };
+            Some(hole[index].clone())
+        };
+        let position_at = |column: usize, row: usize| -> Vector3 {
+            boundary_at(column, row)
+                .unwrap()
+                .as_ref()
+                .borrow()
+                .get_position()
+                .to_vec3()
+        };
+
+        let mut grid: Vec<Vec<Rc<RefCell<BMeshVertex>>>> = Vec::with_capacity(rows + 1);
+        for row in 0..=rows {
+            let mut grid_row = Vec::with_capacity(columns + 1);
+            for column in 0..=columns {
+                if let Some(vertex) = boundary_at(column, row) {
+                    grid_row.push(vertex);
+                    continue;
+                }
+
+                let u = column as Float / columns as Float;
+                let v = row as Float / rows as Float;
+                let between_bottom_and_top =
+                    position_at(column, 0) * (1.0 - v) + position_at(column, rows) * v;
+                let between_left_and_right =
+                    position_at(0, row) * (1.0 - u) + position_at(columns, row) * u;
+                let between_corners = position_at(0, 0) * ((1.0 - u) * (1.0 - v))
+                    + position_at(columns, 0) * (u * (1.0 - v))
+                    + position_at(0, rows) * ((1.0 - u) * v)
+                    + position_at(columns, rows) * (u * v);
+                let position = Point3::origin()
+                    + (between_bottom_and_top + between_left_and_right - between_corners);
+
+                let vertex = BMeshVertex::create_from_position(position, rng);
+                self.add_vertex(vertex.clone());
+                grid_row.push(vertex);
+            }
+            grid.push(grid_row);
+        }
+
+        for row in 0..rows {
+            for column in 0..columns {
+                self.create_face(vec![
+                    grid[row][column].clone(),
+                    grid[row][column + 1].clone(),
+                    grid[row + 1][column + 1].clone(),
+                    grid[row + 1][column].clone(),
+                ]);
+            }
+        }
+
+        true
+    }
+}
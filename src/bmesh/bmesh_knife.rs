@@ -0,0 +1,209 @@
+//! Cutting faces along a path drawn over the mesh, like a knife.
+//!
+//! Each segment of the path, together with the eye, spans a plane. Wherever a visible edge
+//! crosses that plane between the two ends of the segment, the edge gets split, and the faces
+//! between consecutive splits are split along the cut. This means the cut follows what the
+//! user sees on the screen rather than the surface distance between the points.
+
+use std::collections::HashMap;
+
+use crate::math::{point::Point3, vector::Vector3, Float};
+
+use super::bmesh::BMesh;
+
+/// Crossings this close to an end of an edge (as a fraction of the edge) are snapped to that
+/// end, so that cutting through a vertex doesn't create slivers.
+const VERTEX_SNAP_FRACTION: Float = 1e-3;
+
+#[derive(Clone, Copy)]
+enum CutPoint {
+    Vertex(u32),
+    /// A point on the edge between the two vertices, `t` of the way from `v0` to `v1`.
+    Edge {
+        v0_id: u32,
+        v1_id: u32,
+        t: Float,
+    },
+}
+
+impl CutPoint {
+    fn is_same_as(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Vertex(a), Self::Vertex(b)) => a == b,
+            (
+                Self::Edge { v0_id, v1_id, t },
+                Self::Edge {
+                    v0_id: other_v0_id,
+                    v1_id: other_v1_id,
+                    t: other_t,
+                },
+            ) => {
+                (v0_id, v1_id) == (other_v0_id, other_v1_id)
+                    && (t - other_t).abs() < VERTEX_SNAP_FRACTION
+            }
+            _ => false,
+        }
+    }
+}
+
+impl BMesh {
+    /// Cuts the mesh along the path, as seen from `eye`. The points of the path are expected to
+    /// be on the surface of the mesh (e.g. from `ray_cast()`), but they only guide the cut: new
+    /// vertices are only made where the path crosses edges, so a point in the middle of a face
+    /// doesn't end up in the mesh. Returns the number of faces that were split.
+    pub fn knife_cut(&mut self, path: &[Point3], eye: Point3, rng: &mut impl rand::Rng) -> usize {
+        let mut cut_points: Vec<CutPoint> = Vec::new();
+        for segment in path.windows(2) {
+            for cut_point in self.get_knife_crossings(segment[0], segment[1], eye) {
+                if !cut_points
+                    .last()
+                    .is_some_and(|last| last.is_same_as(&cut_point))
+                {
+                    cut_points.push(cut_point);
+                }
+            }
+        }
+
+        // Split the edges. An edge may be crossed more than once, in which case it needs to be
+        // split at all of those points at once.
+        let edge_key = |v0_id: u32, v1_id: u32, t: Float| {
+            if v0_id < v1_id {
+                ((v0_id, v1_id), t)
+            } else {
+                ((v1_id, v0_id), 1.0 - t)
+            }
+        };
+        let mut splits: HashMap<(u32, u32), Vec<Float>> = HashMap::new();
+        for cut_point in &cut_points {
+            if let CutPoint::Edge { v0_id, v1_id, t } = cut_point {
+                let (key, t) = edge_key(*v0_id, *v1_id, *t);
+                splits.entry(key).or_default().push(t);
+            }
+        }
+        let mut split_vertices: HashMap<((u32, u32), u32), u32> = HashMap::new();
+        for ((v0_id, v1_id), mut ts) in splits {
+            ts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            ts.dedup_by(|a, b| (*a - *b).abs() < VERTEX_SNAP_FRACTION);
+            let new_vertices = self.split_edge(v0_id, v1_id, &ts, rng);
+            for (t, vertex) in ts.iter().zip(new_vertices) {
+                let vertex_id = vertex.as_ref().borrow().get_id();
+                split_vertices.insert(((v0_id, v1_id), t.to_bits()), vertex_id);
+            }
+        }
+
+        let chain: Vec<u32> = cut_points
+            .iter()
+            .filter_map(|cut_point| match cut_point {
+                CutPoint::Vertex(id) => Some(*id),
+                CutPoint::Edge { v0_id, v1_id, t } => {
+                    let (key, t) = edge_key(*v0_id, *v1_id, *t);
+                    // Crossings that were merged by the dedup above are close enough to the
+                    // one that was kept.
+                    split_vertices
+                        .iter()
+                        .filter(|((k, _), _)| *k == key)
+                        .min_by(|((_, a), _), ((_, b), _)| {
+                            let da = (Float::from_bits(*a) - t).abs();
+                            let db = (Float::from_bits(*b) - t).abs();
+                            da.partial_cmp(&db).unwrap()
+                        })
+                        .map(|(_, id)| *id)
+                }
+            })
+            .collect();
+
+        // Connect consecutive cut points through the face they share.
+        let mut split_count = 0;
+        for pair in chain.windows(2) {
+            let (v0_id, v1_id) = (pair[0], pair[1]);
+            if v0_id == v1_id || self.edge_lookup_table.get_edge(v0_id, v1_id).is_some() {
+                continue;
+            }
+
+            let face = self.faces.iter().find(|f| {
+                let f = f.as_ref().borrow();
+                f.has_vertex(v0_id) && f.has_vertex(v1_id)
+            });
+            if let Some(face) = face.cloned() {
+                if self.split_face(&face, v0_id, v1_id) {
+                    split_count += 1;
+                }
+            }
+        }
+
+        self.rebuild_tesselation();
+        split_count
+    }
+
+    /// Where visible edges cross the segment from `p` to `q`, as seen from `eye`, ordered from
+    /// `p` to `q`.
+    fn get_knife_crossings(&self, p: Point3, q: Point3, eye: Point3) -> Vec<CutPoint> {
+        let to_p = (p - eye).normalize();
+        let to_q = (q - eye).normalize();
+        let plane_normal = Vector3::cross(to_p, to_q).normalize();
+        if plane_normal.length() < Float::EPSILON {
+            // The segment points straight at the eye, so it doesn't cross anything on screen.
+            return Vec::new();
+        }
+
+        let mut crossings: Vec<(Float, CutPoint)> = Vec::new();
+        for edge in &self.edges {
+            let edge = edge.as_ref().borrow();
+            let a = edge.get_v0().as_ref().borrow().get_position();
+            let b = edge.get_v1().as_ref().borrow().get_position();
+
+            let a_side = Vector3::dot(a - eye, plane_normal);
+            let b_side = Vector3::dot(b - eye, plane_normal);
+            if (a_side > 0.0 && b_side > 0.0) || (a_side < 0.0 && b_side < 0.0) {
+                continue;
+            }
+            if a_side == b_side {
+                // The edge lies in the plane, so it runs along the cut rather than across it.
+                continue;
+            }
+
+            let t = a_side / (a_side - b_side);
+            let crossing = a.lerp(&b, t);
+            let to_crossing = (crossing - eye).normalize();
+
+            // The plane extends past the segment, so check that the crossing is between the
+            // two ends (and in front of the eye rather than behind it).
+            let tolerance = -1e-5;
+            let after_p = Vector3::dot(Vector3::cross(to_p, to_crossing), plane_normal);
+            let before_q = Vector3::dot(Vector3::cross(to_crossing, to_q), plane_normal);
+            if after_p < tolerance
+                || before_q < tolerance
+                || Vector3::dot(to_crossing, to_p + to_q) <= 0.0
+            {
+                continue;
+            }
+
+            if !self.is_point_visible(eye, crossing) {
+                continue;
+            }
+
+            let cut_point = if t < VERTEX_SNAP_FRACTION {
+                CutPoint::Vertex(edge.get_v0_id())
+            } else if t > 1.0 - VERTEX_SNAP_FRACTION {
+                CutPoint::Vertex(edge.get_v1_id())
+            } else {
+                CutPoint::Edge {
+                    v0_id: edge.get_v0_id(),
+                    v1_id: edge.get_v1_id(),
+                    t,
+                }
+            };
+            let angle_from_p = Float::atan2(
+                Vector3::cross(to_p, to_crossing).length(),
+                Vector3::dot(to_p, to_crossing),
+            );
+            crossings.push((angle_from_p, cut_point));
+        }
+
+        crossings.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap());
+        crossings
+            .into_iter()
+            .map(|(_, cut_point)| cut_point)
+            .collect()
+    }
+}
@@ -0,0 +1,377 @@
+//! Merging ("welding") vertices together. Whatever the merge target, this boils down to
+//! mapping every vertex that goes away onto the vertex that stays, and then repairing the
+//! topology: edges that collapse to a point or end up doubled are removed, and faces lose the
+//! corners that were merged together (or go away entirely once they have fewer than three).
+
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use crate::math::{point::Point3, Float};
+
+use super::{
+    bmesh::BMesh,
+    bmesh_edge::BMeshEdgeLoop,
+    bmesh_face::{BMeshFace, TesselationStrategy},
+    bmesh_vertex::BMeshVertex,
+    spatial_hash::SpatialHash,
+};
+
+/// Where the selected vertices end up when merging them into a single vertex.
+pub enum MergeTarget {
+    /// The average position of the selected vertices.
+    Center,
+    /// The vertex that was selected first.
+    First,
+    /// The vertex that was selected last.
+    Last,
+    /// An arbitrary position, e.g. the 3D cursor.
+    Cursor(Point3),
+}
+
+impl BMesh {
+    /// Merges all selected vertices into one. The vertex that remains is the last selected one
+    /// (for `MergeTarget::First`, the first one), moved to the target position. Returns the
+    /// number of vertices removed.
+    pub fn merge_selected_vertices(&mut self, target: MergeTarget) -> usize {
+        let selected = self.get_selected_vertices();
+        if selected.len() < 2 {
+            return 0;
+        }
+
+        let kept = match target {
+            MergeTarget::First => selected.first().unwrap().clone(),
+            _ => selected.last().unwrap().clone(),
+        };
+        let position = match target {
+            MergeTarget::Center => {
+                let positions: Vec<Point3> = selected
+                    .iter()
+                    .map(|v| v.as_ref().borrow().get_position())
+                    .collect();
+                Point3::centroid(&positions)
+            }
+            MergeTarget::First | MergeTarget::Last => kept.as_ref().borrow().get_position(),
+            MergeTarget::Cursor(position) => position,
+        };
+        kept.as_ref().borrow_mut().set_position(position);
+
+        let kept_id = kept.as_ref().borrow().get_id();
+        let weld_map: HashMap<u32, Rc<RefCell<BMeshVertex>>> = selected
+            .iter()
+            .map(|v| v.as_ref().borrow().get_id())
+            .filter(|id| *id != kept_id)
+            .map(|id| (id, kept.clone()))
+            .collect();
+
+        self.weld_vertices(&weld_map)
+    }
+
+    /// Merges selected vertices that are within `threshold` of each other. Each group of close
+    /// vertices collapses into the one that comes first in the mesh, which keeps its position.
+    /// Returns the number of vertices removed.
+    pub fn merge_vertices_by_distance(&mut self, threshold: Float) -> usize {
+        let selected = self.get_selected_vertices();
+        let positions: Vec<Point3> = selected
+            .iter()
+            .map(|v| v.as_ref().borrow().get_position())
+            .collect();
+
+        let mut spatial_hash = SpatialHash::new(threshold);
+        for (i, position) in positions.iter().enumerate() {
+            spatial_hash.insert(i, position);
+        }
+
+        // Group the vertices with a union-find, so that chains of close vertices end up
+        // together even if the ends of the chain are further apart than the threshold.
+        let mut parents: Vec<usize> = (0..selected.len()).collect();
+        fn find(parents: &mut [usize], i: usize) -> usize {
+            let mut root = i;
+            while parents[root] != root {
+                root = parents[root];
+            }
+            parents[i] = root;
+            root
+        }
+
+        for (i, position) in positions.iter().enumerate() {
+            for j in spatial_hash.get_candidates(position) {
+                if j <= i || position.distance(&positions[j]) > threshold {
+                    continue;
+                }
+
+                let root_i = find(&mut parents, i);
+                let root_j = find(&mut parents, j);
+                if root_i != root_j {
+                    parents[root_i.max(root_j)] = root_i.min(root_j);
+                }
+            }
+        }
+
+        // Selection order is not meaningful here, so let the vertex that comes first in the
+        // mesh survive.
+        let mesh_order: HashMap<u32, usize> = self
+            .vertices
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (v.as_ref().borrow().get_id(), i))
+            .collect();
+        let mut survivors: HashMap<usize, Rc<RefCell<BMeshVertex>>> = HashMap::new();
+        for i in 0..selected.len() {
+            let root = find(&mut parents, i);
+            let survivor = survivors.entry(root).or_insert(selected[i].clone());
+            let survivor_order = mesh_order[&survivor.as_ref().borrow().get_id()];
+            if mesh_order[&selected[i].as_ref().borrow().get_id()] < survivor_order {
+                *survivor = selected[i].clone();
+            }
+        }
+
+        let mut weld_map: HashMap<u32, Rc<RefCell<BMeshVertex>>> = HashMap::new();
+        for i in 0..selected.len() {
+            let survivor = survivors[&find(&mut parents, i)].clone();
+            let id = selected[i].as_ref().borrow().get_id();
+            if id != survivor.as_ref().borrow().get_id() {
+                weld_map.insert(id, survivor);
+            }
+        }
+
+        self.weld_vertices(&weld_map)
+    }
+
+    /// Replaces every vertex whose id is a key of `weld_map` with the vertex it maps to, and
+    /// cleans up the topology that this leaves behind. The vertices being replaced are removed
+    /// from the mesh. Returns the number of vertices removed.
+    pub(super) fn weld_vertices(
+        &mut self,
+        weld_map: &HashMap<u32, Rc<RefCell<BMeshVertex>>>,
+    ) -> usize {
+        if weld_map.is_empty() {
+            return 0;
+        }
+
+        let remap = |vertex: Rc<RefCell<BMeshVertex>>| -> Rc<RefCell<BMeshVertex>> {
+            let id = vertex.as_ref().borrow().get_id();
+            weld_map.get(&id).cloned().unwrap_or(vertex)
+        };
+
+        // Edges. Untouched edges stay in the lookup table, so checking the table tells us if a
+        // remapped edge now doubles an existing one.
+        let mut edges = Vec::with_capacity(self.edges.len());
+        for edge in self.edges.drain(..) {
+            let (v0, v1, v0_id, v1_id) = {
+                let e = edge.as_ref().borrow();
+                (e.get_v0(), e.get_v1(), e.get_v0_id(), e.get_v1_id())
+            };
+            if !weld_map.contains_key(&v0_id) && !weld_map.contains_key(&v1_id) {
+                edges.push(edge);
+                continue;
+            }
+
+            self.edge_lookup_table.remove_edge(v0_id, v1_id);
+
+            let new_v0 = remap(v0);
+            let new_v1 = remap(v1);
+            let new_v0_id = new_v0.as_ref().borrow().get_id();
+            let new_v1_id = new_v1.as_ref().borrow().get_id();
+            if new_v0_id == new_v1_id {
+                // Collapsed to a point.
+                continue;
+            }
+            if self
+                .edge_lookup_table
+                .get_edge(new_v0_id, new_v1_id)
+                .is_some()
+            {
+                // Doubles an edge we already have.
+                continue;
+            }
+
+            edge.as_ref().borrow_mut().set_vertices(new_v0, new_v1);
+            self.edge_lookup_table.insert_edge(edge.clone());
+            edges.push(edge);
+        }
+        self.edges = edges;
+
+        // Faces
+        let mut seen_faces: HashSet<Vec<u32>> = HashSet::new();
+        let mut faces = Vec::with_capacity(self.faces.len());
+        for face in self.faces.drain(..) {
+            let old_vertices = face.as_ref().borrow().get_vertices();
+            let mut new_vertices: Vec<Rc<RefCell<BMeshVertex>>> = Vec::new();
+            for vertex in old_vertices.iter() {
+                let vertex = remap(vertex.clone());
+                let is_repeat = new_vertices.last().is_some_and(|last| {
+                    last.as_ref().borrow().get_id() == vertex.as_ref().borrow().get_id()
+                });
+                if !is_repeat {
+                    new_vertices.push(vertex);
+                }
+            }
+            // The loop wraps around, so the last corner may have merged into the first.
+            while new_vertices.len() > 1
+                && new_vertices.first().unwrap().as_ref().borrow().get_id()
+                    == new_vertices.last().unwrap().as_ref().borrow().get_id()
+            {
+                new_vertices.pop();
+            }
+
+            // Corners that weren't next to each other may have merged too, e.g. the opposite
+            // corners of a quad, leaving the face going through the same vertex twice. It is
+            // split into a face on either side of that vertex.
+            let loops = split_at_repeated_vertices(new_vertices);
+            let is_split = loops.len() > 1;
+            let mut face = Some(face);
+            for new_vertices in loops {
+                if new_vertices.len() < 3 {
+                    // Degenerate.
+                    continue;
+                }
+
+                let mut key: Vec<u32> = new_vertices
+                    .iter()
+                    .map(|v| v.as_ref().borrow().get_id())
+                    .collect();
+                key.sort_unstable();
+                if !seen_faces.insert(key) {
+                    // Two faces collapsed onto the same vertices.
+                    continue;
+                }
+
+                // The first loop keeps the face, the others get new ones. Their edges are all
+                // sides of the face already.
+                match face.take() {
+                    Some(face) => {
+                        if is_split
+                            || new_vertices.len() != old_vertices.len()
+                            || weld_affects(&old_vertices, weld_map)
+                        {
+                            face.as_ref()
+                                .borrow_mut()
+                                .set_edge_loop(BMeshEdgeLoop::new(new_vertices));
+                        }
+                        faces.push(face);
+                    }
+                    None => {
+                        let strategy =
+                            TesselationStrategy::from_vertex_count(new_vertices.len()).unwrap();
+                        faces.push(BMeshFace::create(
+                            BMeshEdgeLoop::new(new_vertices),
+                            strategy,
+                        ));
+                    }
+                }
+            }
+        }
+        self.faces = faces;
+
+        // Vertices
+        let vertex_count = self.vertices.len();
+        self.vertices
+            .retain(|v| !weld_map.contains_key(&v.as_ref().borrow().get_id()));
+        for id in weld_map.keys() {
+            self.vertex_lookup_table.remove(id);
+            self.selection_set.remove(id);
+        }
+        self.selection_history
+            .retain(|id| !weld_map.contains_key(id));
+
+        self.rebuild_tesselation();
+
+        vertex_count - self.vertices.len()
+    }
+}
+
+/// Splits a loop of vertices that goes through some vertex more than once into loops that go
+/// through each of their vertices once, e.g. `[a, b, c, a, d, e]` into `[a, b, c]` and
+/// `[a, d, e]`. The loops can be shorter than three vertices.
+fn split_at_repeated_vertices(
+    vertices: Vec<Rc<RefCell<BMeshVertex>>>,
+) -> Vec<Vec<Rc<RefCell<BMeshVertex>>>> {
+    let ids: Vec<u32> = vertices
+        .iter()
+        .map(|v| v.as_ref().borrow().get_id())
+        .collect();
+    for j in 1..ids.len() {
+        if let Some(i) = ids[..j].iter().position(|id| *id == ids[j]) {
+            let inner = vertices[i..j].to_vec();
+            let outer = vertices[j..]
+                .iter()
+                .chain(&vertices[..i])
+                .cloned()
+                .collect();
+            let mut loops = split_at_repeated_vertices(inner);
+            loops.extend(split_at_repeated_vertices(outer));
+            return loops;
+        }
+    }
+
+    vec![vertices]
+}
+
+fn weld_affects(
+    vertices: &[Rc<RefCell<BMeshVertex>>],
+    weld_map: &HashMap<u32, Rc<RefCell<BMeshVertex>>>,
+) -> bool {
+    vertices
+        .iter()
+        .any(|v| weld_map.contains_key(&v.as_ref().borrow().get_id()))
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+
+    /// A mesh with a single face going around the points, with the vertices at the given
+    /// indices selected.
+    fn create_face(points: &[Point3], selected: &[usize]) -> BMesh {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let polygon: Vec<usize> = (0..points.len()).collect();
+        let mut mesh = BMesh::from_polygons(&mut rng, points, &[polygon]);
+        for &i in selected {
+            let id = mesh.get_vertices()[i].as_ref().borrow().get_id();
+            mesh.select_vertex(id);
+        }
+        mesh
+    }
+
+    #[test]
+    fn merging_opposite_corners_of_a_quad_removes_it() {
+        let points = [
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, 0.0, 0.0),
+            Point3::new(1.0, 1.0, 0.0),
+            Point3::new(0.0, 1.0, 0.0),
+        ];
+        let mut mesh = create_face(&points, &[0, 2]);
+
+        assert_eq!(mesh.merge_selected_vertices(MergeTarget::First), 1);
+        assert!(mesh.get_faces().is_empty());
+        assert!(mesh.validate().is_empty());
+    }
+
+    #[test]
+    fn merging_non_adjacent_corners_splits_the_face() {
+        let points = [
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, -1.0, 0.0),
+            Point3::new(2.0, 0.0, 0.0),
+            Point3::new(3.0, 0.0, 0.0),
+            Point3::new(2.0, 1.0, 0.0),
+            Point3::new(1.0, 1.0, 0.0),
+        ];
+        // Corners 0 and 3 merge at the center, leaving a triangle on either side.
+        let mut mesh = create_face(&points, &[0, 3]);
+
+        assert_eq!(mesh.merge_selected_vertices(MergeTarget::Center), 1);
+        let faces = mesh.get_faces();
+        assert_eq!(faces.len(), 2);
+        assert!(faces
+            .iter()
+            .all(|f| f.as_ref().borrow().vertex_count() == 3));
+        assert!(mesh.validate().is_empty());
+    }
+}
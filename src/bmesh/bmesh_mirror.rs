@@ -0,0 +1,256 @@
+//! Finding which vertex mirrors which across an axis plane, and editing with symmetry.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::math::{point::Point3, vector::Vector3, Float};
+
+use super::{bmesh::BMesh, spatial_hash::SpatialHash};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum MirrorAxis {
+    X,
+    Y,
+    Z,
+}
+
+impl MirrorAxis {
+    /// Reflects the point across the plane through the origin perpendicular to the axis.
+    pub fn reflect(&self, p: &Point3) -> Point3 {
+        match self {
+            Self::X => Point3::new(-p.x(), p.y(), p.z()),
+            Self::Y => Point3::new(p.x(), -p.y(), p.z()),
+            Self::Z => Point3::new(p.x(), p.y(), -p.z()),
+        }
+    }
+
+    /// How far the point is from the mirror plane, negative on one side and positive on the
+    /// other.
+    pub fn signed_distance(&self, p: &Point3) -> Float {
+        match self {
+            Self::X => p.x(),
+            Self::Y => p.y(),
+            Self::Z => p.z(),
+        }
+    }
+
+    /// The point moved onto the mirror plane.
+    pub fn project(&self, p: &Point3) -> Point3 {
+        *p - self.normal() * self.signed_distance(p)
+    }
+
+    pub fn normal(&self) -> Vector3 {
+        match self {
+            Self::X => Vector3::unit_x(),
+            Self::Y => Vector3::unit_y(),
+            Self::Z => Vector3::unit_z(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum MirrorMatching {
+    /// A vertex's counterpart is the vertex closest to its reflection (within the threshold).
+    Positional,
+    /// Counterparts are found by walking the edges outwards from the vertices on the mirror
+    /// plane, so that they are still found after one side has been moved out of symmetry.
+    /// Positions only break ties between vertices that look the same topologically.
+    Topological,
+}
+
+/// Keeps a mesh symmetric while editing it: moving a vertex moves its counterpart as well.
+#[derive(Clone, Copy)]
+pub struct Symmetry {
+    pub axis: MirrorAxis,
+    pub matching: MirrorMatching,
+    /// How far a vertex may be from the reflection of its counterpart (or from the mirror
+    /// plane, to be its own counterpart).
+    pub threshold: Float,
+}
+
+impl BMesh {
+    /// Pairs up vertices that mirror each other across the plane perpendicular to `axis`. The
+    /// map goes both ways, and vertices on the plane map to themselves. Vertices without a
+    /// counterpart are left out.
+    pub fn get_mirror_pairs(
+        &self,
+        axis: MirrorAxis,
+        matching: MirrorMatching,
+        threshold: Float,
+    ) -> HashMap<u32, u32> {
+        match matching {
+            MirrorMatching::Positional => self.get_positional_mirror_pairs(axis, threshold),
+            MirrorMatching::Topological => self.get_topological_mirror_pairs(axis, threshold),
+        }
+    }
+
+    fn get_positional_mirror_pairs(&self, axis: MirrorAxis, threshold: Float) -> HashMap<u32, u32> {
+        let positions: Vec<Point3> = self
+            .vertices
+            .iter()
+            .map(|v| v.as_ref().borrow().get_position())
+            .collect();
+        let mut spatial_hash = SpatialHash::new(threshold);
+        for (i, position) in positions.iter().enumerate() {
+            spatial_hash.insert(i, position);
+        }
+
+        let mut pairs = HashMap::new();
+        for (i, position) in positions.iter().enumerate() {
+            let reflected = axis.reflect(position);
+            let closest = spatial_hash
+                .get_candidates(&reflected)
+                .into_iter()
+                .map(|j| (j, positions[j].distance(&reflected)))
+                .filter(|(_, distance)| *distance <= threshold)
+                .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap());
+
+            if let Some((j, _)) = closest {
+                let id = self.vertices[i].as_ref().borrow().get_id();
+                let counterpart_id = self.vertices[j].as_ref().borrow().get_id();
+                pairs.insert(id, counterpart_id);
+            }
+        }
+
+        // Keep only the pairs where both vertices agree, so that the map goes both ways.
+        pairs
+            .iter()
+            .filter(|(a, b)| pairs.get(b) == Some(a))
+            .map(|(a, b)| (*a, *b))
+            .collect()
+    }
+
+    fn get_topological_mirror_pairs(
+        &self,
+        axis: MirrorAxis,
+        threshold: Float,
+    ) -> HashMap<u32, u32> {
+        let positions: HashMap<u32, Point3> = self
+            .vertices
+            .iter()
+            .map(|v| {
+                let v = v.as_ref().borrow();
+                (v.get_id(), v.get_position())
+            })
+            .collect();
+        let neighbours = |id: u32| -> Vec<u32> {
+            self.edge_lookup_table
+                .get_edges_of_vertex(id)
+                .iter()
+                .map(|e| e.as_ref().borrow().other_vertex(id).as_ref().borrow().get_id())
+                .collect()
+        };
+        let side = |id: u32| -> i32 {
+            let distance = axis.signed_distance(&positions[&id]);
+            if distance.abs() <= threshold {
+                0
+            } else {
+                distance.signum() as i32
+            }
+        };
+
+        // Start from the vertices on the plane, or from the vertices that are still symmetric
+        // if there are none (e.g. the halves aren't connected).
+        let mut pairs: HashMap<u32, u32> = positions
+            .keys()
+            .filter(|id| side(**id) == 0)
+            .map(|id| (*id, *id))
+            .collect();
+        if pairs.is_empty() {
+            pairs = self.get_positional_mirror_pairs(axis, threshold);
+        }
+
+        let mut queue: VecDeque<(u32, u32)> = pairs.iter().map(|(a, b)| (*a, *b)).collect();
+        while let Some((a, a_mirror)) = queue.pop_front() {
+            let a_neighbours: Vec<u32> = neighbours(a)
+                .into_iter()
+                .filter(|n| !pairs.contains_key(n))
+                .collect();
+            let mut candidates: HashSet<u32> = neighbours(a_mirror)
+                .into_iter()
+                .filter(|n| !pairs.contains_key(n))
+                .collect();
+
+            for n in a_neighbours {
+                // The counterpart has to be on the other side, with as many edges. If that
+                // still leaves more than one, take the one closest to the reflection.
+                let valence = neighbours(n).len();
+                let reflected = axis.reflect(&positions[&n]);
+                let best = candidates
+                    .iter()
+                    .filter(|m| side(**m) == -side(n) && neighbours(**m).len() == valence)
+                    .filter(|m| side(n) == 0 || **m != n)
+                    .min_by(|m0, m1| {
+                        let d0 = positions[*m0].distance(&reflected);
+                        let d1 = positions[*m1].distance(&reflected);
+                        d0.partial_cmp(&d1).unwrap()
+                    })
+                    .copied();
+
+                if let Some(m) = best {
+                    candidates.remove(&m);
+                    pairs.insert(n, m);
+                    pairs.insert(m, n);
+                    queue.push_back((n, m));
+                }
+            }
+        }
+
+        pairs
+    }
+
+    /// Moves the vertices to the given positions. With `symmetry`, the counterparts of the
+    /// vertices are moved to the reflected positions as well (unless they are being moved
+    /// themselves), and vertices on the mirror plane stay on it.
+    pub fn set_vertex_positions(&mut self, positions: &[(u32, Point3)], symmetry: Option<&Symmetry>) {
+        let pairs = symmetry.map(|s| self.get_mirror_pairs(s.axis, s.matching, s.threshold));
+        let moved: HashSet<u32> = positions.iter().map(|(id, _)| *id).collect();
+
+        for (id, position) in positions {
+            let vertex = match self.get_vertex(*id) {
+                Some(vertex) => vertex,
+                None => continue,
+            };
+
+            let (Some(symmetry), Some(pairs)) = (symmetry, &pairs) else {
+                vertex.as_ref().borrow_mut().set_position(*position);
+                continue;
+            };
+
+            match pairs.get(id) {
+                Some(counterpart_id) if counterpart_id == id => {
+                    vertex
+                        .as_ref()
+                        .borrow_mut()
+                        .set_position(symmetry.axis.project(position));
+                }
+                Some(counterpart_id) => {
+                    vertex.as_ref().borrow_mut().set_position(*position);
+                    if !moved.contains(counterpart_id) {
+                        let counterpart = self.get_vertex(*counterpart_id).unwrap();
+                        counterpart
+                            .as_ref()
+                            .borrow_mut()
+                            .set_position(symmetry.axis.reflect(position));
+                    }
+                }
+                None => vertex.as_ref().borrow_mut().set_position(*position),
+            }
+        }
+
+        self.rebuild_tesselation();
+    }
+
+    /// Moves the selected vertices by `offset`. See `set_vertex_positions()` for `symmetry`.
+    pub fn translate_selected(&mut self, offset: Vector3, symmetry: Option<&Symmetry>) {
+        let positions: Vec<(u32, Point3)> = self
+            .get_selected_vertices()
+            .iter()
+            .map(|v| {
+                let v = v.as_ref().borrow();
+                (v.get_id(), v.get_position() + offset)
+            })
+            .collect();
+
+        self.set_vertex_positions(&positions, symmetry);
+    }
+}
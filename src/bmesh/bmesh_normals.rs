@@ -0,0 +1,233 @@
+//! Normals of the faces and vertices, and the split normals used for shading.
+//!
+//! Each corner of a face gets its own (split) normal. Corners around a vertex share a normal
+//! as long as the edges between their faces are smooth, so a vertex on a sharp edge ends up
+//! with a different normal on each side of it. An edge is sharp if it is marked as such, if it
+//! doesn't have exactly two faces, or if auto smooth is on and the angle between its faces is
+//! larger than the auto smooth angle.
+
+use std::collections::HashMap;
+
+use crate::math::{point::Point3, vector::Vector3, Float};
+
+use super::{bmesh::BMesh, bmesh_face::newell_normal};
+
+/// The normals of a mesh, as of the last time it changed (see `rebuild_tesselation()`).
+/// Faces are referred to by their index in `BMesh::get_faces()`.
+#[derive(Default)]
+pub struct BMeshNormals {
+    face_normals: Vec<Vector3>,
+    vertex_normals: HashMap<u32, Vector3>,
+    /// For each face, a normal per corner in the order of `BMeshFace::get_vertices()`.
+    corner_normals: Vec<Vec<Vector3>>,
+}
+
+impl BMesh {
+    pub fn get_face_normals(&self) -> &Vec<Vector3> {
+        &self.normals.face_normals
+    }
+
+    /// The normal of the vertex as if all of its edges were smooth.
+    pub fn get_vertex_normal(&self, vertex_id: u32) -> Option<Vector3> {
+        self.normals.vertex_normals.get(&vertex_id).copied()
+    }
+
+    /// The split normals, for each face one per corner.
+    pub fn get_corner_normals(&self) -> &Vec<Vec<Vector3>> {
+        &self.normals.corner_normals
+    }
+
+    /// The angle (in radians) between faces above which the edge between them is treated as
+    /// sharp, if auto smooth is on.
+    pub fn get_auto_smooth_angle(&self) -> Option<Float> {
+        self.auto_smooth_angle
+    }
+
+    pub fn set_auto_smooth_angle(&mut self, angle: Option<Float>) {
+        self.auto_smooth_angle = angle;
+        self.rebuild_tesselation();
+    }
+
+    /// Marks (or clears) the selected edges as sharp. Returns the number of edges changed.
+    pub fn set_selected_edges_sharp(&mut self, sharp: bool) -> usize {
+        let mut changed = 0;
+        for edge in self.get_selected_edges() {
+            let mut edge = edge.as_ref().borrow_mut();
+            if edge.is_sharp() != sharp {
+                edge.set_sharp(sharp);
+                changed += 1;
+            }
+        }
+
+        self.rebuild_tesselation();
+        changed
+    }
+
+    /// Computes all the normals from scratch. Face normals use Newell's method so that n-gons
+    /// that are concave or not quite planar get a sensible normal, and the normals around a
+    /// vertex are weighted by the angle of each face's corner, so that splitting a face into
+    /// more triangles doesn't pull the normal towards it.
+    pub(super) fn recalculate_normals(&mut self) {
+        let faces: Vec<(Vec<u32>, Vec<Point3>)> = self
+            .faces
+            .iter()
+            .map(|face| {
+                let vertices = face.as_ref().borrow().get_vertices();
+                let ids = vertices.iter().map(|v| v.as_ref().borrow().get_id());
+                let positions = vertices.iter().map(|v| v.as_ref().borrow().get_position());
+                (ids.collect(), positions.collect())
+            })
+            .collect();
+
+        let face_normals: Vec<Vector3> = faces
+            .iter()
+            .map(|(_, positions)| newell_normal(positions).normalize())
+            .collect();
+
+        // How much each corner contributes to the normals around its vertex.
+        let corner_weights: Vec<Vec<Float>> = faces
+            .iter()
+            .map(|(_, positions)| {
+                let n = positions.len();
+                (0..n)
+                    .map(|i| {
+                        let p = positions[i];
+                        let to_previous = (positions[(i + n - 1) % n] - p).normalize();
+                        let to_next = (positions[(i + 1) % n] - p).normalize();
+                        Vector3::dot(to_previous, to_next).clamp(-1.0, 1.0).acos()
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut vertex_normals: HashMap<u32, Vector3> = HashMap::new();
+        for (face_index, (ids, _)) in faces.iter().enumerate() {
+            for (corner, id) in ids.iter().enumerate() {
+                let weighted = face_normals[face_index] * corner_weights[face_index][corner];
+                let normal = vertex_normals.entry(*id).or_insert_with(Vector3::zero);
+                *normal = *normal + weighted;
+            }
+        }
+        for normal in vertex_normals.values_mut() {
+            *normal = normal.normalize();
+        }
+
+        // Corners are numbered one face after the other. Corners of the same vertex that are
+        // connected through smooth edges end up in the same group, and share a normal.
+        let mut first_corner = Vec::with_capacity(faces.len());
+        let mut corner_count = 0;
+        for (ids, _) in &faces {
+            first_corner.push(corner_count);
+            corner_count += ids.len();
+        }
+        let mut groups = CornerGroups::new(corner_count);
+
+        let mut faces_of_edge: HashMap<(u32, u32), Vec<(usize, usize)>> = HashMap::new();
+        for (face_index, (ids, _)) in faces.iter().enumerate() {
+            for corner in 0..ids.len() {
+                let (a, b) = (ids[corner], ids[(corner + 1) % ids.len()]);
+                faces_of_edge
+                    .entry((a.min(b), a.max(b)))
+                    .or_default()
+                    .push((face_index, corner));
+            }
+        }
+
+        let max_cos = self.auto_smooth_angle.map(|angle| angle.cos());
+        for ((v0_id, v1_id), sides) in &faces_of_edge {
+            let [(face_a, corner_a), (face_b, corner_b)] = sides[..] else {
+                continue;
+            };
+            let is_marked_sharp = self
+                .edge_lookup_table
+                .get_edge(*v0_id, *v1_id)
+                .is_some_and(|edge| edge.as_ref().borrow().is_sharp());
+            let is_over_angle = max_cos.is_some_and(|max_cos| {
+                Vector3::dot(face_normals[face_a], face_normals[face_b]) < max_cos
+            });
+            if is_marked_sharp || is_over_angle {
+                continue;
+            }
+
+            // The side starts at `corner` and ends at the next corner of the face.
+            let corner_of = |face: usize, corner: usize, vertex_id: u32| {
+                let ids = &faces[face].0;
+                let offset = if ids[corner] == vertex_id {
+                    corner
+                } else {
+                    (corner + 1) % ids.len()
+                };
+                first_corner[face] + offset
+            };
+            for vertex_id in [*v0_id, *v1_id] {
+                groups.join(
+                    corner_of(face_a, corner_a, vertex_id),
+                    corner_of(face_b, corner_b, vertex_id),
+                );
+            }
+        }
+
+        let mut group_normals: HashMap<usize, Vector3> = HashMap::new();
+        for (face_index, weights) in corner_weights.iter().enumerate() {
+            for (corner, weight) in weights.iter().enumerate() {
+                let group = groups.find(first_corner[face_index] + corner);
+                let weighted = face_normals[face_index] * *weight;
+                let normal = group_normals.entry(group).or_insert_with(Vector3::zero);
+                *normal = *normal + weighted;
+            }
+        }
+        let corner_normals: Vec<Vec<Vector3>> = faces
+            .iter()
+            .enumerate()
+            .map(|(face_index, (ids, _))| {
+                (0..ids.len())
+                    .map(|corner| {
+                        let group = groups.find(first_corner[face_index] + corner);
+                        group_normals[&group].normalize()
+                    })
+                    .collect()
+            })
+            .collect();
+
+        self.normals = BMeshNormals {
+            face_normals,
+            vertex_normals,
+            corner_normals,
+        };
+    }
+}
+
+/// Groups of corners that share a normal, merged as smooth edges are found (a union-find).
+struct CornerGroups {
+    parents: Vec<usize>,
+}
+
+impl CornerGroups {
+    fn new(corner_count: usize) -> Self {
+        Self {
+            parents: (0..corner_count).collect(),
+        }
+    }
+
+    fn find(&mut self, corner: usize) -> usize {
+        let mut root = corner;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+
+        // Point everything on the way straight at the root, so later lookups are quick.
+        let mut current = corner;
+        while self.parents[current] != root {
+            let next = self.parents[current];
+            self.parents[current] = root;
+            current = next;
+        }
+
+        root
+    }
+
+    fn join(&mut self, a: usize, b: usize) {
+        let (root_a, root_b) = (self.find(a), self.find(b));
+        self.parents[root_a] = root_b;
+    }
+}
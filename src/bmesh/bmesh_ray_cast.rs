@@ -0,0 +1,51 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::math::{point::Point3, ray::Ray, Float};
+
+use super::{bmesh::BMesh, bmesh_face::BMeshFace};
+
+pub struct BMeshRayHit {
+    pub face: Rc<RefCell<BMeshFace>>,
+    pub position: Point3,
+    /// How far along the ray the hit is.
+    pub distance: Float,
+}
+
+impl BMesh {
+    /// The closest face the ray hits, if any. This checks every triangle of the tesselation,
+    /// which is fine for picking a handful of times per frame.
+    pub fn ray_cast(&self, ray: &Ray) -> Option<BMeshRayHit> {
+        let mut closest: Option<BMeshRayHit> = None;
+        for face in &self.faces {
+            for [a, b, c] in face.as_ref().borrow().get_triangles() {
+                let distance = match ray.intersect_triangle(a, b, c) {
+                    Some(distance) => distance,
+                    None => continue,
+                };
+                if closest.as_ref().is_none_or(|hit| distance < hit.distance) {
+                    closest = Some(BMeshRayHit {
+                        face: face.clone(),
+                        position: ray.at(distance),
+                        distance,
+                    });
+                }
+            }
+        }
+
+        closest
+    }
+
+    /// Whether the point can be seen from `eye`, i.e. there is no face in between. Points on
+    /// the surface are visible as long as the face they are on is the first one hit.
+    pub fn is_point_visible(&self, eye: Point3, point: Point3) -> bool {
+        let distance = eye.distance(&point);
+        match self.ray_cast(&Ray::between(eye, point)) {
+            Some(hit) => hit.distance >= distance - VISIBILITY_TOLERANCE * distance.max(1.0),
+            None => true,
+        }
+    }
+}
+
+/// Relative to the distance of the point, so that points on the surface aren't hidden by the
+/// face they are on because of floating point error.
+const VISIBILITY_TOLERANCE: Float = 1e-3;
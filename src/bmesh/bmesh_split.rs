@@ -0,0 +1,122 @@
+//! Splitting edges and faces, the building blocks for operators that add topology to existing
+//! faces (e.g. the knife). Like the other low level operations, these don't update the
+//! tesselation.
+
+use std::{cell::RefCell, rc::Rc};
+
+use crate::math::Float;
+
+use super::{
+    bmesh::BMesh, bmesh_edge::BMeshEdgeLoop, bmesh_face::BMeshFace, bmesh_vertex::BMeshVertex,
+};
+
+impl BMesh {
+    /// Adds vertices along the edge between `v0` and `v1`, at each of the (increasing)
+    /// parameters in `ts`, where 0 is `v0` and 1 is `v1`. The faces using the edge get the new
+    /// vertices as extra corners. Returns the new vertices, going from `v0` to `v1`.
+    pub(super) fn split_edge(
+        &mut self,
+        v0_id: u32,
+        v1_id: u32,
+        ts: &[Float],
+        rng: &mut impl rand::Rng,
+    ) -> Vec<Rc<RefCell<BMeshVertex>>> {
+        let edge = match self.edge_lookup_table.get_edge(v0_id, v1_id) {
+            Some(edge) => edge,
+            None => return Vec::new(),
+        };
+        let (v0, v1) = {
+            let edge = edge.as_ref().borrow();
+            if edge.get_v0_id() == v0_id {
+                (edge.get_v0(), edge.get_v1())
+            } else {
+                (edge.get_v1(), edge.get_v0())
+            }
+        };
+
+        let p0 = v0.as_ref().borrow().get_position();
+        let p1 = v1.as_ref().borrow().get_position();
+        let new_vertices: Vec<Rc<RefCell<BMeshVertex>>> = ts
+            .iter()
+            .map(|t| BMeshVertex::create_from_position(p0.lerp(&p1, *t), rng))
+            .collect();
+        for vertex in &new_vertices {
+            self.add_vertex(vertex.clone());
+        }
+
+        let faces = self.get_faces_of_edge(v0_id, v1_id);
+        self.unlink_edge(v0_id, v1_id);
+
+        let mut chain = vec![v0.clone()];
+        chain.extend(new_vertices.iter().cloned());
+        chain.push(v1.clone());
+        for pair in chain.windows(2) {
+            self.get_or_create_edge(pair[0].clone(), pair[1].clone());
+        }
+
+        for face in faces {
+            let corners = face.as_ref().borrow().get_vertices();
+            let mut new_corners = Vec::with_capacity(corners.len() + new_vertices.len());
+            for i in 0..corners.len() {
+                let current = corners[i].as_ref().borrow().get_id();
+                let next = corners[(i + 1) % corners.len()].as_ref().borrow().get_id();
+                new_corners.push(corners[i].clone());
+                if current == v0_id && next == v1_id {
+                    new_corners.extend(new_vertices.iter().cloned());
+                } else if current == v1_id && next == v0_id {
+                    new_corners.extend(new_vertices.iter().rev().cloned());
+                }
+            }
+            face.as_ref()
+                .borrow_mut()
+                .set_edge_loop(BMeshEdgeLoop::new(new_corners));
+        }
+
+        new_vertices
+    }
+
+    /// Splits the face in two along a new edge between two of its corners. Returns whether the
+    /// face was split; it is not if the vertices are not corners of the face or are next to each
+    /// other.
+    pub(super) fn split_face(
+        &mut self,
+        face: &Rc<RefCell<BMeshFace>>,
+        v0_id: u32,
+        v1_id: u32,
+    ) -> bool {
+        let corners = face.as_ref().borrow().get_vertices();
+        let ids = face.as_ref().borrow().get_vertex_ids();
+        let (i0, i1) = match (
+            ids.iter().position(|id| *id == v0_id),
+            ids.iter().position(|id| *id == v1_id),
+        ) {
+            (Some(i0), Some(i1)) => (i0, i1),
+            _ => return false,
+        };
+
+        let n = corners.len();
+        let is_adjacent = (i0 + 1) % n == i1 || (i1 + 1) % n == i0;
+        if i0 == i1 || is_adjacent {
+            return false;
+        }
+
+        // Going around the face from one corner to the other, and then back.
+        let walk = |from: usize, to: usize| -> Vec<Rc<RefCell<BMeshVertex>>> {
+            let mut walked = vec![corners[from].clone()];
+            let mut i = from;
+            while i != to {
+                i = (i + 1) % n;
+                walked.push(corners[i].clone());
+            }
+            walked
+        };
+        let first_half = walk(i0, i1);
+        let second_half = walk(i1, i0);
+
+        self.kill_face(face);
+        self.create_face(first_half);
+        self.create_face(second_half);
+
+        true
+    }
+}
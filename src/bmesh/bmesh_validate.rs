@@ -0,0 +1,236 @@
+//! Checking that a mesh is consistent, to catch operators that leave it in a broken state.
+//!
+//! In debug builds this runs after every operator (see `rebuild_tesselation()`), and the
+//! problems found are logged.
+
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use log::warn;
+
+use crate::math::{point::Point3, Float};
+
+use super::{bmesh::BMesh, bmesh_face::newell_normal};
+
+/// Faces with a smaller area than this are reported as having no area.
+const ZERO_AREA_THRESHOLD: Float = 1e-8;
+
+/// Something wrong with a mesh. Elements are referred to by the ids of their vertices, since
+/// that is also how they are selected.
+#[derive(Clone, Debug)]
+pub enum MeshProblem {
+    /// An edge that is a side of more than two faces.
+    NonManifoldEdge {
+        v0_id: u32,
+        v1_id: u32,
+        face_count: usize,
+    },
+    /// More than one edge between the same two vertices. Only one of them can be in the lookup
+    /// table, so the others are invisible to anything looking edges up.
+    DuplicateEdge {
+        v0_id: u32,
+        v1_id: u32,
+        count: usize,
+    },
+    /// An edge in the mesh that the lookup table doesn't know about, or knows as another edge.
+    UnindexedEdge { v0_id: u32, v1_id: u32 },
+    /// A face with fewer than three corners, or that goes through the same vertex twice.
+    DegenerateFace { vertex_ids: Vec<u32> },
+    /// A face whose corners are all (nearly) on a line or at the same position.
+    ZeroAreaFace { vertex_ids: Vec<u32> },
+    /// Two consecutive corners of a face without an edge between them.
+    MissingFaceEdge {
+        vertex_ids: Vec<u32>,
+        v0_id: u32,
+        v1_id: u32,
+    },
+    /// A face going through a vertex that is not in the mesh.
+    UnknownFaceVertex {
+        vertex_ids: Vec<u32>,
+        vertex_id: u32,
+    },
+    /// A vertex that is not an endpoint of any edge.
+    IsolatedVertex { vertex_id: u32 },
+}
+
+impl MeshProblem {
+    /// A short description of the problem, for showing to the user.
+    pub fn get_description(&self) -> String {
+        match self {
+            Self::NonManifoldEdge { face_count, .. } => {
+                format!("Edge with {} faces", face_count)
+            }
+            Self::DuplicateEdge { count, .. } => {
+                format!("{} edges between the same vertices", count)
+            }
+            Self::UnindexedEdge { .. } => "Edge missing from the lookup table".to_string(),
+            Self::DegenerateFace { vertex_ids } => {
+                format!("Degenerate face with {} corners", vertex_ids.len())
+            }
+            Self::ZeroAreaFace { vertex_ids } => {
+                format!("Face with no area, {} corners", vertex_ids.len())
+            }
+            Self::MissingFaceEdge { vertex_ids, .. } => {
+                format!("Face side without an edge, {} corners", vertex_ids.len())
+            }
+            Self::UnknownFaceVertex { vertex_id, .. } => {
+                format!("Face using vertex {}, which is not in the mesh", vertex_id)
+            }
+            Self::IsolatedVertex { .. } => "Isolated vertex".to_string(),
+        }
+    }
+
+    /// The vertices of the elements involved, which is what gets selected to show the problem.
+    pub fn get_vertex_ids(&self) -> Vec<u32> {
+        match self {
+            Self::NonManifoldEdge { v0_id, v1_id, .. }
+            | Self::DuplicateEdge { v0_id, v1_id, .. }
+            | Self::UnindexedEdge { v0_id, v1_id }
+            | Self::MissingFaceEdge { v0_id, v1_id, .. } => vec![*v0_id, *v1_id],
+            Self::DegenerateFace { vertex_ids } | Self::ZeroAreaFace { vertex_ids } => {
+                vertex_ids.clone()
+            }
+            Self::UnknownFaceVertex { vertex_ids, .. } => vertex_ids.clone(),
+            Self::IsolatedVertex { vertex_id } => vec![*vertex_id],
+        }
+    }
+}
+
+impl BMesh {
+    /// Checks the mesh for problems in its topology. An empty list means the mesh is
+    /// consistent, although not necessarily closed.
+    pub fn validate(&self) -> Vec<MeshProblem> {
+        let mut problems = Vec::new();
+
+        // Edges, keyed by their vertices regardless of direction.
+        let edge_key = |v0_id: u32, v1_id: u32| (v0_id.min(v1_id), v0_id.max(v1_id));
+        let mut edge_counts: HashMap<(u32, u32), usize> = HashMap::new();
+        for edge in &self.edges {
+            let (v0_id, v1_id) = {
+                let edge = edge.as_ref().borrow();
+                (edge.get_v0_id(), edge.get_v1_id())
+            };
+            *edge_counts.entry(edge_key(v0_id, v1_id)).or_default() += 1;
+
+            let is_indexed = self
+                .edge_lookup_table
+                .get_edge(v0_id, v1_id)
+                .is_some_and(|indexed| Rc::ptr_eq(&indexed, edge));
+            if !is_indexed {
+                problems.push(MeshProblem::UnindexedEdge { v0_id, v1_id });
+            }
+        }
+        for ((v0_id, v1_id), count) in &edge_counts {
+            if *count > 1 {
+                problems.push(MeshProblem::DuplicateEdge {
+                    v0_id: *v0_id,
+                    v1_id: *v1_id,
+                    count: *count,
+                });
+            }
+        }
+
+        let vertex_ids: HashSet<u32> = self
+            .vertices
+            .iter()
+            .map(|v| v.as_ref().borrow().get_id())
+            .collect();
+
+        let mut face_counts: HashMap<(u32, u32), usize> = HashMap::new();
+        for face in &self.faces {
+            let face = face.as_ref().borrow();
+            let ids = face.get_vertex_ids();
+
+            let distinct: HashSet<u32> = ids.iter().copied().collect();
+            if ids.len() < 3 || distinct.len() != ids.len() {
+                problems.push(MeshProblem::DegenerateFace {
+                    vertex_ids: ids.clone(),
+                });
+            } else {
+                let positions: Vec<Point3> = face
+                    .get_vertices()
+                    .iter()
+                    .map(|v| v.as_ref().borrow().get_position())
+                    .collect();
+                // The Newell normal is twice the area of the face.
+                if newell_normal(&positions).length() / 2.0 < ZERO_AREA_THRESHOLD {
+                    problems.push(MeshProblem::ZeroAreaFace {
+                        vertex_ids: ids.clone(),
+                    });
+                }
+            }
+
+            for id in &ids {
+                if !vertex_ids.contains(id) {
+                    problems.push(MeshProblem::UnknownFaceVertex {
+                        vertex_ids: ids.clone(),
+                        vertex_id: *id,
+                    });
+                }
+            }
+
+            for (v0_id, v1_id) in face.get_half_edges() {
+                if v0_id == v1_id {
+                    continue;
+                }
+                *face_counts.entry(edge_key(v0_id, v1_id)).or_default() += 1;
+                if self.edge_lookup_table.get_edge(v0_id, v1_id).is_none() {
+                    problems.push(MeshProblem::MissingFaceEdge {
+                        vertex_ids: ids.clone(),
+                        v0_id,
+                        v1_id,
+                    });
+                }
+            }
+        }
+        for ((v0_id, v1_id), face_count) in face_counts {
+            if face_count > 2 {
+                problems.push(MeshProblem::NonManifoldEdge {
+                    v0_id,
+                    v1_id,
+                    face_count,
+                });
+            }
+        }
+
+        for vertex in &self.vertices {
+            let vertex_id = vertex.as_ref().borrow().get_id();
+            if self
+                .edge_lookup_table
+                .get_edges_of_vertex(vertex_id)
+                .is_empty()
+            {
+                problems.push(MeshProblem::IsolatedVertex { vertex_id });
+            }
+        }
+
+        problems
+    }
+
+    /// Selects the elements involved in the problem, and nothing else.
+    pub fn select_problem(&mut self, problem: &MeshProblem) {
+        self.deselect_all();
+        for vertex_id in problem.get_vertex_ids() {
+            self.select_vertex(vertex_id);
+        }
+    }
+
+    /// Logs the problems with the mesh, if there are any. Used in debug builds after every
+    /// operator, so that broken topology shows up right where it is made.
+    pub(super) fn log_problems(&self) {
+        let problems = self.validate();
+        if problems.is_empty() {
+            return;
+        }
+
+        warn!("the mesh has {} problem(s):", problems.len());
+        for problem in problems.iter().take(10) {
+            warn!("    {}: {:?}", problem.get_description(), problem);
+        }
+        if problems.len() > 10 {
+            warn!("    and {} more", problems.len() - 10);
+        }
+    }
+}
@@ -5,6 +5,7 @@ use crate::math::point::Point3;
 pub struct BMeshVertex {
     id: u32,
     position: Point3,
+    selected: bool,
 }
 
 impl BMeshVertex {
@@ -12,10 +13,27 @@ impl BMeshVertex {
         Rc::new(RefCell::new(Self {
             id: rng.next_u32(),
             position,
+            selected: false,
         }))
     }
 
     pub fn get_id(&self) -> u32 {
         self.id
     }
+
+    pub fn get_position(&self) -> Point3 {
+        self.position
+    }
+
+    pub fn set_position(&mut self, position: Point3) {
+        self.position = position;
+    }
+
+    pub fn is_selected(&self) -> bool {
+        self.selected
+    }
+
+    pub(super) fn set_selected(&mut self, selected: bool) {
+        self.selected = selected;
+    }
 }
@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+
+use crate::math::{point::Point3, Float};
+
+/// Buckets points into a uniform grid of cubic cells, so that finding the points close to a
+/// given one only means looking at the surrounding cells rather than at every point. This is
+/// what keeps distance based operators (e.g. merging by distance) usable on large meshes.
+///
+/// The hash stores indices, so it is up to the caller to keep track of what they refer to.
+pub struct SpatialHash {
+    cell_size: Float,
+    cells: HashMap<(i64, i64, i64), Vec<usize>>,
+}
+
+impl SpatialHash {
+    /// `cell_size` should be at least the largest distance that will be queried, since queries
+    /// only look one cell away in each direction.
+    pub fn new(cell_size: Float) -> Self {
+        Self {
+            cell_size: Float::max(cell_size, Float::EPSILON),
+            cells: HashMap::new(),
+        }
+    }
+
+    fn cell_of(&self, point: &Point3) -> (i64, i64, i64) {
+        (
+            (point.x() / self.cell_size).floor() as i64,
+            (point.y() / self.cell_size).floor() as i64,
+            (point.z() / self.cell_size).floor() as i64,
+        )
+    }
+
+    pub fn insert(&mut self, index: usize, point: &Point3) {
+        let cell = self.cell_of(point);
+        self.cells.entry(cell).or_default().push(index);
+    }
+
+    /// The indices in the cell containing `point` and in the 26 cells around it. This is a
+    /// superset of the indices within `cell_size` of the point, so the caller still needs to
+    /// check the actual distance.
+    pub fn get_candidates(&self, point: &Point3) -> Vec<usize> {
+        let (x, y, z) = self.cell_of(point);
+
+        let mut candidates = Vec::new();
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    if let Some(cell) = self.cells.get(&(x + dx, y + dy, z + dz)) {
+                        candidates.extend_from_slice(cell);
+                    }
+                }
+            }
+        }
+
+        candidates
+    }
+}
@@ -1,11 +1,15 @@
 use std::f64::consts;
 
-use crate::math::{
-    matrix::{Matrix3, Matrix4},
-    point::Point3,
-    quaternion::Quaternion,
-    vector::{Vector2, Vector3},
-    Degrees, Radians,
+use crate::{
+    math::{
+        matrix::{Matrix3, Matrix4},
+        point::Point3,
+        quaternion::Quaternion,
+        ray::Ray,
+        vector::{Vector2, Vector3, Vector4},
+        Degrees, Radians,
+    },
+    scene::SceneCamera,
 };
 
 #[rustfmt::skip]
@@ -16,6 +20,52 @@ pub const OPENGL_TO_WGPU_MATRIX: Matrix4 = Matrix4::new(
     0.0, 0.0, 0.5, 1.0,
 );
 
+/// How much closer one notch of the scroll wheel zooms in.
+const ZOOM_STEP: f32 = 1.2;
+
+/// The views a camera can be reset to. All of them look at the origin from the same distance.
+#[derive(Clone, Copy, PartialEq)]
+pub enum ViewPreset {
+    /// The view the app starts with.
+    Perspective,
+    /// Orthographic, looking down the Y axis.
+    Top,
+    /// Orthographic, looking down the Z axis.
+    Front,
+    /// Orthographic, looking down the X axis.
+    Right,
+}
+
+impl ViewPreset {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            ViewPreset::Perspective => "Perspective",
+            ViewPreset::Top => "Top",
+            ViewPreset::Front => "Front",
+            ViewPreset::Right => "Right",
+        }
+    }
+}
+
+/// Preferences for how the viewport cameras move.
+pub struct NavigationSettings {
+    /// Orbit around the center of the selection, rather than the point the camera was last
+    /// orbiting around.
+    pub orbit_around_selection: bool,
+    /// Zoom towards what is under the mouse, rather than towards the point the camera orbits
+    /// around.
+    pub zoom_to_mouse_position: bool,
+}
+
+impl NavigationSettings {
+    pub fn new() -> Self {
+        Self {
+            orbit_around_selection: false,
+            zoom_to_mouse_position: false,
+        }
+    }
+}
+
 pub struct Camera {
     view_info: ViewInfo,
     projection_info: ProjectionInfo,
@@ -79,12 +129,188 @@ impl Camera {
         self.rebuild_view_projection_matrix();
     }
 
-    pub fn get_z_near(&self) -> f32 {
-        self.projection_info.z_near
+    pub fn set_view_preset(&mut self, preset: ViewPreset) {
+        // The rotations take the axis looked down to the one pointing towards the camera.
+        let rotation = match preset {
+            ViewPreset::Perspective | ViewPreset::Front => Quaternion::identity(),
+            ViewPreset::Top => {
+                Quaternion::rotation_from_axis_angle(Vector3::unit_x(), Degrees(90.0))
+            }
+            ViewPreset::Right => {
+                Quaternion::rotation_from_axis_angle(Vector3::unit_y(), Degrees(-90.0))
+            }
+        };
+        let distance = self.get_orbit_distance();
+        self.view_info.z_offset = Point3::new(0.0, 0.0, distance);
+        self.view_info.current_rotation = rotation;
+        self.view_info.rotation_modifier = Quaternion::identity();
+        self.view_info.should_reverse = false;
+        self.view_info.rebuild_view_matrix();
+
+        // Orthographic views show as much of the scene around the look at point as the
+        // perspective one does.
+        let orthographic_half_height = (preset != ViewPreset::Perspective).then(|| {
+            let fov: Radians = Degrees(self.projection_info.vertical_fov).into();
+            distance * (fov.0 / 2.0).tan()
+        });
+        self.projection_info
+            .set_orthographic_half_height(orthographic_half_height);
+        self.rebuild_view_projection_matrix();
+    }
+
+    /// Puts the camera where the scene camera is, with the same lens. The camera keeps orbiting
+    /// at the same distance, around the point that far in front of the scene camera.
+    pub fn look_through(&mut self, scene_camera: &SceneCamera) {
+        self.set_pose(scene_camera.position, scene_camera.rotation);
+
+        self.projection_info.vertical_fov = scene_camera.get_vertical_fov();
+        self.projection_info.z_near = scene_camera.clip_start;
+        self.projection_info.z_far = scene_camera.clip_end;
+        self.projection_info.set_orthographic_half_height(None);
+        self.rebuild_view_projection_matrix();
+    }
+
+    /// Moves the scene camera to where this camera is, with the same field of view and clipping
+    /// planes. Its sensor size is kept.
+    pub fn align_scene_camera(&self, scene_camera: &mut SceneCamera) {
+        scene_camera.position = self.get_position();
+        scene_camera.rotation = self.get_rotation();
+        scene_camera.set_vertical_fov(self.projection_info.vertical_fov);
+        scene_camera.clip_start = self.projection_info.z_near;
+        scene_camera.clip_end = self.projection_info.z_far;
+    }
+
+    /// The rotation from the space of the camera, where it looks down -Z, to the world.
+    pub fn get_rotation(&self) -> Quaternion {
+        (self.view_info.current_rotation * self.view_info.rotation_modifier)
+            .invert()
+            .normalize()
+    }
+
+    /// Puts the camera at the position, turned by the rotation (like `get_rotation()`). It keeps
+    /// orbiting at the same distance, around the point that far in front of it.
+    pub fn set_pose(&mut self, position: Point3, rotation: Quaternion) {
+        let distance = self.get_orbit_distance();
+        let forward = rotation.rotate_vector(-Vector3::unit_z());
+        self.view_info.look_at = position + forward * distance;
+        self.view_info.z_offset = Point3::new(0.0, 0.0, distance);
+        self.view_info.current_rotation = rotation.invert();
+        self.view_info.rotation_modifier = Quaternion::identity();
+        self.view_info.rebuild_view_matrix();
+        self.solidify_view_info();
+        self.rebuild_view_projection_matrix();
+    }
+
+    /// How far the camera is from the point it orbits around.
+    pub fn get_orbit_distance(&self) -> f32 {
+        Vector3::from(self.view_info.z_offset).length()
+    }
+
+    /// Makes the camera orbit around the point from now on, without moving it. The point stays
+    /// where it is on the screen, which isn't necessarily the middle.
+    pub fn set_orbit_pivot(&mut self, pivot: Point3) {
+        let rotation = self.view_info.current_rotation * self.view_info.rotation_modifier;
+        let offset = rotation.rotate_vector(self.get_position() - pivot);
+        self.view_info.look_at = pivot;
+        self.view_info.z_offset = Point3::new(offset.x(), offset.y(), offset.z());
+        self.view_info.rebuild_view_matrix();
+        self.rebuild_view_projection_matrix();
+    }
+
+    /// Where the ray crosses the plane through the point the camera orbits around, facing the
+    /// camera.
+    pub fn get_point_at_orbit_depth(&self, ray: &Ray) -> Point3 {
+        let forward = self.get_rotation().rotate_vector(-Vector3::unit_z());
+        let distance = Vector3::dot(self.view_info.look_at - ray.origin, forward)
+            / Vector3::dot(ray.direction, forward);
+        ray.at(distance)
+    }
+
+    /// Zooms in (positive) or out by notches of the scroll wheel, towards the point or else
+    /// the point the camera orbits around. The point stays where it is on the screen.
+    /// Orthographic views get scaled, since moving the camera closer wouldn't show.
+    pub fn zoom(&mut self, scroll_notches: f32, toward: Option<Point3>) {
+        let factor = ZOOM_STEP.powf(-scroll_notches);
+        let toward = toward.unwrap_or(self.view_info.look_at);
+        self.view_info.look_at = toward + (self.view_info.look_at - toward) * factor;
+        self.view_info.z_offset =
+            Point3::origin() + Vector3::from(self.view_info.z_offset) * factor;
+        self.view_info.rebuild_view_matrix();
+
+        if let Some(half_height) = self.projection_info.orthographic_half_height {
+            self.projection_info
+                .set_orthographic_half_height(Some(half_height * factor));
+        }
+        self.rebuild_view_projection_matrix();
+    }
+
+    /// Half the height of what orthographic views show, or `None` for a perspective view.
+    pub fn get_orthographic_half_height(&self) -> Option<f32> {
+        self.projection_info.orthographic_half_height
+    }
+
+    pub fn set_orthographic_half_height(&mut self, value: Option<f32>) {
+        self.projection_info.set_orthographic_half_height(value);
+        self.rebuild_view_projection_matrix();
+    }
+
+    pub fn is_orthographic(&self) -> bool {
+        self.projection_info.orthographic_half_height.is_some()
+    }
+
+    /// Where the camera is in world space.
+    pub fn get_position(&self) -> Point3 {
+        self.view_info
+            .get_view_matrix()
+            .invert()
+            .unwrap()
+            .transform_point(Point3::origin())
     }
 
-    pub fn get_z_far(&self) -> f32 {
-        self.projection_info.z_far
+    /// The directions in world space that point right and up on the screen.
+    pub fn get_right_and_up(&self) -> (Vector3, Vector3) {
+        let camera_to_world = self.view_info.get_view_matrix().invert().unwrap();
+        let origin = camera_to_world.transform_point(Point3::origin());
+        let right = camera_to_world.transform_point(Point3::new(1.0, 0.0, 0.0)) - origin;
+        let up = camera_to_world.transform_point(Point3::new(0.0, 1.0, 0.0)) - origin;
+
+        (right.normalize(), up.normalize())
+    }
+}
+
+// Going between the screen and the world. Screen positions are in pixels relative to the top
+// left corner of the drawing region, as egui gives them to us.
+impl Camera {
+    /// The ray from the camera through the given point on the screen.
+    pub fn get_ray_through_screen_point(
+        &self,
+        screen_point: &Vector2,
+        screen_size: &Vector2,
+    ) -> Ray {
+        let ndc_x = 2.0 * screen_point.x() / screen_size.x() - 1.0;
+        let ndc_y = 1.0 - 2.0 * screen_point.y() / screen_size.y();
+
+        // The depth range is [0, 1] after `OPENGL_TO_WGPU_MATRIX`.
+        let inverse = self.get_view_projection_matrix_inverse();
+        let near = inverse.transform_point(Point3::new(ndc_x, ndc_y, 0.0));
+        let far = inverse.transform_point(Point3::new(ndc_x, ndc_y, 1.0));
+
+        Ray::between(near, far)
+    }
+
+    /// Where the point ends up on the screen, or `None` if it is behind the camera.
+    pub fn project_to_screen(&self, point: Point3, screen_size: &Vector2) -> Option<Vector2> {
+        let clip = self.get_view_projection_matrix() * Vector4::from_point(point);
+        if clip.w() <= 0.0 {
+            return None;
+        }
+
+        let ndc_x = clip.x() / clip.w();
+        let ndc_y = clip.y() / clip.w();
+        Some(Vector2::new(
+            (ndc_x + 1.0) * 0.5 * screen_size.x(),
+            (1.0 - ndc_y) * 0.5 * screen_size.y(),
+        ))
     }
 }
 
@@ -141,8 +367,10 @@ impl Camera {
 /// accidentally change one of these data points without changing
 /// the view matrix.
 struct ViewInfo {
-    /// How far the camera is from the look_at point, without accounting for
-    /// rotation.
+    /// Where the camera is relative to the look_at point, without accounting
+    /// for rotation. It is straight behind it, along +Z, unless the camera
+    /// orbits around a point away from the middle of the screen (see
+    /// `Camera::set_orbit_pivot()`).
     z_offset: Point3,
     /// The point the camera is pointing at / the center point.
     look_at: Point3,
@@ -167,10 +395,8 @@ impl ViewInfo {
 
         let current_rotation = Quaternion::identity();
         let rotation_modifier = Quaternion::identity();
-        let view_matrix = Self::build_view_matrix_from_rotation_and_offset(
-            current_rotation,
-            Vector3::from(z_offset) + Vector3::from(look_at),
-        );
+        let view_matrix =
+            Self::build_view_matrix(current_rotation, Vector3::from(z_offset), look_at);
 
         Self {
             z_offset,
@@ -182,20 +408,21 @@ impl ViewInfo {
         }
     }
 
-    fn build_view_matrix_from_rotation_and_offset(
-        rotation: Quaternion,
-        offset: Vector3,
-    ) -> Matrix4 {
+    /// Moves the look at point to the origin, rotates around it, and then moves the camera back
+    /// by the offset.
+    fn build_view_matrix(rotation: Quaternion, offset: Vector3, look_at: Point3) -> Matrix4 {
+        let look_at_matrix = Matrix4::from_translation(-Vector3::from(look_at));
         let rotation_matrix = Matrix4::from(rotation);
         let negative_offset_matrix = Matrix4::from_translation(-offset);
 
-        negative_offset_matrix * rotation_matrix
+        negative_offset_matrix * rotation_matrix * look_at_matrix
     }
 
     fn rebuild_view_matrix(&mut self) {
-        self.view_matrix = Self::build_view_matrix_from_rotation_and_offset(
+        self.view_matrix = Self::build_view_matrix(
             self.current_rotation * self.rotation_modifier,
-            Vector3::from(self.z_offset) + Vector3::from(self.look_at),
+            Vector3::from(self.z_offset),
+            self.look_at,
         );
     }
 
@@ -219,6 +446,9 @@ struct ProjectionInfo {
     z_near: f32,
     /// distance to far clipping plane    
     z_far: f32,
+    /// Set for an orthographic projection, which shows this much above and below the center of
+    /// the view.
+    orthographic_half_height: Option<f32>,
     projection_matrix: Matrix4,
 }
 
@@ -237,17 +467,33 @@ impl ProjectionInfo {
             aspect_ratio,
             z_near,
             z_far,
+            orthographic_half_height: None,
             projection_matrix,
         }
     }
 
     fn build_projection_matrix(&mut self) {
-        self.projection_matrix = Matrix4::create_perspective(
-            Degrees(self.vertical_fov),
-            self.aspect_ratio,
-            self.z_near,
-            self.z_far,
-        );
+        self.projection_matrix = match self.orthographic_half_height {
+            // Nothing gets closer with an orthographic projection, so what is behind the camera
+            // is shown too.
+            Some(half_height) => Matrix4::create_orthographic(
+                half_height * self.aspect_ratio,
+                half_height,
+                -self.z_far,
+                self.z_far,
+            ),
+            None => Matrix4::create_perspective(
+                Degrees(self.vertical_fov),
+                self.aspect_ratio,
+                self.z_near,
+                self.z_far,
+            ),
+        };
+    }
+
+    fn set_orthographic_half_height(&mut self, value: Option<f32>) {
+        self.orthographic_half_height = value;
+        self.build_projection_matrix();
     }
 
     fn set_aspect_ratio(&mut self, arg: f32) {
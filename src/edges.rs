@@ -2,77 +2,114 @@
 //! rendering wireframe outlines.
 //!
 //! We follow the approach in https://blog.mapbox.com/drawing-antialiased-lines-with-opengl-8766f34192d.
+//! Each edge becomes a quad whose four corners all start out on the edge itself. The vertex
+//! shader pushes them out sideways in screen space, so that the line has the same width in
+//! pixels however far away it is, and the fragment shader fades out the sides.
 
-use crate::math::{point::Point3, vector::Vector2};
+use crate::{bmesh::bmesh::BMesh, math::point::Point3};
 
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct EdgeVertex {
-    /// The 3D position of the vertex. The z-coordinate is only used for depth-testing.
-    position: [f32; 3],
-    /// A unit vector perpindicular to the point described by the first two coordinates of
-    /// the vertex position. The sign will dictate which direction we push the vertex out;
-    /// two `EdgeVertex`s describing the same position should have opposite sign normals.
-    normal: [f32; 2],
+    /// The two ends of the edge. Both are needed to find the direction of the edge on the
+    /// screen, which is only known once they are projected.
+    start: [f32; 3],
+    end: [f32; 3],
+    /// 0 for a vertex at the start of the edge, 1 for one at the end.
+    at_end: f32,
+    /// Which way the vertex is pushed out from the edge: 1 to the left of the edge (as seen on
+    /// the screen, going from start to end), -1 to the right. Each end has one of each.
+    side: f32,
+    /// 1 if the vertex of the mesh at this end of the edge is selected, 0 if not. An edge with
+    /// only one end selected fades from one color to the other.
+    selected: f32,
 }
 
 impl EdgeVertex {
-    pub fn new(position: Point3, normal: Vector2) -> Self {
+    pub fn new(start: Point3, end: Point3, at_end: bool, side: f32, selected: bool) -> Self {
         Self {
-            position: position.into(),
-            normal: normal.into(),
+            start: start.into(),
+            end: end.into(),
+            at_end: if at_end { 1.0 } else { 0.0 },
+            side,
+            selected: if selected { 1.0 } else { 0.0 },
         }
     }
-}
 
-/// Convenience struct for specifying a collection of edges using an arbitrarily ordered collection
-/// of positions and a sequence of indices. Rather than requiring the number of indices provided be
-/// even, this struct makes it clear which indices should be considered together to define an edge.
-pub struct EdgeIndex {
-    point0_index: u16,
-    point1_index: u16,
+    pub fn get_descriptor<'a>() -> eframe::wgpu::VertexBufferLayout<'a> {
+        const ATTRIBUTES: [eframe::wgpu::VertexAttribute; 5] = eframe::wgpu::vertex_attr_array![
+            0 => Float32x3,
+            1 => Float32x3,
+            2 => Float32,
+            3 => Float32,
+            4 => Float32,
+        ];
+
+        eframe::wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<EdgeVertex>() as eframe::wgpu::BufferAddress,
+            step_mode: eframe::wgpu::VertexStepMode::Vertex,
+            attributes: &ATTRIBUTES,
+        }
+    }
 }
 
 pub struct EdgeMesh {
-    vertices: Vec<EdgeVertex>,
-    indices: Vec<u16>,
+    pub vertices: Vec<EdgeVertex>,
+    pub indices: Vec<u32>,
 }
 
 impl EdgeMesh {
-    // /// Create a new `EdgeMesh` given an arbitrarily ordered collection of vertices, where edges
-    // /// are specified by `indices`.
-    // pub fn new_from_vertices_and_indices(vertices: &Vec<Point3>, indices: &Vec<EdgeIndex>) -> Self {
-    //     let mesh_vertices = Vec::<EdgeVertex>::with_capacity(vertices.len());
-    //     let mesh_indices = Vec::<u16>::with_capacity(indices.len() * 6);
-
-    //     for index in indices {
-    //         let p0: Point3 = vertices.get(index.point0_index as usize).unwrap().clone();
-    //         let p1 = vertices.get(index.point1_index.into()).unwrap();
+    /// Interpreting an edge between the two provided points, construct the mesh. `selected` is
+    /// whether each of the points is selected.
+    pub fn new_from_two_points(p0: &Point3, p1: &Point3, selected: [bool; 2]) -> Self {
+        let v0 = EdgeVertex::new(*p0, *p1, false, 1.0, selected[0]);
+        let v1 = EdgeVertex::new(*p0, *p1, false, -1.0, selected[0]);
+        let v2 = EdgeVertex::new(*p0, *p1, true, 1.0, selected[1]);
+        let v3 = EdgeVertex::new(*p0, *p1, true, -1.0, selected[1]);
 
-    //         let edge_mesh = Self::new_from_two_points(&p0, &p1);
-    //         mesh_vertices.append(&mut edge_mesh.vertices);
-    //     }
+        #[rustfmt::skip]
+        let mesh = Self {
+            vertices: vec![v0, v1, v2, v3],
+            indices: vec![
+                0, 1, 2,
+                1, 3, 2,
+            ],
+        };
 
-    //     todo!()
-    // }
+        mesh
+    }
 
-    // /// Interpreting an edge between the two provided points, construct the mesh.
-    // pub fn new_from_two_points(p0: &Point3, p1: &Point3) -> Self {
-    //     let p0_to_p1 = (p1 - p0).xy();
+    /// The edges of the `BMesh`, including those that are not the side of any face. If
+    /// `show_selection` is set, they are colored by which of their vertices are selected.
+    pub fn from_bmesh(bmesh: &BMesh, show_selection: bool) -> Self {
+        let mut mesh = Self {
+            vertices: Vec::with_capacity(bmesh.get_edges().len() * 4),
+            indices: Vec::with_capacity(bmesh.get_edges().len() * 6),
+        };
 
-    //     let normal0 = Vector2::get_perpindicular_cw_vector(p0_to_p1).normalize();
-    //     let normal1 = -normal0;
+        for edge in bmesh.get_edges() {
+            let edge = edge.as_ref().borrow();
+            let v0 = edge.get_v0();
+            let v1 = edge.get_v1();
+            let (v0, v1) = (v0.as_ref().borrow(), v1.as_ref().borrow());
+            mesh.append(Self::new_from_two_points(
+                &v0.get_position(),
+                &v1.get_position(),
+                [
+                    show_selection && v0.is_selected(),
+                    show_selection && v1.is_selected(),
+                ],
+            ));
+        }
 
-    //     let v0 = EdgeVertex::new(p0, normal0);
-    //     let v1 = EdgeVertex::new(p0, normal1);
-    //     let v2 = EdgeVertex::new(p0, normal0);
-    //     let v3 = EdgeVertex::new(p0, normal1);
+        mesh
+    }
 
-    //     #[cfg_attr(rustfmt, rustfmt_skip)]
-    //     Self {
-    //         vertices: vec![v0, v1, v2, v3],
-    //         indices: vec![
-    //             0, 1, 2,
-    //             1, 2, 3,
-    //         ],
-    //     }
-    // }
+    /// Adds the edges of the other mesh to this one.
+    pub fn append(&mut self, mut other: Self) {
+        let index_offset = self.vertices.len() as u32;
+        self.vertices.append(&mut other.vertices);
+        self.indices
+            .extend(other.indices.iter().map(|i| i + index_offset));
+    }
 }
@@ -1,10 +1,332 @@
-use crate::application_state::ApplicationState;
+//! What the keyboard and mouse are doing, kept from frame to frame on top of egui's input
+//! events (see `posts/post1.adoc`). Besides which keys and buttons are down, it tells clicks
+//! from drags and double-clicks, notices buttons held without moving, and lets a modal tool
+//! capture all the input. Tools and shortcuts (see `Keymap`) query it rather than egui.
 
-impl ApplicationState {}
+use std::collections::HashSet;
 
-pub fn viewport_camera_rotate(egui_input_state: &eframe::egui::InputState) -> bool {
-    egui_input_state.key_down(eframe::egui::Key::Z)
-        && egui_input_state
-            .pointer
-            .button_down(eframe::egui::PointerButton::Primary)
+use eframe::egui::{Event, Key, Modifiers, PointerButton, Pos2, NUM_POINTER_BUTTONS};
+
+use crate::{
+    keymap::{Action, ActionContext, Keymap},
+    math::vector::Vector2,
+    tools::fly::FlyMovement,
+};
+
+/// How far (in points) the pointer needs to move with a button down for it to be a drag rather
+/// than a click.
+const DRAG_THRESHOLD: f32 = 4.0;
+
+/// How soon (in seconds) after a click another one needs to come to make a double-click.
+const DOUBLE_CLICK_TIME: f64 = 0.3;
+
+/// How long (in seconds) a button needs to be held without moving for it to count as held.
+const HOLD_TIME: f64 = 0.5;
+
+/// How many points one notch of the scroll wheel scrolls by.
+const POINTS_PER_SCROLL_NOTCH: f32 = 50.0;
+
+/// Something a mouse button did this frame. A button can do several things in the same frame,
+/// e.g. be released, clicked and double-clicked.
+#[derive(Clone, Copy, PartialEq)]
+pub enum ButtonGesture {
+    Pressed,
+    Released,
+    /// Released without having been dragged or held.
+    Click,
+    /// The second of two clicks close in time and space, which is also a click.
+    DoubleClick,
+    /// Moved further than the drag threshold since being pressed.
+    DragStarted,
+    /// Released after having been dragged.
+    DragEnded,
+    /// Held down without moving for a while, e.g. to open a menu.
+    Held,
+}
+
+pub struct ButtonState {
+    pub is_pressed: bool,
+    pub position_when_pressed: Option<Pos2>,
+    time_when_pressed: f64,
+    is_dragging: bool,
+    is_held: bool,
+    /// When and where the last click was, to recognize double-clicks.
+    last_click: Option<(f64, Pos2)>,
+    /// What happened this frame.
+    gestures: Vec<ButtonGesture>,
+}
+
+impl ButtonState {
+    fn new() -> Self {
+        Self {
+            is_pressed: false,
+            position_when_pressed: None,
+            time_when_pressed: 0.0,
+            is_dragging: false,
+            is_held: false,
+            last_click: None,
+            gestures: Vec::new(),
+        }
+    }
+
+    pub fn happened(&self, gesture: ButtonGesture) -> bool {
+        self.gestures.contains(&gesture)
+    }
+
+    fn press(&mut self, position: Pos2, time: f64) {
+        self.is_pressed = true;
+        self.position_when_pressed = Some(position);
+        self.time_when_pressed = time;
+        self.is_dragging = false;
+        self.is_held = false;
+        self.gestures.push(ButtonGesture::Pressed);
+    }
+
+    fn move_to(&mut self, position: Pos2) {
+        let moved_far = self
+            .position_when_pressed
+            .is_some_and(|pressed| pressed.distance(position) > DRAG_THRESHOLD);
+        if self.is_pressed && !self.is_dragging && moved_far {
+            self.is_dragging = true;
+            self.gestures.push(ButtonGesture::DragStarted);
+        }
+    }
+
+    fn release(&mut self, position: Pos2, time: f64) {
+        if !self.is_pressed {
+            return;
+        }
+        self.is_pressed = false;
+        self.position_when_pressed = None;
+        self.gestures.push(ButtonGesture::Released);
+
+        if self.is_dragging {
+            self.gestures.push(ButtonGesture::DragEnded);
+        } else if !self.is_held {
+            self.gestures.push(ButtonGesture::Click);
+            let is_double_click = self.last_click.is_some_and(|(click_time, click_position)| {
+                time - click_time <= DOUBLE_CLICK_TIME
+                    && click_position.distance(position) <= DRAG_THRESHOLD
+            });
+            if is_double_click {
+                self.gestures.push(ButtonGesture::DoubleClick);
+                self.last_click = None;
+            } else {
+                self.last_click = Some((time, position));
+            }
+        }
+    }
+
+    /// How long until the button counts as held, if it is down and hasn't moved.
+    fn get_time_until_held(&self, time: f64) -> Option<f64> {
+        (self.is_pressed && !self.is_dragging && !self.is_held)
+            .then(|| (self.time_when_pressed + HOLD_TIME - time).max(0.0))
+    }
+}
+
+pub struct MouseState {
+    /// `None` when the pointer isn't over the window.
+    pub current_position: Option<Pos2>,
+    /// Where the pointer was at the end of the last frame.
+    pub previous_position: Option<Pos2>,
+    /// In points, this frame.
+    pub scroll_delta: Vector2,
+    buttons: [ButtonState; NUM_POINTER_BUTTONS],
+}
+
+impl MouseState {
+    /// How far the pointer moved this frame, in points.
+    pub fn get_delta(&self) -> Vector2 {
+        match (self.previous_position, self.current_position) {
+            (Some(previous), Some(current)) => Vector2::from(current - previous),
+            _ => Vector2::new(0.0, 0.0),
+        }
+    }
+
+    pub fn get_button(&self, button: PointerButton) -> &ButtonState {
+        &self.buttons[button as usize]
+    }
+
+    /// How far the scroll wheel turned, up being positive. Touchpads turn it by fractions of a
+    /// notch.
+    pub fn get_scroll_notches(&self) -> f32 {
+        self.scroll_delta.y() / POINTS_PER_SCROLL_NOTCH
+    }
+}
+
+pub struct KeyboardState {
+    pub modifiers: Modifiers,
+    keys_down: HashSet<Key>,
+    /// In the order they were pressed this frame, including key repeats.
+    keys_pressed: Vec<Key>,
+    /// What the keys pressed this frame typed.
+    text: String,
+}
+
+impl KeyboardState {
+    pub fn key_down(&self, key: Key) -> bool {
+        self.keys_down.contains(&key)
+    }
+
+    pub fn key_pressed(&self, key: Key) -> bool {
+        self.keys_pressed.contains(&key)
+    }
+
+    /// The first key pressed this frame.
+    pub fn get_pressed_key(&self) -> Option<Key> {
+        self.keys_pressed.first().copied()
+    }
+
+    /// Any of the keys held down.
+    pub fn get_held_key(&self) -> Option<Key> {
+        self.keys_down.iter().next().copied()
+    }
+
+    /// Whether any of the characters were typed this frame.
+    pub fn typed_any(&self, characters: &[char]) -> bool {
+        self.text.contains(characters)
+    }
+}
+
+pub struct InputState {
+    pub mouse_state: MouseState,
+    pub keyboard_state: KeyboardState,
+    /// How long the last frame took, in seconds.
+    pub dt: f32,
+    time: f64,
+    /// The only context input goes to, while a modal tool has it.
+    capture: Option<ActionContext>,
+    /// Set when the capture ends, so that the input ending it doesn't go anywhere else that
+    /// frame.
+    release_pending: bool,
+}
+
+impl InputState {
+    pub fn new() -> Self {
+        Self {
+            mouse_state: MouseState {
+                current_position: None,
+                previous_position: None,
+                scroll_delta: Vector2::new(0.0, 0.0),
+                buttons: std::array::from_fn(|_| ButtonState::new()),
+            },
+            keyboard_state: KeyboardState {
+                modifiers: Modifiers::NONE,
+                keys_down: HashSet::new(),
+                keys_pressed: Vec::new(),
+                text: String::new(),
+            },
+            dt: 0.0,
+            time: 0.0,
+            capture: None,
+            release_pending: false,
+        }
+    }
+
+    /// Moves the machine on with what egui got this frame. Call this once per frame, before
+    /// anything queries it.
+    pub fn update(&mut self, egui_input_state: &eframe::egui::InputState) {
+        if self.release_pending {
+            self.capture = None;
+            self.release_pending = false;
+        }
+
+        self.time = egui_input_state.time;
+        self.dt = egui_input_state.stable_dt;
+        let mouse = &mut self.mouse_state;
+        let keyboard = &mut self.keyboard_state;
+        mouse.previous_position = mouse.current_position;
+        mouse.scroll_delta = Vector2::from(egui_input_state.scroll_delta);
+        keyboard.modifiers = egui_input_state.modifiers;
+        keyboard.keys_pressed.clear();
+        keyboard.text.clear();
+        for button in &mut mouse.buttons {
+            button.gestures.clear();
+        }
+
+        for event in &egui_input_state.events {
+            match event {
+                Event::PointerMoved(position) => {
+                    mouse.current_position = Some(*position);
+                    for button in &mut mouse.buttons {
+                        button.move_to(*position);
+                    }
+                }
+                Event::PointerGone => mouse.current_position = None,
+                Event::PointerButton {
+                    pos,
+                    button,
+                    pressed,
+                    ..
+                } => {
+                    let button = &mut mouse.buttons[*button as usize];
+                    if *pressed {
+                        button.press(*pos, self.time);
+                    } else {
+                        button.release(*pos, self.time);
+                    }
+                }
+                Event::Key { key, pressed, .. } => {
+                    if *pressed {
+                        keyboard.keys_down.insert(*key);
+                        keyboard.keys_pressed.push(*key);
+                    } else {
+                        keyboard.keys_down.remove(key);
+                    }
+                }
+                Event::Text(text) => keyboard.text.push_str(text),
+                _ => {}
+            }
+        }
+
+        for button in &mut mouse.buttons {
+            if button
+                .get_time_until_held(self.time)
+                .is_some_and(|time| time <= 0.0)
+            {
+                button.is_held = true;
+                button.gestures.push(ButtonGesture::Held);
+            }
+        }
+    }
+
+    /// How long until a button counts as held, for requesting a repaint then, since nothing
+    /// else may cause one.
+    pub fn get_time_until_held(&self) -> Option<f64> {
+        self.mouse_state
+            .buttons
+            .iter()
+            .filter_map(|button| button.get_time_until_held(self.time))
+            .reduce(f64::min)
+    }
+
+    /// From now on, only the shortcuts of the context get any input, until it is released.
+    pub fn capture(&mut self, context: ActionContext) {
+        self.capture = Some(context);
+        self.release_pending = false;
+    }
+
+    /// Gives the input back to everything, from the next frame on.
+    pub fn release(&mut self) {
+        self.release_pending = true;
+    }
+
+    /// Whether input goes to the context, i.e. no other has captured it.
+    pub fn is_available_to(&self, context: ActionContext) -> bool {
+        self.capture.is_none_or(|capture| capture == context)
+    }
+}
+
+/// Where the fly actions held down go.
+pub fn fly_navigation_movement(input_state: &InputState, keymap: &Keymap) -> FlyMovement {
+    let axis = |positive, negative| {
+        let held = |action| keymap.is_active(action, input_state) as i32 as f32;
+        held(positive) - held(negative)
+    };
+
+    FlyMovement {
+        sideways: axis(Action::FlyRight, Action::FlyLeft),
+        forward: axis(Action::FlyForward, Action::FlyBackward),
+        vertical: axis(Action::FlyUp, Action::FlyDown),
+    }
 }
@@ -0,0 +1,710 @@
+//! Which shortcuts trigger which actions. The keymap starts from a preset, and can be changed
+//! in the app (see `panels/keymap_panel.rs`) and saved to `keymap.toml` in the config directory,
+//! where it is loaded from on startup. The file looks like this:
+//!
+//! ```toml
+//! preset = "Maya"
+//!
+//! [bindings]
+//! viewport_orbit = ["Alt+LMB"]
+//! knife_tool = ["K", "Ctrl+K"]
+//! "mesh.delete" = ["X"]
+//! ```
+//!
+//! Actions that aren't listed keep the bindings of the preset. Operators are listed by their id
+//! (see `operators.rs`), and have no bindings unless given some.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use eframe::egui::{Key, PointerButton};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    input_state::{ButtonGesture, InputState},
+    operators::OperatorRegistry,
+};
+
+/// Something the user can do with a shortcut.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    ViewportOrbit,
+    KnifeTool,
+    KnifeConfirm,
+    KnifeCancel,
+    FlyNavigation,
+    FlyConfirm,
+    FlyCancel,
+    FlyForward,
+    FlyBackward,
+    FlyLeft,
+    FlyRight,
+    FlyUp,
+    FlyDown,
+    CommandPalette,
+    /// Runs the operator with the id (see `OperatorRegistry`) with its default parameters.
+    Operator(&'static str),
+}
+
+/// Where shortcuts are listened for. Shortcuts of actions in overlapping contexts can't be the
+/// same.
+#[derive(Clone, Copy, PartialEq)]
+pub enum ActionContext {
+    Viewport,
+    /// While cutting, when the viewport shortcuts still work.
+    Knife,
+    /// While flying, when nothing else does.
+    Fly,
+}
+
+impl ActionContext {
+    fn overlaps(self, other: Self) -> bool {
+        self == other
+            || matches!(
+                (self, other),
+                (ActionContext::Viewport, ActionContext::Knife)
+                    | (ActionContext::Knife, ActionContext::Viewport)
+            )
+    }
+}
+
+impl Action {
+    /// The built-in actions, which all presets bind. Operators are bound on top of these.
+    pub const ALL: [Action; 14] = [
+        Action::ViewportOrbit,
+        Action::KnifeTool,
+        Action::KnifeConfirm,
+        Action::KnifeCancel,
+        Action::FlyNavigation,
+        Action::FlyConfirm,
+        Action::FlyCancel,
+        Action::FlyForward,
+        Action::FlyBackward,
+        Action::FlyLeft,
+        Action::FlyRight,
+        Action::FlyUp,
+        Action::FlyDown,
+        Action::CommandPalette,
+    ];
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Action::ViewportOrbit => "Orbit",
+            Action::KnifeTool => "Knife",
+            Action::KnifeConfirm => "Confirm cut",
+            Action::KnifeCancel => "Cancel cut",
+            Action::FlyNavigation => "Fly",
+            Action::FlyConfirm => "Stop flying",
+            Action::FlyCancel => "Cancel flying",
+            Action::FlyForward => "Fly forward",
+            Action::FlyBackward => "Fly backward",
+            Action::FlyLeft => "Fly left",
+            Action::FlyRight => "Fly right",
+            Action::FlyUp => "Fly up",
+            Action::FlyDown => "Fly down",
+            Action::CommandPalette => "Command palette",
+            // The name of the operator is in the registry.
+            Action::Operator(id) => id,
+        }
+    }
+
+    /// What the action is called in the keymap file.
+    pub fn get_id(&self) -> &'static str {
+        match self {
+            Action::ViewportOrbit => "viewport_orbit",
+            Action::KnifeTool => "knife_tool",
+            Action::KnifeConfirm => "knife_confirm",
+            Action::KnifeCancel => "knife_cancel",
+            Action::FlyNavigation => "fly_navigation",
+            Action::FlyConfirm => "fly_confirm",
+            Action::FlyCancel => "fly_cancel",
+            Action::FlyForward => "fly_forward",
+            Action::FlyBackward => "fly_backward",
+            Action::FlyLeft => "fly_left",
+            Action::FlyRight => "fly_right",
+            Action::FlyUp => "fly_up",
+            Action::FlyDown => "fly_down",
+            Action::CommandPalette => "command_palette",
+            Action::Operator(id) => id,
+        }
+    }
+
+    pub fn get_context(&self) -> ActionContext {
+        match self {
+            Action::ViewportOrbit
+            | Action::KnifeTool
+            | Action::FlyNavigation
+            | Action::CommandPalette
+            | Action::Operator(_) => ActionContext::Viewport,
+            Action::KnifeConfirm | Action::KnifeCancel => ActionContext::Knife,
+            _ => ActionContext::Fly,
+        }
+    }
+
+    /// Whether the action lasts for as long as its shortcut is held, rather than happening
+    /// once when it is pressed.
+    pub fn is_held(&self) -> bool {
+        matches!(
+            self,
+            Action::ViewportOrbit
+                | Action::FlyForward
+                | Action::FlyBackward
+                | Action::FlyLeft
+                | Action::FlyRight
+                | Action::FlyUp
+                | Action::FlyDown
+        )
+    }
+
+    /// Operator ids are only known if they are in the registry.
+    fn from_id(id: &str, operators: &OperatorRegistry) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|action| action.get_id() == id)
+            .or_else(|| {
+                operators
+                    .get(id)
+                    .map(|operator| Action::Operator(operator.id))
+            })
+    }
+}
+
+/// The keys that can be bound, which are all of egui's.
+const KEYS: [Key; 73] = [
+    Key::ArrowDown,
+    Key::ArrowLeft,
+    Key::ArrowRight,
+    Key::ArrowUp,
+    Key::Escape,
+    Key::Tab,
+    Key::Backspace,
+    Key::Enter,
+    Key::Space,
+    Key::Insert,
+    Key::Delete,
+    Key::Home,
+    Key::End,
+    Key::PageUp,
+    Key::PageDown,
+    Key::Minus,
+    Key::PlusEquals,
+    Key::Num0,
+    Key::Num1,
+    Key::Num2,
+    Key::Num3,
+    Key::Num4,
+    Key::Num5,
+    Key::Num6,
+    Key::Num7,
+    Key::Num8,
+    Key::Num9,
+    Key::A,
+    Key::B,
+    Key::C,
+    Key::D,
+    Key::E,
+    Key::F,
+    Key::G,
+    Key::H,
+    Key::I,
+    Key::J,
+    Key::K,
+    Key::L,
+    Key::M,
+    Key::N,
+    Key::O,
+    Key::P,
+    Key::Q,
+    Key::R,
+    Key::S,
+    Key::T,
+    Key::U,
+    Key::V,
+    Key::W,
+    Key::X,
+    Key::Y,
+    Key::Z,
+    Key::F1,
+    Key::F2,
+    Key::F3,
+    Key::F4,
+    Key::F5,
+    Key::F6,
+    Key::F7,
+    Key::F8,
+    Key::F9,
+    Key::F10,
+    Key::F11,
+    Key::F12,
+    Key::F13,
+    Key::F14,
+    Key::F15,
+    Key::F16,
+    Key::F17,
+    Key::F18,
+    Key::F19,
+    Key::F20,
+];
+
+/// What completes a chord, after the modifiers (and held key) are down.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ChordInput {
+    Key(Key),
+    Pointer(PointerButton),
+    /// egui has no key for it, so it is recognized by the text it types (see
+    /// `BACKTICK_CHARACTERS`).
+    Backtick,
+}
+
+/// What the backtick key types: ` or, with shift, ~ (on US layouts).
+const BACKTICK_CHARACTERS: [char; 2] = ['`', '~'];
+
+/// A combination of modifiers, optionally a key held down, and a key or mouse button, e.g.
+/// "Ctrl+K", "Alt+LMB" or "Z+LMB".
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Chord {
+    /// Command on Mac.
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    /// A key held together with a mouse button, like Z in "Z+LMB".
+    pub held_key: Option<Key>,
+    pub input: ChordInput,
+}
+
+impl Chord {
+    /// The input on its own, without modifiers.
+    pub fn new(input: ChordInput) -> Self {
+        Self {
+            ctrl: false,
+            shift: false,
+            alt: false,
+            held_key: None,
+            input,
+        }
+    }
+
+    pub fn key(key: Key) -> Self {
+        Self::new(ChordInput::Key(key))
+    }
+
+    pub fn pointer(button: PointerButton) -> Self {
+        Self::new(ChordInput::Pointer(button))
+    }
+
+    fn with_ctrl(self) -> Self {
+        Self { ctrl: true, ..self }
+    }
+
+    fn with_shift(self) -> Self {
+        Self {
+            shift: true,
+            ..self
+        }
+    }
+
+    fn with_alt(self) -> Self {
+        Self { alt: true, ..self }
+    }
+
+    fn with_held_key(self, key: Key) -> Self {
+        Self {
+            held_key: Some(key),
+            ..self
+        }
+    }
+
+    /// Whether the chord is down (`held`) or was pressed this frame.
+    fn is_active(&self, input_state: &InputState, held: bool) -> bool {
+        let keyboard = &input_state.keyboard_state;
+        let modifiers = keyboard.modifiers;
+        if modifiers.command != self.ctrl
+            || modifiers.shift != self.shift
+            || modifiers.alt != self.alt
+            || self.held_key.is_some_and(|key| !keyboard.key_down(key))
+        {
+            return false;
+        }
+
+        match (self.input, held) {
+            (ChordInput::Key(key), true) => keyboard.key_down(key),
+            (ChordInput::Key(key), false) => keyboard.key_pressed(key),
+            (ChordInput::Pointer(button), true) => {
+                input_state.mouse_state.get_button(button).is_pressed
+            }
+            (ChordInput::Pointer(button), false) => input_state
+                .mouse_state
+                .get_button(button)
+                .happened(ButtonGesture::Click),
+            (ChordInput::Backtick, _) => keyboard.typed_any(&BACKTICK_CHARACTERS),
+        }
+    }
+
+    /// The chord the user is pressing this frame, if any, for recording shortcuts.
+    pub fn from_input(input_state: &InputState) -> Option<Self> {
+        let keyboard = &input_state.keyboard_state;
+        let pressed_button = [
+            PointerButton::Primary,
+            PointerButton::Secondary,
+            PointerButton::Middle,
+            PointerButton::Extra1,
+            PointerButton::Extra2,
+        ]
+        .into_iter()
+        .find(|button| {
+            input_state
+                .mouse_state
+                .get_button(*button)
+                .happened(ButtonGesture::Pressed)
+        });
+
+        let (input, held_key) = if let Some(key) = keyboard.get_pressed_key() {
+            (ChordInput::Key(key), None)
+        } else if let Some(button) = pressed_button {
+            (ChordInput::Pointer(button), keyboard.get_held_key())
+        } else if keyboard.typed_any(&BACKTICK_CHARACTERS) {
+            (ChordInput::Backtick, None)
+        } else {
+            return None;
+        };
+
+        Some(Self {
+            ctrl: keyboard.modifiers.command,
+            shift: keyboard.modifiers.shift,
+            alt: keyboard.modifiers.alt,
+            held_key,
+            input,
+        })
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if self.ctrl {
+            parts.push("Ctrl");
+        }
+        if self.shift {
+            parts.push("Shift");
+        }
+        if self.alt {
+            parts.push("Alt");
+        }
+        if let Some(key) = self.held_key {
+            parts.push(key.name());
+        }
+        parts.push(match self.input {
+            ChordInput::Key(key) => key.name(),
+            ChordInput::Pointer(PointerButton::Primary) => "LMB",
+            ChordInput::Pointer(PointerButton::Secondary) => "RMB",
+            ChordInput::Pointer(PointerButton::Middle) => "MMB",
+            ChordInput::Pointer(PointerButton::Extra1) => "Mouse4",
+            ChordInput::Pointer(PointerButton::Extra2) => "Mouse5",
+            ChordInput::Backtick => "`",
+        });
+        write!(f, "{}", parts.join("+"))
+    }
+}
+
+impl std::str::FromStr for Chord {
+    type Err = KeymapError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid = || KeymapError::InvalidChord(text.to_string());
+        let parse_key = |name: &str| KEYS.into_iter().find(|key| key.name() == name);
+
+        let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        let input = match parts.pop().ok_or_else(invalid)? {
+            "LMB" => ChordInput::Pointer(PointerButton::Primary),
+            "RMB" => ChordInput::Pointer(PointerButton::Secondary),
+            "MMB" => ChordInput::Pointer(PointerButton::Middle),
+            "Mouse4" => ChordInput::Pointer(PointerButton::Extra1),
+            "Mouse5" => ChordInput::Pointer(PointerButton::Extra2),
+            "`" => ChordInput::Backtick,
+            name => ChordInput::Key(parse_key(name).ok_or_else(invalid)?),
+        };
+
+        let mut chord = Self::new(input);
+        for part in parts {
+            match part {
+                "Ctrl" => chord.ctrl = true,
+                "Shift" => chord.shift = true,
+                "Alt" => chord.alt = true,
+                // Only mouse buttons can be pressed with a key held down.
+                name if chord.held_key.is_none()
+                    && matches!(chord.input, ChordInput::Pointer(_)) =>
+                {
+                    chord.held_key = Some(parse_key(name).ok_or_else(invalid)?);
+                }
+                _ => return Err(invalid()),
+            }
+        }
+
+        Ok(chord)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum KeymapPreset {
+    /// Like Blender: orbiting while holding Z, and Shift+` to fly.
+    Blender,
+    /// Like Maya: orbiting with Alt and the left mouse button.
+    Maya,
+}
+
+impl KeymapPreset {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            KeymapPreset::Blender => "Blender",
+            KeymapPreset::Maya => "Maya",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        [KeymapPreset::Blender, KeymapPreset::Maya]
+            .into_iter()
+            .find(|preset| preset.get_name() == name)
+    }
+}
+
+#[derive(Debug)]
+pub enum KeymapError {
+    Io(std::io::Error),
+    Parsing(toml::de::Error),
+    Serializing(toml::ser::Error),
+    UnknownPreset(String),
+    UnknownAction(String),
+    /// The text isn't a chord, like "Ctrl+K" (see `Chord`).
+    InvalidChord(String),
+}
+
+impl fmt::Display for KeymapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeymapError::Io(error) => write!(f, "{}", error),
+            KeymapError::Parsing(error) => write!(f, "invalid keymap file: {}", error),
+            KeymapError::Serializing(error) => write!(f, "{}", error),
+            KeymapError::UnknownPreset(name) => write!(f, "there is no preset called {:?}", name),
+            KeymapError::UnknownAction(id) => {
+                write!(f, "there is no action or operator called {:?}", id)
+            }
+            KeymapError::InvalidChord(text) => write!(f, "{:?} isn't a shortcut", text),
+        }
+    }
+}
+
+impl std::error::Error for KeymapError {}
+
+/// How the keymap is written to a file.
+#[derive(Serialize, Deserialize)]
+struct KeymapFile {
+    preset: String,
+    #[serde(default)]
+    bindings: BTreeMap<String, Vec<String>>,
+}
+
+/// Two actions that can be triggered by the same chord at the same time.
+pub struct KeymapConflict {
+    pub actions: [Action; 2],
+    pub chord: Chord,
+}
+
+pub struct Keymap {
+    /// What the bindings started from, which is also what they are reset to.
+    pub preset: KeymapPreset,
+    /// The chords of each action, which can have any number of them. All of `Action::ALL` are
+    /// there, followed by the operators that were bound.
+    bindings: Vec<(Action, Vec<Chord>)>,
+}
+
+impl Keymap {
+    pub fn from_preset(preset: KeymapPreset) -> Self {
+        let orbit = match preset {
+            KeymapPreset::Blender => Chord::pointer(PointerButton::Primary).with_held_key(Key::Z),
+            KeymapPreset::Maya => Chord::pointer(PointerButton::Primary).with_alt(),
+        };
+        let confirm = vec![Chord::key(Key::Enter), Chord::key(Key::Space)];
+
+        let bindings = Action::ALL
+            .into_iter()
+            .map(|action| {
+                let chords = match action {
+                    Action::ViewportOrbit => vec![orbit],
+                    Action::KnifeTool => vec![Chord::key(Key::K)],
+                    Action::KnifeConfirm => confirm.clone(),
+                    Action::KnifeCancel => vec![Chord::key(Key::Escape)],
+                    Action::FlyNavigation => vec![Chord::new(ChordInput::Backtick).with_shift()],
+                    Action::FlyConfirm => {
+                        let mut chords = vec![Chord::pointer(PointerButton::Primary)];
+                        chords.extend(confirm.iter().copied());
+                        chords
+                    }
+                    Action::FlyCancel => vec![
+                        Chord::key(Key::Escape),
+                        Chord::pointer(PointerButton::Secondary),
+                    ],
+                    Action::FlyForward => vec![Chord::key(Key::W)],
+                    Action::FlyBackward => vec![Chord::key(Key::S)],
+                    Action::FlyLeft => vec![Chord::key(Key::A)],
+                    Action::FlyRight => vec![Chord::key(Key::D)],
+                    Action::FlyUp => vec![Chord::key(Key::E)],
+                    Action::FlyDown => vec![Chord::key(Key::Q)],
+                    Action::CommandPalette => vec![
+                        Chord::key(Key::F3),
+                        Chord::key(Key::P).with_ctrl().with_shift(),
+                    ],
+                    Action::Operator(_) => unreachable!("operators aren't in Action::ALL"),
+                };
+                (action, chords)
+            })
+            .collect();
+
+        Self { preset, bindings }
+    }
+
+    /// Where the keymap is saved, if there is a config directory on this platform.
+    pub fn get_path() -> Option<PathBuf> {
+        Some(dirs::config_dir()?.join("fe").join("keymap.toml"))
+    }
+
+    /// The saved keymap, or the Blender preset if there is none or it can't be loaded.
+    pub fn load_or_default(operators: &OperatorRegistry) -> Self {
+        let Some(path) = Self::get_path().filter(|path| path.exists()) else {
+            return Self::from_preset(KeymapPreset::Blender);
+        };
+        Self::load(&path, operators).unwrap_or_else(|error| {
+            log::error!(
+                "Couldn't load the keymap from {}: {}",
+                path.display(),
+                error
+            );
+            Self::from_preset(KeymapPreset::Blender)
+        })
+    }
+
+    /// Operators can only be bound if they are in `operators`.
+    pub fn load(path: &Path, operators: &OperatorRegistry) -> Result<Self, KeymapError> {
+        let text = std::fs::read_to_string(path).map_err(KeymapError::Io)?;
+        let file: KeymapFile = toml::from_str(&text).map_err(KeymapError::Parsing)?;
+        let preset = KeymapPreset::from_name(&file.preset)
+            .ok_or_else(|| KeymapError::UnknownPreset(file.preset.clone()))?;
+
+        let mut keymap = Self::from_preset(preset);
+        for (id, chords) in &file.bindings {
+            let action = Action::from_id(id, operators)
+                .ok_or_else(|| KeymapError::UnknownAction(id.clone()))?;
+            let chords = chords
+                .iter()
+                .map(|chord| chord.parse())
+                .collect::<Result<_, _>>()?;
+            *keymap.get_chords_mut(action) = chords;
+        }
+
+        Ok(keymap)
+    }
+
+    /// Writes all the bindings, so that the file still means the same if the preset changes.
+    pub fn save(&self, path: &Path) -> Result<(), KeymapError> {
+        let file = KeymapFile {
+            preset: self.preset.get_name().to_string(),
+            bindings: self
+                .bindings
+                .iter()
+                .map(|(action, chords)| {
+                    let chords = chords.iter().map(|chord| chord.to_string()).collect();
+                    (action.get_id().to_string(), chords)
+                })
+                .collect(),
+        };
+        let text = toml::to_string_pretty(&file).map_err(KeymapError::Serializing)?;
+        if let Some(directory) = path.parent() {
+            std::fs::create_dir_all(directory).map_err(KeymapError::Io)?;
+        }
+        std::fs::write(path, text).map_err(KeymapError::Io)
+    }
+
+    /// None for operators that aren't bound.
+    pub fn get_chords(&self, action: Action) -> &[Chord] {
+        self.bindings
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map_or(&[], |(_, chords)| chords)
+    }
+
+    /// Operators that aren't bound yet are added, without chords.
+    pub fn get_chords_mut(&mut self, action: Action) -> &mut Vec<Chord> {
+        let index = match self.bindings.iter().position(|(a, _)| *a == action) {
+            Some(index) => index,
+            None => {
+                self.bindings.push((action, Vec::new()));
+                self.bindings.len() - 1
+            }
+        };
+        &mut self.bindings[index].1
+    }
+
+    /// The operators that were bound, in the order they were added, even if they no longer
+    /// have any chords.
+    pub fn get_bound_operators(&self) -> Vec<&'static str> {
+        self.bindings
+            .iter()
+            .filter_map(|(action, _)| match action {
+                Action::Operator(id) => Some(*id),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The operators whose chords were pressed this frame, to be run by the caller.
+    pub fn get_active_operators(&self, input_state: &InputState) -> Vec<&'static str> {
+        self.get_bound_operators()
+            .into_iter()
+            .filter(|id| self.is_active(Action::Operator(id), input_state))
+            .collect()
+    }
+
+    /// Whether any of the chords of the action is held down, for actions that are held (see
+    /// `Action::is_held()`), or was pressed this frame, for the others. Nothing is active while
+    /// another context has captured the input (see `InputState::capture()`).
+    pub fn is_active(&self, action: Action, input_state: &InputState) -> bool {
+        input_state.is_available_to(action.get_context())
+            && self
+                .get_chords(action)
+                .iter()
+                .any(|chord| chord.is_active(input_state, action.is_held()))
+    }
+
+    /// The chords of the action, for showing in the UI, e.g. "Enter or Space".
+    pub fn describe(&self, action: Action) -> String {
+        match self.get_chords(action) {
+            [] => "nothing".to_string(),
+            chords => chords
+                .iter()
+                .map(|chord| chord.to_string())
+                .collect::<Vec<_>>()
+                .join(" or "),
+        }
+    }
+
+    /// The chords bound to more than one action that can be triggered at the same time.
+    pub fn get_conflicts(&self) -> Vec<KeymapConflict> {
+        let mut conflicts = Vec::new();
+        for (i, (action, chords)) in self.bindings.iter().enumerate() {
+            for (other_action, other_chords) in &self.bindings[i + 1..] {
+                if !action.get_context().overlaps(other_action.get_context()) {
+                    continue;
+                }
+                for chord in chords.iter().filter(|chord| other_chords.contains(chord)) {
+                    conflicts.push(KeymapConflict {
+                        actions: [*action, *other_action],
+                        chord: *chord,
+                    });
+                }
+            }
+        }
+        conflicts
+    }
+}
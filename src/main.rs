@@ -1,28 +1,51 @@
 use application_state::ApplicationState;
-use rendering::render_resources::RenderResources;
+use bmesh::bmesh::BMesh;
+use camera::Camera;
+use keymap::Action;
+use rendering::{
+    headless::HeadlessRenderer, render_resources::RenderResourcesPerViewport,
+    viewport_target::get_supported_sample_counts,
+};
+use scene::{Scene, SceneObject};
 
-//mod bmesh;
+mod bmesh;
 mod application_state;
 mod camera;
 mod edges;
 mod input_state;
+mod keymap;
 mod math;
 mod meshes;
+mod modifiers;
+mod operators;
+mod panels;
 mod rendering;
+mod scene;
+mod tools;
 mod vertex;
+mod viewports;
 
 struct App {
     state: ApplicationState,
 }
 
 impl App {
-    pub fn initialize<'a>(eframe_creation_context: &'a eframe::CreationContext<'a>) -> Self {
-        let mut state = ApplicationState::initialize();
-        RenderResources::initialize(
-            eframe_creation_context,
-            &state.camera,
-            &state.drawing_stuff.camera_uniform,
-        );
+    /// - `adapter`: see `request_adapter()`. Without it the viewport isn't multisampled.
+    pub fn initialize<'a>(
+        eframe_creation_context: &'a eframe::CreationContext<'a>,
+        adapter: Option<&eframe::wgpu::Adapter>,
+    ) -> Self {
+        let render_state = eframe_creation_context.wgpu_render_state.as_ref().unwrap();
+        let supported_sample_counts = match adapter {
+            Some(adapter) => get_supported_sample_counts(
+                adapter,
+                &render_state.device,
+                render_state.target_format,
+            ),
+            None => vec![1],
+        };
+        let mut state = ApplicationState::initialize(supported_sample_counts);
+        RenderResourcesPerViewport::initialize(eframe_creation_context);
 
         draw_cube(&mut state);
 
@@ -32,6 +55,10 @@ impl App {
 
 impl eframe::App for App {
     fn update(&mut self, ctx: &eframe::egui::Context, _frame: &mut eframe::Frame) {
+        eframe::egui::SidePanel::right("object_panel").show(ctx, |ui| {
+            self.state.object_panel(ui);
+        });
+
         eframe::egui::CentralPanel::default().show(ctx, |ui| {
             ui.horizontal(|ui| {
                 ui.spacing_mut().item_spacing.x = 0.0;
@@ -43,33 +70,103 @@ impl eframe::App for App {
                 "It's not a very impressive demo, but it shows you can embed 3D inside of egui.",
             );
 
+            self.state.viewport_header(ui);
             eframe::egui::Frame::canvas(ui.style()).show(ui, |ui| {
                 self.state.custom_painting(ui);
             });
-            ui.label("Drag to rotate!");
+            let keymap = &self.state.keymap;
+            ui.label(format!(
+                "{} and drag to rotate! Press {} to cut with the knife, {} to fly, {} to search \
+                 operators. In edit mode, click to select (shift to add).",
+                keymap.describe(Action::ViewportOrbit),
+                keymap.describe(Action::KnifeTool),
+                keymap.describe(Action::FlyNavigation),
+                keymap.describe(Action::CommandPalette),
+            ));
         });
+
+        self.state.keymap_window(ctx);
+        self.state.command_palette_window(ctx);
     }
 }
 
 fn draw_cube(state: &mut ApplicationState) {
-    state
-        .drawing_stuff
-        .meshes_to_draw
-        .push(state.mesh_bank.get(meshes::MeshBankKeys::Cube));
+    if let Err(error) = state.run_operator("object.add_cube", None) {
+        log::error!("{}", error);
+    }
+}
+
+/// Draws the scene the app starts with into a PNG, without opening a window.
+fn render_startup_scene(path: &str) {
+    let mut scene = Scene::new();
+    let cube = BMesh::create_cube(&mut rand::thread_rng());
+    scene.add_object(SceneObject::new("Cube", cube));
+
+    let size = [1280, 720];
+    let mut camera = Camera::initialize(size[0] as f32, size[1] as f32);
+    let result = HeadlessRenderer::new().and_then(|mut renderer| {
+        renderer.render_to_png(&mut scene, &mut camera, size, std::path::Path::new(path))
+    });
+    if let Err(error) = result {
+        eprintln!("Couldn't render to {}: {}", path, error);
+        std::process::exit(1);
+    }
+}
+
+/// Asks for an adapter the way eframe does, so that the device it requests can be set up for it
+/// before there is a window. eframe also asks for one that works with the window's surface, but
+/// with the same preferences that normally ends up being the same adapter.
+fn request_adapter(
+    configuration: &eframe::egui_wgpu::WgpuConfiguration,
+) -> Option<eframe::wgpu::Adapter> {
+    let instance = eframe::wgpu::Instance::new(eframe::wgpu::InstanceDescriptor {
+        backends: configuration.backends,
+        ..Default::default()
+    });
+    pollster::block_on(
+        instance.request_adapter(&eframe::wgpu::RequestAdapterOptions {
+            power_preference: configuration.power_preference,
+            force_fallback_adapter: false,
+            compatible_surface: None,
+        }),
+    )
 }
 
 fn main() -> Result<(), eframe::Error> {
+    // `fe --render <path>` draws the startup scene into a PNG instead of running the app.
+    let args: Vec<String> = std::env::args().collect();
+    if let [_, flag, path] = args.as_slice() {
+        if flag == "--render" {
+            render_startup_scene(path);
+            return Ok(());
+        }
+    }
+
+    let mut wgpu_options = eframe::egui_wgpu::WgpuConfiguration::default();
+    let adapter = request_adapter(&wgpu_options);
+    // Needed for sample counts other than 1 and 4, where the adapter has it (see
+    // `get_supported_sample_counts()`).
+    wgpu_options.device_descriptor.features = match &adapter {
+        Some(adapter) => {
+            adapter.features() & eframe::wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES
+        }
+        None => eframe::wgpu::Features::empty(),
+    };
+
     let options = eframe::NativeOptions {
         initial_window_size: Some(eframe::egui::vec2(1280.0, 720.0)),
+        // The viewport is drawn into a texture of its own, with its own depth buffer and
+        // multisampling (see `viewport_target.rs`), so egui needs neither.
         multisampling: 1,
-        depth_buffer: 1, // bool
+        depth_buffer: 0,
         renderer: eframe::Renderer::Wgpu,
+        wgpu_options,
         ..Default::default()
     };
 
     eframe::run_native(
         "ekki",
         options,
-        Box::new(|cc| Box::new(App::initialize(cc))),
+        Box::new(move |cc| Box::new(App::initialize(cc, adapter.as_ref()))),
     )
 }
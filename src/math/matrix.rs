@@ -2,6 +2,7 @@ use std::ops::Mul;
 
 use cgmath::SquareMatrix;
 
+use super::point::Point3;
 use super::quaternion::Quaternion;
 use super::vector::{Vector3, Vector4};
 use super::{Float, Radians};
@@ -71,11 +72,47 @@ impl Matrix4 {
         }
     }
 
+    pub fn create_orthographic(
+        half_width: Float,
+        half_height: Float,
+        near_clipping_z: Float,
+        far_clipping_z: Float,
+    ) -> Self {
+        Self {
+            internal: cgmath::ortho(
+                -half_width,
+                half_width,
+                -half_height,
+                half_height,
+                near_clipping_z,
+                far_clipping_z,
+            ),
+        }
+    }
+
     pub fn y(&self) -> Vector4 {
         Vector4 {
             internal: self.internal.y,
         }
     }
+
+    /// Transforms the point, including the division by w. For a projection matrix, check
+    /// that the point is in front of the camera first (see `Mul<Vector4>`).
+    pub fn transform_point(&self, p: Point3) -> Point3 {
+        Point3 {
+            internal: cgmath::Transform::transform_point(&self.internal, p.internal),
+        }
+    }
+}
+
+impl Mul<Vector4> for Matrix4 {
+    type Output = Vector4;
+
+    fn mul(self, rhs: Vector4) -> Self::Output {
+        Vector4 {
+            internal: self.internal * rhs.internal,
+        }
+    }
 }
 
 impl Into<[[Float; 4]; 4]> for Matrix4 {
@@ -1,6 +1,7 @@
 pub mod matrix;
 pub mod point;
 pub mod quaternion;
+pub mod ray;
 pub mod vector;
 
 pub type Float = f32;
@@ -1,4 +1,7 @@
-use std::{hash::Hash, ops::Sub};
+use std::{
+    hash::Hash,
+    ops::{Add, Sub},
+};
 
 use super::{vector::Vector3, Float};
 
@@ -28,6 +31,26 @@ impl Sub for &Point3 {
     }
 }
 
+impl Add<Vector3> for Point3 {
+    type Output = Self;
+
+    fn add(self, rhs: Vector3) -> Self::Output {
+        Self {
+            internal: self.internal + rhs.internal,
+        }
+    }
+}
+
+impl Sub<Vector3> for Point3 {
+    type Output = Self;
+
+    fn sub(self, rhs: Vector3) -> Self::Output {
+        Self {
+            internal: self.internal - rhs.internal,
+        }
+    }
+}
+
 impl Into<[f32; 3]> for Point3 {
     fn into(self) -> [f32; 3] {
         self.internal.into()
@@ -47,6 +70,39 @@ impl Point3 {
         }
     }
 
+    pub fn x(&self) -> Float {
+        self.internal.x
+    }
+
+    pub fn y(&self) -> Float {
+        self.internal.y
+    }
+
+    pub fn z(&self) -> Float {
+        self.internal.z
+    }
+
+    /// Linear interpolation, giving `self` at 0 and `other` at 1.
+    pub fn lerp(&self, other: &Self, t: Float) -> Self {
+        *self + (*other - *self) * t
+    }
+
+    pub fn distance(&self, other: &Self) -> Float {
+        cgmath::MetricSpace::distance(self.internal, other.internal)
+    }
+
+    /// The average of the points. Returns the origin if `points` is empty.
+    pub fn centroid(points: &[Self]) -> Self {
+        if points.is_empty() {
+            return Self::origin();
+        }
+
+        let internals: Vec<cgmath::Point3<Float>> = points.iter().map(|p| p.internal).collect();
+        Self {
+            internal: cgmath::EuclideanSpace::centroid(&internals),
+        }
+    }
+
     pub fn to_vec3(&self) -> Vector3 {
         Vector3 {
             internal: cgmath::EuclideanSpace::to_vec(self.internal),
@@ -1,6 +1,6 @@
 use std::ops::Mul;
 
-use cgmath::{InnerSpace, Rotation3};
+use cgmath::{InnerSpace, Rotation, Rotation3};
 
 use super::{vector::Vector3, Float, Radians};
 
@@ -35,6 +35,19 @@ impl Quaternion {
             internal: self.internal.normalize(),
         }
     }
+
+    /// The opposite rotation.
+    pub fn invert(self) -> Self {
+        Self {
+            internal: self.internal.invert(),
+        }
+    }
+
+    pub fn rotate_vector(&self, v: Vector3) -> Vector3 {
+        Vector3 {
+            internal: self.internal.rotate_vector(v.internal),
+        }
+    }
 }
 
 impl Mul for Quaternion {
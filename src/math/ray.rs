@@ -0,0 +1,59 @@
+use super::{point::Point3, vector::Vector3, Float};
+
+pub struct Ray {
+    pub origin: Point3,
+    /// Always normalized, so that distances along the ray are in world units.
+    pub direction: Vector3,
+}
+
+impl Ray {
+    pub fn new(origin: Point3, direction: Vector3) -> Self {
+        Self {
+            origin,
+            direction: direction.normalize(),
+        }
+    }
+
+    /// The ray starting at `from` and going through `to`.
+    pub fn between(from: Point3, to: Point3) -> Self {
+        Self::new(from, to - from)
+    }
+
+    pub fn at(&self, distance: Float) -> Point3 {
+        self.origin + self.direction * distance
+    }
+
+    /// The distance along the ray to where it hits the triangle, if it does. Both sides of the
+    /// triangle count. This is the Möller–Trumbore algorithm.
+    pub fn intersect_triangle(&self, a: Point3, b: Point3, c: Point3) -> Option<Float> {
+        let edge_ab = b - a;
+        let edge_ac = c - a;
+
+        let p = Vector3::cross(self.direction, edge_ac);
+        let determinant = Vector3::dot(edge_ab, p);
+        if determinant.abs() < Float::EPSILON {
+            // Parallel to the triangle.
+            return None;
+        }
+        let inverse_determinant = 1.0 / determinant;
+
+        let a_to_origin = self.origin - a;
+        let u = Vector3::dot(a_to_origin, p) * inverse_determinant;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let q = Vector3::cross(a_to_origin, edge_ab);
+        let v = Vector3::dot(self.direction, q) * inverse_determinant;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let distance = Vector3::dot(edge_ac, q) * inverse_determinant;
+        if distance < 0.0 {
+            return None;
+        }
+
+        Some(distance)
+    }
+}
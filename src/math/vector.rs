@@ -1,4 +1,4 @@
-use std::ops::{Add, Neg};
+use std::ops::{Add, Mul, Neg, Sub};
 
 use cgmath::InnerSpace;
 
@@ -73,10 +73,31 @@ impl Vector2 {
     }
 }
 
+#[derive(Clone, Copy)]
 pub struct Vector3 {
     pub(super) internal: cgmath::Vector3<Float>,
 }
 
+impl Sub for Vector3 {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self {
+            internal: self.internal - rhs.internal,
+        }
+    }
+}
+
+impl Mul<Float> for Vector3 {
+    type Output = Self;
+
+    fn mul(self, rhs: Float) -> Self::Output {
+        Self {
+            internal: self.internal * rhs,
+        }
+    }
+}
+
 impl Add for Vector3 {
     type Output = Self;
 
@@ -114,6 +135,20 @@ impl Vector3 {
         self.internal.y
     }
 
+    pub fn z(&self) -> Float {
+        self.internal.z
+    }
+
+    pub fn new(x: Float, y: Float, z: Float) -> Self {
+        Self {
+            internal: cgmath::Vector3::new(x, y, z),
+        }
+    }
+
+    pub fn zero() -> Self {
+        Self::new(0.0, 0.0, 0.0)
+    }
+
     pub fn xy(&self) -> Vector2 {
         Vector2 {
             internal: cgmath::Vector2::new(self.x(), self.y()),
@@ -126,16 +161,80 @@ impl Vector3 {
         }
     }
 
+    pub fn unit_x() -> Self {
+        Self {
+            internal: cgmath::Vector3::unit_x(),
+        }
+    }
+
+    pub fn unit_z() -> Self {
+        Self {
+            internal: cgmath::Vector3::unit_z(),
+        }
+    }
+
     pub fn dot(v1: Self, v2: Self) -> Float {
         cgmath::dot(v1.internal, v2.internal)
     }
+
+    pub fn cross(v1: Self, v2: Self) -> Self {
+        Self {
+            internal: v1.internal.cross(v2.internal),
+        }
+    }
+
+    pub fn length(&self) -> Float {
+        self.internal.magnitude()
+    }
+
+    /// Returns the zero vector unchanged rather than dividing by zero.
+    pub fn normalize(self) -> Self {
+        let length = self.length();
+        if length <= Float::EPSILON {
+            return self;
+        }
+
+        Self {
+            internal: self.internal / length,
+        }
+    }
 }
 
+impl From<Vector3> for [f32; 3] {
+    fn from(vector: Vector3) -> Self {
+        vector.internal.into()
+    }
+}
+
+#[derive(Clone, Copy)]
 pub struct Vector4 {
     pub(super) internal: cgmath::Vector4<Float>,
 }
 
 impl Vector4 {
+    pub fn new(x: Float, y: Float, z: Float, w: Float) -> Self {
+        Self {
+            internal: cgmath::Vector4::new(x, y, z, w),
+        }
+    }
+
+    /// The point in homogeneous coordinates, i.e. with a w of 1.
+    pub fn from_point(p: Point3) -> Self {
+        Self::new(p.x(), p.y(), p.z(), 1.0)
+    }
+
+    pub fn x(&self) -> Float {
+        self.internal.x
+    }
+
+    pub fn y(&self) -> Float {
+        self.internal.y
+    }
+
+    pub fn w(&self) -> Float {
+        self.internal.w
+    }
+
     pub fn truncate(self) -> Vector3 {
         Vector3 {
             internal: self.internal.truncate(),
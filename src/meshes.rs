@@ -1,124 +1,45 @@
-use std::collections::HashMap;
-use std::sync::Arc;
-
+use crate::bmesh::bmesh::BMesh;
+use crate::math::point::Point3;
 use crate::vertex::Vertex;
 
 pub struct Mesh {
     pub vertices: Vec<Vertex>,
-    pub indices: Vec<u16>,
+    pub indices: Vec<u32>,
+    /// The color of the object, which the colors of the vertices are multiplied with.
+    pub base_color: [f32; 3],
 }
 
 impl Mesh {
-    fn new_from_slice(vertices: &[Vertex], indices: &[u16]) -> Self {
-        Self {
-            vertices: vertices.to_vec(),
-            indices: indices.to_vec(),
+    /// Flattens the faces of the `BMesh` into triangles. Every triangle gets its own vertices,
+    /// with the split normals of the corners they come from (see `BMesh::get_corner_normals()`).
+    /// The vertices are white, so that they take the base color as it is.
+    pub fn from_bmesh(bmesh: &BMesh, base_color: [f32; 3]) -> Self {
+        let mut vertices = Vec::new();
+        let corner_normals = bmesh.get_corner_normals();
+        for (face, normals) in bmesh.get_faces().iter().zip(corner_normals) {
+            let face = face.as_ref().borrow();
+            let positions: Vec<Point3> = face
+                .get_vertices()
+                .iter()
+                .map(|v| v.as_ref().borrow().get_position())
+                .collect();
+
+            for triangle in face.get_triangle_indices() {
+                for corner in triangle {
+                    vertices.push(Vertex {
+                        position: positions[corner].into(),
+                        normal: normals[corner].into(),
+                        color: [1.0, 1.0, 1.0],
+                    });
+                }
+            }
         }
-    }
-}
-
-// S==== MESH BANK {{{1
-
-#[derive(PartialEq, Eq, Hash)]
-pub enum MeshBankKeys {
-    Cube,
-}
-
-/// This is a cache for default meshes.
-pub struct MeshBank {
-    // Could probably be an array, since we are only querying with enums
-    map: HashMap<MeshBankKeys, Arc<Mesh>>,
-}
-
-impl MeshBank {
-    pub fn initialize() -> Self {
-        let map = HashMap::from([(
-            MeshBankKeys::Cube,
-            Arc::new(Mesh::new_from_slice(CUBE_VERTICES, CUBE_INDICES)),
-        )]);
+        let indices = (0..vertices.len() as u32).collect();
 
-        Self { map }
-    }
-
-    pub fn get(&self, key: MeshBankKeys) -> Arc<Mesh> {
-        if let Some(mesh) = self.map.get(&key) {
-            return mesh.clone();
+        Self {
+            vertices,
+            indices,
+            base_color,
         }
-
-        panic!()
     }
 }
-
-// E==== MESH BANK }}}1
-
-// S==== CONSTANTS {{{1
-
-const CUBE_VERTICES: &[Vertex] = &[
-    // front face
-    Vertex {
-        // 0
-        position: [-0.5, 0.5, -0.5],
-        color: [0.9, 0., 0.],
-    },
-    Vertex {
-        // 1
-        position: [0.5, 0.5, -0.5],
-        color: [0.9, 0., 0.],
-    },
-    Vertex {
-        // 2
-        position: [-0.5, -0.5, -0.5],
-        color: [0.9, 0.9, 0.9],
-    },
-    Vertex {
-        // 3
-        position: [0.5, -0.5, -0.5],
-        color: [0.9, 0.9, 0.9],
-    },
-    // right face
-    Vertex {
-        // 4
-        position: [0.5, 0.5, 0.5],
-        color: [0.9, 0., 0.],
-    },
-    Vertex {
-        // 5
-        position: [0.5, -0.5, 0.5],
-        color: [0.9, 0.9, 0.9],
-    },
-    // left face
-    Vertex {
-        // 6
-        position: [-0.5, 0.5, 0.5],
-        color: [0.9, 0., 0.],
-    },
-    Vertex {
-        // 7
-        position: [-0.5, -0.5, 0.5],
-        color: [0.9, 0.9, 0.9],
-    },
-];
-
-#[rustfmt::skip]
-const CUBE_INDICES: &[u16] = &[
-    // front face
-    0, 1, 2,
-    2, 1, 3, 
-    // right face
-    1, 4, 3,
-    3, 4, 5,
-    // left face
-    0, 7, 6,
-    0, 2, 7,
-    // back face
-    6, 7, 4,
-    7, 5, 4,
-    // top face
-    1, 0, 6,
-    1, 6, 4,
-    // bottom face
-    2, 3, 7,
-    3, 5, 7,
-];
-
-// E==== CONSTANTS }}}1
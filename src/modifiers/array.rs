@@ -0,0 +1,36 @@
+use crate::{
+    bmesh::bmesh::BMesh,
+    math::{point::Point3, vector::Vector3},
+};
+
+/// Repeats the mesh `count` times, each copy moved by `offset` from the previous one.
+#[derive(Clone)]
+pub struct ArrayModifier {
+    pub count: usize,
+    pub offset: Vector3,
+}
+
+impl ArrayModifier {
+    pub fn new(count: usize, offset: Vector3) -> Self {
+        Self { count, offset }
+    }
+
+    pub fn apply(&self, mesh: &BMesh, rng: &mut impl rand::Rng) -> BMesh {
+        let (points, polygons) = mesh.to_polygons();
+
+        let mut array_points: Vec<Point3> = Vec::with_capacity(points.len() * self.count);
+        let mut array_polygons: Vec<Vec<usize>> = Vec::with_capacity(polygons.len() * self.count);
+        for copy in 0..self.count {
+            let first_index = array_points.len();
+            let offset = self.offset * copy as f32;
+            array_points.extend(points.iter().map(|p| *p + offset));
+            array_polygons.extend(
+                polygons
+                    .iter()
+                    .map(|polygon| polygon.iter().map(|i| i + first_index).collect()),
+            );
+        }
+
+        BMesh::from_polygons(rng, &array_points, &array_polygons)
+    }
+}
@@ -0,0 +1,208 @@
+//! Cutting off the edges and corners of the mesh with flat faces (a chamfer).
+//!
+//! Every face shrinks away from its sides, each edge between two faces turns into a strip
+//! connecting the two shrunken faces, and each vertex turns into a face closing the gap
+//! between the strips around it.
+
+use std::collections::HashMap;
+
+use log::warn;
+
+use crate::{
+    bmesh::bmesh::BMesh,
+    math::{point::Point3, Float},
+};
+
+#[derive(Clone)]
+pub struct BevelModifier {
+    /// How far the new corners are from the original ones, along the sides of the faces.
+    pub width: Float,
+}
+
+impl BevelModifier {
+    pub fn new(width: Float) -> Self {
+        Self { width }
+    }
+
+    pub fn apply(&self, mesh: &BMesh, rng: &mut impl rand::Rng) -> BMesh {
+        let (points, polygons) = mesh.to_polygons();
+
+        // Which face each half edge belongs to. The strips and corner faces are found by going
+        // from a half edge to the one going the other way, which needs every edge to have at
+        // most one face on either side.
+        let mut face_of: HashMap<(usize, usize), usize> = HashMap::new();
+        for (face_index, polygon) in polygons.iter().enumerate() {
+            for i in 0..polygon.len() {
+                let half_edge = (polygon[i], polygon[(i + 1) % polygon.len()]);
+                if face_of.insert(half_edge, face_index).is_some() {
+                    warn!("can't bevel a mesh with edges that have more than two faces");
+                    return BMesh::from_polygons(rng, &points, &polygons);
+                }
+            }
+        }
+
+        // The original vertices come first, since the boundary keeps them (the others are
+        // dropped at the end). Then the shrunken corner of each face, keyed by (face, vertex).
+        let mut new_points: Vec<Point3> = points.clone();
+        let mut corner_of: HashMap<(usize, usize), usize> = HashMap::new();
+        for (face_index, polygon) in polygons.iter().enumerate() {
+            let n = polygon.len();
+            for i in 0..n {
+                let p = points[polygon[i]];
+                let to_previous = (points[polygon[(i + n - 1) % n]] - p).normalize();
+                let to_next = (points[polygon[(i + 1) % n]] - p).normalize();
+                corner_of.insert((face_index, polygon[i]), new_points.len());
+                new_points.push(p + (to_previous + to_next) * self.width);
+            }
+        }
+
+        let mut new_polygons: Vec<Vec<usize>> = polygons
+            .iter()
+            .enumerate()
+            .map(|(face_index, polygon)| {
+                polygon
+                    .iter()
+                    .map(|v| corner_of[&(face_index, *v)])
+                    .collect()
+            })
+            .collect();
+
+        // A strip for every edge. Each one is added from the face on one side: for edges with a
+        // face on the other side too, from the half edge with the lower first vertex.
+        for (face_index, polygon) in polygons.iter().enumerate() {
+            for i in 0..polygon.len() {
+                let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+                let near_a = corner_of[&(face_index, a)];
+                let near_b = corner_of[&(face_index, b)];
+                match face_of.get(&(b, a)) {
+                    Some(other_face) if a < b => new_polygons.push(vec![
+                        near_b,
+                        near_a,
+                        corner_of[&(*other_face, a)],
+                        corner_of[&(*other_face, b)],
+                    ]),
+                    Some(_) => {}
+                    None => new_polygons.push(vec![near_b, near_a, a, b]),
+                }
+            }
+        }
+
+        // A face for every vertex, going through the shrunken corners around it. Going around,
+        // the next face is the one on the other side of the edge coming into the vertex.
+        for (start_face, polygon) in polygons.iter().enumerate() {
+            for &v in polygon {
+                let next_face = |face: usize| -> Option<usize> {
+                    let corners = &polygons[face];
+                    let j = corners.iter().position(|c| *c == v).unwrap();
+                    let previous = corners[(j + corners.len() - 1) % corners.len()];
+                    face_of.get(&(v, previous)).copied()
+                };
+
+                // Each vertex is handled once, from its face with the lowest index. On a
+                // boundary the walk is started from the face it can't be reached from.
+                let mut is_first = true;
+                let mut first_face = start_face;
+                let mut face = start_face;
+                let mut is_closed = false;
+                loop {
+                    match next_face(face) {
+                        Some(next) if next == start_face => {
+                            is_closed = true;
+                            break;
+                        }
+                        Some(next) => {
+                            if next < start_face {
+                                is_first = false;
+                            }
+                            face = next;
+                        }
+                        None => break,
+                    }
+                }
+                if !is_closed {
+                    // Walk backwards to the start of the fan.
+                    let previous_face = |face: usize| -> Option<usize> {
+                        let corners = &polygons[face];
+                        let j = corners.iter().position(|c| *c == v).unwrap();
+                        let next = corners[(j + 1) % corners.len()];
+                        face_of.get(&(next, v)).copied()
+                    };
+                    first_face = start_face;
+                    while let Some(previous) = previous_face(first_face) {
+                        if previous < start_face {
+                            is_first = false;
+                        }
+                        first_face = previous;
+                    }
+                }
+                if !is_first {
+                    continue;
+                }
+
+                let mut corner_face = vec![corner_of[&(first_face, v)]];
+                let mut face = first_face;
+                while let Some(next) = next_face(face) {
+                    if next == first_face {
+                        break;
+                    }
+                    corner_face.push(corner_of[&(next, v)]);
+                    face = next;
+                }
+                if !is_closed {
+                    corner_face.push(v);
+                }
+
+                if corner_face.len() >= 3 {
+                    new_polygons.push(corner_face);
+                }
+            }
+        }
+
+        let (new_points, new_polygons) = remove_unused_points(&new_points, &new_polygons);
+        BMesh::from_polygons(rng, &new_points, &new_polygons)
+    }
+}
+
+/// Leaves out the points that no polygon uses, so that they don't end up as isolated vertices.
+fn remove_unused_points(
+    points: &[Point3],
+    polygons: &[Vec<usize>],
+) -> (Vec<Point3>, Vec<Vec<usize>>) {
+    let mut used_points: Vec<Point3> = Vec::new();
+    let mut new_index_of: HashMap<usize, usize> = HashMap::new();
+    let used_polygons = polygons
+        .iter()
+        .map(|polygon| {
+            polygon
+                .iter()
+                .map(|i| {
+                    *new_index_of.entry(*i).or_insert_with(|| {
+                        used_points.push(points[*i]);
+                        used_points.len() - 1
+                    })
+                })
+                .collect()
+        })
+        .collect();
+
+    (used_points, used_polygons)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn beveling_a_cube_leaves_no_loose_vertices() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let cube = BMesh::create_cube(&mut rng);
+
+        let beveled = BevelModifier::new(0.1).apply(&cube, &mut rng);
+        // 6 shrunken faces, 12 strips and 8 corner triangles.
+        assert_eq!(beveled.get_faces().len(), 26);
+        assert_eq!(beveled.get_vertices().len(), 24);
+        assert!(beveled.validate().is_empty());
+    }
+}
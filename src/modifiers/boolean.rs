@@ -0,0 +1,39 @@
+use crate::bmesh::{bmesh::BMesh, bmesh_boolean::BooleanOperation};
+
+use super::ModifierContext;
+
+/// Combines the mesh with the mesh of another object (see `BMesh::boolean()`).
+#[derive(Clone)]
+pub struct BooleanModifier {
+    pub operation: BooleanOperation,
+    /// Name of the object to combine with. Until there is one, the mesh is left as it is.
+    pub cutter: Option<String>,
+}
+
+impl BooleanModifier {
+    pub fn new(operation: BooleanOperation) -> Self {
+        Self {
+            operation,
+            cutter: None,
+        }
+    }
+
+    pub fn apply(
+        &self,
+        mesh: &BMesh,
+        context: &ModifierContext,
+        rng: &mut impl rand::Rng,
+    ) -> BMesh {
+        match self.get_cutter_mesh(context) {
+            Some(cutter) => mesh.boolean(cutter, self.operation, rng),
+            None => {
+                let (points, polygons) = mesh.to_polygons();
+                BMesh::from_polygons(rng, &points, &polygons)
+            }
+        }
+    }
+
+    pub fn get_cutter_mesh<'a>(&self, context: &ModifierContext<'a>) -> Option<&'a BMesh> {
+        context.find_mesh(self.cutter.as_ref()?)
+    }
+}
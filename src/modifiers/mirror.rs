@@ -0,0 +1,63 @@
+use crate::{
+    bmesh::{bmesh::BMesh, bmesh_mirror::MirrorAxis},
+    math::{point::Point3, Float},
+};
+
+/// Adds a copy of the mesh reflected across one of the axis planes (in object space).
+#[derive(Clone)]
+pub struct MirrorModifier {
+    pub axis: MirrorAxis,
+    /// Whether vertices on the mirror plane are shared by both halves, which joins the halves
+    /// into one surface instead of two that only touch.
+    pub merge: bool,
+    /// How close to the plane a vertex has to be to get merged. Merged vertices are moved
+    /// onto the plane.
+    pub merge_threshold: Float,
+}
+
+impl MirrorModifier {
+    pub fn new(axis: MirrorAxis) -> Self {
+        Self {
+            axis,
+            merge: true,
+            merge_threshold: 0.001,
+        }
+    }
+
+    pub fn apply(&self, mesh: &BMesh, rng: &mut impl rand::Rng) -> BMesh {
+        let (mut points, mut polygons) = mesh.to_polygons();
+        let point_count = points.len();
+        let polygon_count = polygons.len();
+
+        let is_on_plane: Vec<bool> = points
+            .iter()
+            .map(|p| self.merge && self.axis.signed_distance(p).abs() <= self.merge_threshold)
+            .collect();
+
+        // Where each vertex ends up in the mirrored half. Merged vertices are their own mirror.
+        let mut mirror_index: Vec<usize> = Vec::with_capacity(point_count);
+        let mut mirrored_points: Vec<Point3> = Vec::new();
+        for (i, p) in points.iter_mut().enumerate() {
+            if is_on_plane[i] {
+                *p = self.axis.project(p);
+                mirror_index.push(i);
+            } else {
+                mirror_index.push(point_count + mirrored_points.len());
+                mirrored_points.push(self.axis.reflect(p));
+            }
+        }
+        points.extend(mirrored_points);
+
+        // Reflecting flips which way the faces wind, so they are reversed to keep facing out.
+        for i in 0..polygon_count {
+            // A face lying in the plane would be mirrored onto itself.
+            if polygons[i].iter().all(|j| is_on_plane[*j]) {
+                continue;
+            }
+            let mirrored_polygon = polygons[i].iter().rev().map(|j| mirror_index[*j]).collect();
+            polygons.push(mirrored_polygon);
+        }
+
+        BMesh::from_polygons(rng, &points, &polygons)
+    }
+}
@@ -0,0 +1,218 @@
+//! Modifiers derive a new mesh from an object's mesh without changing it, so that the object
+//! stays editable while what gets displayed (and exported) is e.g. mirrored or smoothed. They
+//! are evaluated in order, each one working on the result of the one before it.
+
+use crate::{bmesh::bmesh::BMesh, scene::SceneObject};
+
+use self::{
+    array::ArrayModifier, bevel::BevelModifier, boolean::BooleanModifier, mirror::MirrorModifier,
+    solidify::SolidifyModifier, subdivision::SubdivisionModifier, triangulate::TriangulateModifier,
+};
+
+pub mod array;
+pub mod bevel;
+pub mod boolean;
+pub mod mirror;
+pub mod solidify;
+pub mod subdivision;
+pub mod triangulate;
+
+#[derive(Clone)]
+pub enum Modifier {
+    Mirror(MirrorModifier),
+    Array(ArrayModifier),
+    Subdivision(SubdivisionModifier),
+    Solidify(SolidifyModifier),
+    Triangulate(TriangulateModifier),
+    Bevel(BevelModifier),
+    Boolean(BooleanModifier),
+}
+
+/// The rest of the scene, for modifiers that use other objects.
+pub struct ModifierContext<'a> {
+    pub other_objects: Vec<&'a SceneObject>,
+}
+
+impl<'a> ModifierContext<'a> {
+    pub fn find_mesh(&self, name: &str) -> Option<&'a BMesh> {
+        self.other_objects
+            .iter()
+            .find(|object| object.name == name)
+            .map(|object| &object.mesh)
+    }
+}
+
+impl Modifier {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Self::Mirror(_) => "Mirror",
+            Self::Array(_) => "Array",
+            Self::Subdivision(_) => "Subdivision",
+            Self::Solidify(_) => "Solidify",
+            Self::Triangulate(_) => "Triangulate",
+            Self::Bevel(_) => "Bevel",
+            Self::Boolean(_) => "Boolean",
+        }
+    }
+
+    pub fn apply(
+        &self,
+        mesh: &BMesh,
+        context: &ModifierContext,
+        rng: &mut impl rand::Rng,
+    ) -> BMesh {
+        match self {
+            Self::Mirror(modifier) => modifier.apply(mesh, rng),
+            Self::Array(modifier) => modifier.apply(mesh, rng),
+            Self::Subdivision(modifier) => modifier.apply(mesh, rng),
+            Self::Solidify(modifier) => modifier.apply(mesh, rng),
+            Self::Triangulate(modifier) => modifier.apply(mesh, rng),
+            Self::Bevel(modifier) => modifier.apply(mesh, rng),
+            Self::Boolean(modifier) => modifier.apply(mesh, context, rng),
+        }
+    }
+}
+
+pub struct ModifierStackEntry {
+    modifier: Modifier,
+    enabled: bool,
+}
+
+impl ModifierStackEntry {
+    pub fn get_modifier(&self) -> &Modifier {
+        &self.modifier
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+}
+
+struct EvaluatedMesh {
+    mesh_revision: u64,
+    stack_revision: u64,
+    /// Revisions of the meshes of other objects the modifiers use, see
+    /// `get_dependency_revisions()`.
+    dependency_revisions: Vec<Option<u64>>,
+    mesh: BMesh,
+}
+
+/// The modifiers of an object, in the order they are evaluated. The result of the last
+/// evaluation is kept around, and only evaluated again once the stack or the mesh changes.
+pub struct ModifierStack {
+    entries: Vec<ModifierStackEntry>,
+    /// Bumped on every change to the stack, to tell whether `evaluated` is out of date.
+    revision: u64,
+    evaluated: Option<EvaluatedMesh>,
+}
+
+impl ModifierStack {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            revision: 0,
+            evaluated: None,
+        }
+    }
+
+    pub fn get_entries(&self) -> &Vec<ModifierStackEntry> {
+        &self.entries
+    }
+
+    pub fn has_enabled_modifiers(&self) -> bool {
+        self.entries.iter().any(|entry| entry.enabled)
+    }
+
+    /// Adds the modifier at the end of the stack, enabled.
+    pub fn push(&mut self, modifier: Modifier) {
+        self.entries.push(ModifierStackEntry {
+            modifier,
+            enabled: true,
+        });
+        self.revision += 1;
+    }
+
+    pub fn remove(&mut self, index: usize) -> ModifierStackEntry {
+        self.revision += 1;
+        self.entries.remove(index)
+    }
+
+    /// Moves the modifier at `index` to `new_index`, shifting the ones in between.
+    pub fn move_modifier(&mut self, index: usize, new_index: usize) {
+        let entry = self.entries.remove(index);
+        self.entries
+            .insert(new_index.min(self.entries.len()), entry);
+        self.revision += 1;
+    }
+
+    pub fn set_enabled(&mut self, index: usize, enabled: bool) {
+        if self.entries[index].enabled != enabled {
+            self.entries[index].enabled = enabled;
+            self.revision += 1;
+        }
+    }
+
+    /// For changing the settings of a modifier. The stack is assumed to have changed, since
+    /// there is no telling what the caller did with the modifier.
+    pub fn get_modifier_mut(&mut self, index: usize) -> &mut Modifier {
+        self.revision += 1;
+        &mut self.entries[index].modifier
+    }
+
+    /// The revisions of the meshes of other objects that the enabled modifiers use, so that
+    /// the result gets evaluated again when one of them changes.
+    fn get_dependency_revisions(&self, context: &ModifierContext) -> Vec<Option<u64>> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.enabled)
+            .filter_map(|entry| match &entry.modifier {
+                Modifier::Boolean(boolean) => Some(
+                    boolean
+                        .get_cutter_mesh(context)
+                        .map(|mesh| mesh.get_revision()),
+                ),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The result of running the mesh through the enabled modifiers, in order.
+    pub fn evaluate(
+        &mut self,
+        mesh: &BMesh,
+        context: &ModifierContext,
+        rng: &mut impl rand::Rng,
+    ) -> &BMesh {
+        let dependency_revisions = self.get_dependency_revisions(context);
+        let is_up_to_date = self.evaluated.as_ref().is_some_and(|evaluated| {
+            evaluated.mesh_revision == mesh.get_revision()
+                && evaluated.stack_revision == self.revision
+                && evaluated.dependency_revisions == dependency_revisions
+        });
+
+        if !is_up_to_date {
+            let mut enabled = self.entries.iter().filter(|entry| entry.enabled);
+            let mut result = match enabled.next() {
+                Some(entry) => entry.modifier.apply(mesh, context, rng),
+                None => {
+                    let (points, polygons) = mesh.to_polygons();
+                    BMesh::from_polygons(rng, &points, &polygons)
+                }
+            };
+            for entry in enabled {
+                result = entry.modifier.apply(&result, context, rng);
+            }
+            // Sharp edges don't survive the modifiers, but smoothing by angle still applies.
+            result.set_auto_smooth_angle(mesh.get_auto_smooth_angle());
+
+            self.evaluated = Some(EvaluatedMesh {
+                mesh_revision: mesh.get_revision(),
+                stack_revision: self.revision,
+                dependency_revisions,
+                mesh: result,
+            });
+        }
+
+        &self.evaluated.as_ref().unwrap().mesh
+    }
+}
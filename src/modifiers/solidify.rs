@@ -0,0 +1,66 @@
+use std::collections::HashSet;
+
+use crate::{
+    bmesh::{bmesh::BMesh, bmesh_face::newell_normal},
+    math::{point::Point3, vector::Vector3, Float},
+};
+
+/// Gives the surface a thickness, by adding a copy of it moved inwards (against the normals)
+/// and closing the gap along open edges.
+#[derive(Clone)]
+pub struct SolidifyModifier {
+    pub thickness: Float,
+}
+
+impl SolidifyModifier {
+    pub fn new(thickness: Float) -> Self {
+        Self { thickness }
+    }
+
+    pub fn apply(&self, mesh: &BMesh, rng: &mut impl rand::Rng) -> BMesh {
+        let (mut points, mut polygons) = mesh.to_polygons();
+        let point_count = points.len();
+        let polygon_count = polygons.len();
+
+        // Each vertex moves along the average of the normals of the faces around it.
+        let mut normals: Vec<Vector3> = vec![Vector3::zero(); point_count];
+        for polygon in &polygons {
+            let corners: Vec<Point3> = polygon.iter().map(|i| points[*i]).collect();
+            let normal = newell_normal(&corners);
+            for i in polygon {
+                normals[*i] = normals[*i] + normal;
+            }
+        }
+        let inner_points: Vec<Point3> = points
+            .iter()
+            .zip(&normals)
+            .map(|(p, n)| *p - n.normalize() * self.thickness)
+            .collect();
+        points.extend(inner_points);
+
+        // The inside faces the other way, so its faces are reversed.
+        for i in 0..polygon_count {
+            let inner_polygon = polygons[i].iter().rev().map(|j| j + point_count).collect();
+            polygons.push(inner_polygon);
+        }
+
+        // Sides along the edges that only have a face on one side.
+        let half_edges: HashSet<(usize, usize)> = polygons[..polygon_count]
+            .iter()
+            .flat_map(|polygon| {
+                (0..polygon.len()).map(move |i| (polygon[i], polygon[(i + 1) % polygon.len()]))
+            })
+            .collect();
+        for i in 0..polygon_count {
+            let polygon = polygons[i].clone();
+            for j in 0..polygon.len() {
+                let (a, b) = (polygon[j], polygon[(j + 1) % polygon.len()]);
+                if !half_edges.contains(&(b, a)) {
+                    polygons.push(vec![b, a, a + point_count, b + point_count]);
+                }
+            }
+        }
+
+        BMesh::from_polygons(rng, &points, &polygons)
+    }
+}
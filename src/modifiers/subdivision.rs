@@ -0,0 +1,151 @@
+//! Catmull-Clark subdivision. Every face gets split into quads around a new vertex at its
+//! center, and all vertices get moved towards the average of their surroundings, which rounds
+//! the mesh off a bit more with each level.
+
+use std::collections::HashMap;
+
+use crate::{
+    bmesh::bmesh::BMesh,
+    math::{point::Point3, vector::Vector3, Float},
+};
+
+#[derive(Clone)]
+pub struct SubdivisionModifier {
+    /// How many times to subdivide. Each level multiplies the face count by about four.
+    pub levels: usize,
+}
+
+impl SubdivisionModifier {
+    pub fn new(levels: usize) -> Self {
+        Self { levels }
+    }
+
+    pub fn apply(&self, mesh: &BMesh, rng: &mut impl rand::Rng) -> BMesh {
+        let (mut points, mut polygons) = mesh.to_polygons();
+        for _ in 0..self.levels {
+            (points, polygons) = subdivide(&points, &polygons);
+        }
+
+        BMesh::from_polygons(rng, &points, &polygons)
+    }
+}
+
+fn subdivide(points: &[Point3], polygons: &[Vec<usize>]) -> (Vec<Point3>, Vec<Vec<usize>>) {
+    let edge_key = |a: usize, b: usize| (usize::min(a, b), usize::max(a, b));
+
+    let face_points: Vec<Point3> = polygons
+        .iter()
+        .map(|polygon| {
+            let corners: Vec<Point3> = polygon.iter().map(|i| points[*i]).collect();
+            Point3::centroid(&corners)
+        })
+        .collect();
+
+    // The faces on either side of each edge.
+    let mut edge_faces: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+    for (face_index, polygon) in polygons.iter().enumerate() {
+        for i in 0..polygon.len() {
+            let key = edge_key(polygon[i], polygon[(i + 1) % polygon.len()]);
+            edge_faces.entry(key).or_default().push(face_index);
+        }
+    }
+    let is_boundary = |faces: &Vec<usize>| faces.len() != 2;
+
+    // Positions of the new vertices: the original vertices (moved), then the face points, then
+    // the edge points.
+    let mut new_points: Vec<Point3> = Vec::new();
+
+    let mut vertex_faces: Vec<Vec<usize>> = vec![Vec::new(); points.len()];
+    for (face_index, polygon) in polygons.iter().enumerate() {
+        for i in polygon {
+            vertex_faces[*i].push(face_index);
+        }
+    }
+    let mut vertex_edges: Vec<Vec<(usize, usize)>> = vec![Vec::new(); points.len()];
+    for (a, b) in edge_faces.keys() {
+        vertex_edges[*a].push((*a, *b));
+        vertex_edges[*b].push((*a, *b));
+    }
+
+    for (i, p) in points.iter().enumerate() {
+        let boundary_neighbours: Vec<usize> = vertex_edges[i]
+            .iter()
+            .filter(|e| is_boundary(&edge_faces[*e]))
+            .map(|(a, b)| if *a == i { *b } else { *a })
+            .collect();
+
+        let moved = if vertex_faces[i].is_empty() {
+            *p
+        } else if !boundary_neighbours.is_empty() {
+            // Along a boundary only the boundary counts, so that open edges stay in place
+            // instead of shrinking into the mesh. Corners where the boundary isn't a simple
+            // line are left alone.
+            if boundary_neighbours.len() == 2 {
+                let a = points[boundary_neighbours[0]].to_vec3();
+                let b = points[boundary_neighbours[1]].to_vec3();
+                Point3::origin() + (p.to_vec3() * 6.0 + a + b) * (1.0 / 8.0)
+            } else {
+                *p
+            }
+        } else {
+            let n = vertex_faces[i].len() as Float;
+            let face_average = average(vertex_faces[i].iter().map(|f| face_points[*f]));
+            let edge_midpoint_average = average(
+                vertex_edges[i]
+                    .iter()
+                    .map(|(a, b)| points[*a].lerp(&points[*b], 0.5)),
+            );
+            Point3::origin()
+                + (face_average + edge_midpoint_average * 2.0 + p.to_vec3() * (n - 3.0))
+                    * (1.0 / n)
+        };
+        new_points.push(moved);
+    }
+
+    let first_face_point = new_points.len();
+    new_points.extend(face_points.iter().copied());
+
+    let mut edge_point_index: HashMap<(usize, usize), usize> = HashMap::new();
+    for ((a, b), faces) in &edge_faces {
+        let midpoint = points[*a].lerp(&points[*b], 0.5);
+        let edge_point = if is_boundary(faces) {
+            midpoint
+        } else {
+            Point3::origin()
+                + (midpoint.to_vec3() * 2.0
+                    + face_points[faces[0]].to_vec3()
+                    + face_points[faces[1]].to_vec3())
+                    * 0.25
+        };
+        edge_point_index.insert((*a, *b), new_points.len());
+        new_points.push(edge_point);
+    }
+
+    let mut new_polygons: Vec<Vec<usize>> = Vec::new();
+    for (face_index, polygon) in polygons.iter().enumerate() {
+        let n = polygon.len();
+        for i in 0..n {
+            let previous = polygon[(i + n - 1) % n];
+            let current = polygon[i];
+            let next = polygon[(i + 1) % n];
+            new_polygons.push(vec![
+                current,
+                edge_point_index[&edge_key(current, next)],
+                first_face_point + face_index,
+                edge_point_index[&edge_key(previous, current)],
+            ]);
+        }
+    }
+
+    (new_points, new_polygons)
+}
+
+fn average(points: impl Iterator<Item = Point3>) -> Vector3 {
+    let mut sum = Vector3::zero();
+    let mut count = 0;
+    for p in points {
+        sum = sum + p.to_vec3();
+        count += 1;
+    }
+    sum * (1.0 / count as Float)
+}
@@ -0,0 +1,28 @@
+use crate::bmesh::bmesh::BMesh;
+
+/// Splits every face into the triangles it is drawn with.
+#[derive(Clone)]
+pub struct TriangulateModifier;
+
+impl TriangulateModifier {
+    pub fn apply(&self, mesh: &BMesh, rng: &mut impl rand::Rng) -> BMesh {
+        let (points, polygons) = mesh.to_polygons();
+
+        // `to_polygons()` keeps the faces in order, so each polygon lines up with its face.
+        let triangles: Vec<Vec<usize>> = mesh
+            .get_faces()
+            .iter()
+            .zip(&polygons)
+            .flat_map(|(face, polygon)| {
+                face.as_ref()
+                    .borrow()
+                    .get_triangle_indices()
+                    .into_iter()
+                    .map(|[a, b, c]| vec![polygon[a], polygon[b], polygon[c]])
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        BMesh::from_polygons(rng, &points, &triangles)
+    }
+}
@@ -0,0 +1,868 @@
+//! Everything the user can do to the scene, the meshes, the view or files, registered as named
+//! operators. Each has an id (for keymaps and scripts), a name (for menus, the command palette
+//! and undo history), a check for whether it can run right now, and typed parameters with
+//! defaults. Menus and the command palette (see `panels/command_palette.rs`) are built from the
+//! registry rather than calling into the scene directly.
+
+use crate::{
+    application_state::ApplicationState,
+    bmesh::{
+        bmesh::BMesh, bmesh_boolean::BooleanOperation, bmesh_delete::DeleteMode,
+        bmesh_fill::FillMode, bmesh_merge::MergeTarget,
+    },
+    camera::ViewPreset,
+    keymap::{Keymap, KeymapPreset},
+    rendering::{grid::GridSettings, headless::HeadlessRenderer},
+    scene::{SceneCamera, SceneObject},
+    tools::knife::KnifeTool,
+};
+
+/// What an operator works on, which is how menus group them.
+#[derive(Clone, Copy, PartialEq)]
+pub enum OperatorCategory {
+    Add,
+    Object,
+    Mesh,
+    View,
+    File,
+}
+
+impl OperatorCategory {
+    pub const ALL: [OperatorCategory; 5] = [
+        OperatorCategory::Add,
+        OperatorCategory::Object,
+        OperatorCategory::Mesh,
+        OperatorCategory::View,
+        OperatorCategory::File,
+    ];
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            OperatorCategory::Add => "Add",
+            OperatorCategory::Object => "Object",
+            OperatorCategory::Mesh => "Mesh",
+            OperatorCategory::View => "View",
+            OperatorCategory::File => "File",
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub enum ParameterValue {
+    Bool(bool),
+    Integer {
+        value: i32,
+        min: i32,
+        max: i32,
+    },
+    Float {
+        value: f32,
+        min: f32,
+        max: f32,
+    },
+    /// One of the options, by index.
+    Choice {
+        options: &'static [&'static str],
+        selected: usize,
+    },
+    Text(String),
+}
+
+/// A setting of an operator, e.g. what to delete out of the selection.
+#[derive(Clone, PartialEq)]
+pub struct Parameter {
+    /// Also what the operator looks the parameter up by.
+    pub name: &'static str,
+    pub value: ParameterValue,
+}
+
+impl Parameter {
+    fn new(name: &'static str, value: ParameterValue) -> Self {
+        Self { name, value }
+    }
+}
+
+/// The parameters an operator runs with. Operators only ask for the parameters they declared,
+/// with their types, so a missing or mistyped one is a bug and panics.
+#[derive(Clone, PartialEq)]
+pub struct OperatorParameters(pub Vec<Parameter>);
+
+impl OperatorParameters {
+    fn get(&self, name: &str) -> &ParameterValue {
+        &self
+            .0
+            .iter()
+            .find(|parameter| parameter.name == name)
+            .unwrap_or_else(|| panic!("No parameter named {}", name))
+            .value
+    }
+
+    pub fn get_bool(&self, name: &str) -> bool {
+        match self.get(name) {
+            ParameterValue::Bool(value) => *value,
+            _ => panic!("{} isn't a bool", name),
+        }
+    }
+
+    pub fn get_integer(&self, name: &str) -> i32 {
+        match self.get(name) {
+            ParameterValue::Integer { value, .. } => *value,
+            _ => panic!("{} isn't an integer", name),
+        }
+    }
+
+    pub fn get_float(&self, name: &str) -> f32 {
+        match self.get(name) {
+            ParameterValue::Float { value, .. } => *value,
+            _ => panic!("{} isn't a float", name),
+        }
+    }
+
+    /// The index of the selected option.
+    pub fn get_choice(&self, name: &str) -> usize {
+        match self.get(name) {
+            ParameterValue::Choice { selected, .. } => *selected,
+            _ => panic!("{} isn't a choice", name),
+        }
+    }
+
+    pub fn get_text(&self, name: &str) -> &str {
+        match self.get(name) {
+            ParameterValue::Text(text) => text,
+            _ => panic!("{} isn't text", name),
+        }
+    }
+}
+
+/// Why running an operator did nothing, in words for the user.
+pub type OperatorError = String;
+
+#[derive(Clone, Copy)]
+pub struct Operator {
+    /// E.g. "mesh.delete". Unique within the registry.
+    pub id: &'static str,
+    /// E.g. "Delete". Shown in menus and the command palette.
+    pub name: &'static str,
+    pub description: &'static str,
+    pub category: OperatorCategory,
+    parameters: fn() -> Vec<Parameter>,
+    poll: fn(&ApplicationState) -> bool,
+    execute: fn(&mut ApplicationState, &OperatorParameters) -> Result<(), OperatorError>,
+}
+
+impl Operator {
+    /// The parameters the operator runs with unless told otherwise.
+    pub fn get_default_parameters(&self) -> OperatorParameters {
+        OperatorParameters((self.parameters)())
+    }
+
+    /// Whether the operator can run right now, e.g. mesh operators need an object in edit
+    /// mode.
+    pub fn poll(&self, state: &ApplicationState) -> bool {
+        (self.poll)(state)
+    }
+
+    /// Runs the operator, which must have passed `poll()`. Use `ApplicationState::
+    /// run_operator()` instead, which also checks that and refreshes what gets drawn.
+    pub fn execute(
+        &self,
+        state: &mut ApplicationState,
+        parameters: &OperatorParameters,
+    ) -> Result<(), OperatorError> {
+        (self.execute)(state, parameters)
+    }
+}
+
+pub struct OperatorRegistry {
+    operators: Vec<Operator>,
+}
+
+impl OperatorRegistry {
+    /// The registry with all the built-in operators.
+    pub fn new() -> Self {
+        let mut registry = Self {
+            operators: Vec::new(),
+        };
+        register_add_operators(&mut registry);
+        register_object_operators(&mut registry);
+        register_mesh_operators(&mut registry);
+        register_view_operators(&mut registry);
+        register_file_operators(&mut registry);
+        registry
+    }
+
+    pub fn register(&mut self, operator: Operator) {
+        assert!(
+            self.get(operator.id).is_none(),
+            "Operator {} is registered twice",
+            operator.id
+        );
+        self.operators.push(operator);
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Operator> {
+        self.operators.iter().find(|operator| operator.id == id)
+    }
+
+    pub fn get_all(&self) -> &[Operator] {
+        &self.operators
+    }
+
+    /// The operators whose name or id fuzzily match the query, best matches first. An empty
+    /// query matches all of them, in the order they were registered.
+    pub fn search(&self, query: &str) -> Vec<&Operator> {
+        let mut matches: Vec<(i32, &Operator)> = self
+            .operators
+            .iter()
+            .filter_map(|operator| {
+                let name_score = get_fuzzy_score(query, operator.name);
+                let id_score = get_fuzzy_score(query, operator.id);
+                Some((name_score.max(id_score)?, operator))
+            })
+            .collect();
+        // Stable, so that equally good matches stay in the order they were registered.
+        matches.sort_by_key(|(score, _)| -score);
+        matches.into_iter().map(|(_, operator)| operator).collect()
+    }
+}
+
+/// How well the query matches the text, if all its characters appear in the text in order,
+/// ignoring case. Characters that follow each other in the text, or start a word in it, score
+/// more, so that e.g. "dv" ranks "Dissolve vertices" above "Dissolve edges".
+fn get_fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let mut query_characters = query.chars().flat_map(char::to_lowercase).peekable();
+    let mut score = 0;
+    let mut previous_matched = false;
+    let mut previous_character = None;
+    for character in text.chars().flat_map(char::to_lowercase) {
+        let Some(&wanted) = query_characters.peek() else {
+            break;
+        };
+        let starts_word = previous_character.is_none_or(|c: char| !c.is_alphanumeric());
+        if character == wanted {
+            query_characters.next();
+            score += 1;
+            if previous_matched {
+                score += 4;
+            }
+            if starts_word {
+                score += 3;
+            }
+            previous_matched = true;
+        } else {
+            previous_matched = false;
+        }
+        previous_character = Some(character);
+    }
+
+    query_characters.peek().is_none().then_some(score)
+}
+
+fn no_parameters() -> Vec<Parameter> {
+    Vec::new()
+}
+
+fn always(_state: &ApplicationState) -> bool {
+    true
+}
+
+fn has_active_object(state: &ApplicationState) -> bool {
+    state.scene.get_active_object().is_some()
+}
+
+fn is_editing_mesh(state: &ApplicationState) -> bool {
+    state.scene.edit_mode && has_active_object(state)
+}
+
+/// The mesh of the object in edit mode, which `is_editing_mesh()` checked there is.
+fn get_edited_mesh(state: &mut ApplicationState) -> &mut BMesh {
+    &mut state.scene.get_active_object_mut().unwrap().mesh
+}
+
+fn register_add_operators(registry: &mut OperatorRegistry) {
+    registry.register(Operator {
+        id: "object.add_cube",
+        name: "Add cube",
+        description: "Add a cube next to the last object",
+        category: OperatorCategory::Add,
+        parameters: no_parameters,
+        poll: always,
+        execute: |state, _| {
+            state.scene.add_cube(&mut rand::thread_rng());
+            Ok(())
+        },
+    });
+    registry.register(Operator {
+        id: "object.add_camera",
+        name: "Add camera from view",
+        description: "Add a scene camera where the camera of the active viewport is",
+        category: OperatorCategory::Add,
+        parameters: no_parameters,
+        poll: always,
+        execute: |state, _| {
+            let mut scene_camera = SceneCamera::new("Camera");
+            state.viewports[state.active_viewport]
+                .camera
+                .align_scene_camera(&mut scene_camera);
+            state.scene.add_camera(scene_camera);
+            Ok(())
+        },
+    });
+}
+
+/// The operations of `object.boolean`, in the order of its "Operation" choice.
+const BOOLEAN_OPERATIONS: [BooleanOperation; 3] = [
+    BooleanOperation::Union,
+    BooleanOperation::Difference,
+    BooleanOperation::Intersect,
+];
+
+/// The parameters to run `object.boolean` with, combining the active object with the one
+/// called `other_name`.
+pub fn get_boolean_parameters(operation: BooleanOperation, other_name: &str) -> OperatorParameters {
+    OperatorParameters(vec![
+        Parameter::new(
+            "Operation",
+            ParameterValue::Choice {
+                options: &["Union", "Difference", "Intersect"],
+                selected: BOOLEAN_OPERATIONS
+                    .iter()
+                    .position(|o| *o == operation)
+                    .unwrap(),
+            },
+        ),
+        Parameter::new("With", ParameterValue::Text(other_name.to_string())),
+    ])
+}
+
+fn register_object_operators(registry: &mut OperatorRegistry) {
+    registry.register(Operator {
+        id: "object.boolean",
+        name: "Boolean",
+        description: "Combine the mesh of the active object with that of another object, which \
+                      is hidden",
+        category: OperatorCategory::Object,
+        parameters: || get_boolean_parameters(BooleanOperation::Difference, "").0,
+        poll: |state| has_active_object(state) && state.scene.objects.len() > 1,
+        execute: |state, parameters| {
+            let operation = BOOLEAN_OPERATIONS[parameters.get_choice("Operation")];
+            let other_name = parameters.get_text("With");
+            let active_object = state.scene.active_object.unwrap();
+            let other_object = state
+                .scene
+                .objects
+                .iter()
+                .position(|o| o.name == other_name)
+                .filter(|i| *i != active_object)
+                .ok_or_else(|| format!("There is no other object called \"{}\"", other_name))?;
+            state.scene.apply_boolean(
+                active_object,
+                other_object,
+                operation,
+                &mut rand::thread_rng(),
+            );
+            Ok(())
+        },
+    });
+    registry.register(Operator {
+        id: "object.apply_modifiers",
+        name: "Apply all modifiers",
+        description: "Bake the modifiers of the active object into its mesh",
+        category: OperatorCategory::Object,
+        parameters: no_parameters,
+        poll: |state| {
+            state
+                .scene
+                .get_active_object()
+                .is_some_and(|object| !object.modifiers.get_entries().is_empty())
+        },
+        execute: |state, _| {
+            let index = state.scene.active_object.unwrap();
+            state
+                .scene
+                .apply_all_modifiers(index, &mut rand::thread_rng());
+            Ok(())
+        },
+    });
+    registry.register(Operator {
+        id: "object.duplicate",
+        name: "Duplicate object",
+        description: "Add a copy of the mesh of the active object, without its modifiers",
+        category: OperatorCategory::Object,
+        parameters: no_parameters,
+        poll: has_active_object,
+        execute: |state, _| {
+            let object = state.scene.get_active_object().unwrap();
+            let (points, polygons) = object.mesh.to_polygons();
+            let mesh = BMesh::from_polygons(&mut rand::thread_rng(), &points, &polygons);
+            let mut duplicate = SceneObject::new(&object.name, mesh);
+            duplicate.color = object.color;
+            state.scene.add_object(duplicate);
+            Ok(())
+        },
+    });
+    registry.register(Operator {
+        id: "object.toggle_edit_mode",
+        name: "Toggle edit mode",
+        description: "Switch between editing the mesh of the active object and the objects as a \
+                      whole",
+        category: OperatorCategory::Object,
+        parameters: no_parameters,
+        poll: has_active_object,
+        execute: |state, _| {
+            state.scene.edit_mode = !state.scene.edit_mode;
+            Ok(())
+        },
+    });
+}
+
+fn register_mesh_operators(registry: &mut OperatorRegistry) {
+    const REMOVE_TWO_VALENCE_VERTICES: &str = "Remove two-valence vertices";
+    fn dissolve_parameters() -> Vec<Parameter> {
+        vec![Parameter::new(
+            REMOVE_TWO_VALENCE_VERTICES,
+            ParameterValue::Bool(true),
+        )]
+    }
+
+    registry.register(Operator {
+        id: "mesh.select_all",
+        name: "Select all",
+        description: "Select every vertex of the mesh",
+        category: OperatorCategory::Mesh,
+        parameters: no_parameters,
+        poll: is_editing_mesh,
+        execute: |state, _| {
+            get_edited_mesh(state).select_all();
+            Ok(())
+        },
+    });
+    registry.register(Operator {
+        id: "mesh.deselect_all",
+        name: "Deselect all",
+        description: "Clear the selection",
+        category: OperatorCategory::Mesh,
+        parameters: no_parameters,
+        poll: is_editing_mesh,
+        execute: |state, _| {
+            get_edited_mesh(state).deselect_all();
+            Ok(())
+        },
+    });
+    registry.register(Operator {
+        id: "mesh.delete",
+        name: "Delete",
+        description: "Delete the selected vertices, edges or faces",
+        category: OperatorCategory::Mesh,
+        parameters: || {
+            vec![Parameter::new(
+                "Delete",
+                ParameterValue::Choice {
+                    options: &[
+                        "Vertices",
+                        "Edges",
+                        "Faces",
+                        "Only faces",
+                        "Only edges and faces",
+                    ],
+                    selected: 0,
+                },
+            )]
+        },
+        poll: is_editing_mesh,
+        execute: |state, parameters| {
+            let mode = match parameters.get_choice("Delete") {
+                0 => DeleteMode::Vertices,
+                1 => DeleteMode::Edges,
+                2 => DeleteMode::Faces,
+                3 => DeleteMode::OnlyFaces,
+                _ => DeleteMode::OnlyEdgesAndFaces,
+            };
+            get_edited_mesh(state).delete_selected(mode);
+            Ok(())
+        },
+    });
+    registry.register(Operator {
+        id: "mesh.dissolve_vertices",
+        name: "Dissolve vertices",
+        description: "Remove the selected vertices, merging the faces around each of them",
+        category: OperatorCategory::Mesh,
+        parameters: dissolve_parameters,
+        poll: is_editing_mesh,
+        execute: |state, parameters| {
+            let remove = parameters.get_bool(REMOVE_TWO_VALENCE_VERTICES);
+            get_edited_mesh(state).dissolve_selected_vertices(remove);
+            Ok(())
+        },
+    });
+    registry.register(Operator {
+        id: "mesh.dissolve_edges",
+        name: "Dissolve edges",
+        description: "Remove the selected edges, merging the faces on either side",
+        category: OperatorCategory::Mesh,
+        parameters: dissolve_parameters,
+        poll: is_editing_mesh,
+        execute: |state, parameters| {
+            let remove = parameters.get_bool(REMOVE_TWO_VALENCE_VERTICES);
+            get_edited_mesh(state).dissolve_selected_edges(remove);
+            Ok(())
+        },
+    });
+    registry.register(Operator {
+        id: "mesh.dissolve_faces",
+        name: "Dissolve faces",
+        description: "Merge each connected group of selected faces into one face",
+        category: OperatorCategory::Mesh,
+        parameters: dissolve_parameters,
+        poll: is_editing_mesh,
+        execute: |state, parameters| {
+            let remove = parameters.get_bool(REMOVE_TWO_VALENCE_VERTICES);
+            get_edited_mesh(state).dissolve_selected_faces(remove);
+            Ok(())
+        },
+    });
+    registry.register(Operator {
+        id: "mesh.fill_holes",
+        name: "Fill holes",
+        description: "Close the holes whose outlines are selected",
+        category: OperatorCategory::Mesh,
+        parameters: || {
+            vec![Parameter::new(
+                "Fill with",
+                ParameterValue::Choice {
+                    options: &["N-gon", "Triangle fan", "Grid"],
+                    selected: 0,
+                },
+            )]
+        },
+        poll: is_editing_mesh,
+        execute: |state, parameters| {
+            let mode = match parameters.get_choice("Fill with") {
+                0 => FillMode::Ngon,
+                1 => FillMode::TriangleFan,
+                _ => FillMode::Grid,
+            };
+            match get_edited_mesh(state).fill_selected_holes(mode, &mut rand::thread_rng()) {
+                0 => Err("No hole is selected".to_string()),
+                _ => Ok(()),
+            }
+        },
+    });
+    registry.register(Operator {
+        id: "mesh.merge",
+        name: "Merge vertices",
+        description: "Merge the selected vertices into one",
+        category: OperatorCategory::Mesh,
+        parameters: || {
+            vec![Parameter::new(
+                "At",
+                ParameterValue::Choice {
+                    options: &["Center", "First", "Last", "Cursor"],
+                    selected: 0,
+                },
+            )]
+        },
+        poll: is_editing_mesh,
+        execute: |state, parameters| {
+            let target = match parameters.get_choice("At") {
+                0 => MergeTarget::Center,
+                1 => MergeTarget::First,
+                2 => MergeTarget::Last,
+                _ => MergeTarget::Cursor(state.scene.cursor),
+            };
+            get_edited_mesh(state).merge_selected_vertices(target);
+            Ok(())
+        },
+    });
+    registry.register(Operator {
+        id: "mesh.merge_by_distance",
+        name: "Merge by distance",
+        description: "Merge vertices that are closer together than the threshold",
+        category: OperatorCategory::Mesh,
+        parameters: || {
+            vec![Parameter::new(
+                "Threshold",
+                ParameterValue::Float {
+                    value: 0.0001,
+                    min: 0.0,
+                    max: 10.0,
+                },
+            )]
+        },
+        poll: is_editing_mesh,
+        execute: |state, parameters| {
+            let threshold = parameters.get_float("Threshold");
+            get_edited_mesh(state).merge_vertices_by_distance(threshold);
+            Ok(())
+        },
+    });
+    registry.register(Operator {
+        id: "mesh.bridge_edge_loops",
+        name: "Bridge edge loops",
+        description: "Connect the two selected edge loops with faces",
+        category: OperatorCategory::Mesh,
+        parameters: || {
+            vec![
+                Parameter::new(
+                    "Twist",
+                    ParameterValue::Integer {
+                        value: 0,
+                        min: -64,
+                        max: 64,
+                    },
+                ),
+                Parameter::new(
+                    "Segments",
+                    ParameterValue::Integer {
+                        value: 1,
+                        min: 1,
+                        max: 64,
+                    },
+                ),
+            ]
+        },
+        poll: is_editing_mesh,
+        execute: |state, parameters| {
+            let twist = parameters.get_integer("Twist");
+            let segments = parameters.get_integer("Segments") as usize;
+            get_edited_mesh(state)
+                .bridge_selected_edge_loops(twist, segments, &mut rand::thread_rng())
+                .map_err(|error| format!("Couldn't bridge: {:?}", error))
+        },
+    });
+    registry.register(Operator {
+        id: "mesh.mark_sharp",
+        name: "Mark sharp",
+        description: "Split the normals along the selected edges",
+        category: OperatorCategory::Mesh,
+        parameters: no_parameters,
+        poll: is_editing_mesh,
+        execute: |state, _| {
+            get_edited_mesh(state).set_selected_edges_sharp(true);
+            Ok(())
+        },
+    });
+    registry.register(Operator {
+        id: "mesh.clear_sharp",
+        name: "Clear sharp",
+        description: "Stop splitting the normals along the selected edges",
+        category: OperatorCategory::Mesh,
+        parameters: no_parameters,
+        poll: is_editing_mesh,
+        execute: |state, _| {
+            get_edited_mesh(state).set_selected_edges_sharp(false);
+            Ok(())
+        },
+    });
+    registry.register(Operator {
+        id: "mesh.knife",
+        name: "Knife",
+        description: "Cut the mesh along a path clicked in the active viewport",
+        category: OperatorCategory::Mesh,
+        parameters: no_parameters,
+        poll: |state| state.knife_tool.is_none() && has_active_object(state),
+        execute: |state, _| {
+            state.knife_tool = Some(KnifeTool::new());
+            Ok(())
+        },
+    });
+}
+
+fn register_view_operators(registry: &mut OperatorRegistry) {
+    fn set_view_preset(state: &mut ApplicationState, preset: ViewPreset) {
+        state.viewports[state.active_viewport]
+            .camera
+            .set_view_preset(preset);
+    }
+
+    registry.register(Operator {
+        id: "view.perspective",
+        name: ViewPreset::Perspective.get_name(),
+        description: "Reset the camera of the active viewport to the view the app starts with",
+        category: OperatorCategory::View,
+        parameters: no_parameters,
+        poll: always,
+        execute: |state, _| {
+            set_view_preset(state, ViewPreset::Perspective);
+            Ok(())
+        },
+    });
+    registry.register(Operator {
+        id: "view.top",
+        name: ViewPreset::Top.get_name(),
+        description: "Look down the Y axis, orthographically",
+        category: OperatorCategory::View,
+        parameters: no_parameters,
+        poll: always,
+        execute: |state, _| {
+            set_view_preset(state, ViewPreset::Top);
+            Ok(())
+        },
+    });
+    registry.register(Operator {
+        id: "view.front",
+        name: ViewPreset::Front.get_name(),
+        description: "Look down the Z axis, orthographically",
+        category: OperatorCategory::View,
+        parameters: no_parameters,
+        poll: always,
+        execute: |state, _| {
+            set_view_preset(state, ViewPreset::Front);
+            Ok(())
+        },
+    });
+    registry.register(Operator {
+        id: "view.right",
+        name: ViewPreset::Right.get_name(),
+        description: "Look down the X axis, orthographically",
+        category: OperatorCategory::View,
+        parameters: no_parameters,
+        poll: always,
+        execute: |state, _| {
+            set_view_preset(state, ViewPreset::Right);
+            Ok(())
+        },
+    });
+    registry.register(Operator {
+        id: "view.cursor_to_selection",
+        name: "Cursor to selection",
+        description: "Move the 3D cursor to the center of the selected vertices, or of the \
+                      active object outside of edit mode",
+        category: OperatorCategory::View,
+        parameters: no_parameters,
+        poll: |state| state.scene.get_selection_center().is_some(),
+        execute: |state, _| {
+            state.scene.cursor = state.scene.get_selection_center().unwrap();
+            Ok(())
+        },
+    });
+}
+
+fn register_file_operators(registry: &mut OperatorRegistry) {
+    registry.register(Operator {
+        id: "file.render",
+        name: "Render to PNG",
+        description: "Draw what the active viewport shows into an image file",
+        category: OperatorCategory::File,
+        parameters: || {
+            vec![
+                Parameter::new("Path", ParameterValue::Text("render.png".to_string())),
+                Parameter::new(
+                    "Width",
+                    ParameterValue::Integer {
+                        value: 1280,
+                        min: 1,
+                        max: 8192,
+                    },
+                ),
+                Parameter::new(
+                    "Height",
+                    ParameterValue::Integer {
+                        value: 720,
+                        min: 1,
+                        max: 8192,
+                    },
+                ),
+            ]
+        },
+        poll: always,
+        execute: |state, parameters| {
+            let size = [
+                parameters.get_integer("Width") as u32,
+                parameters.get_integer("Height") as u32,
+            ];
+            let path = std::path::Path::new(parameters.get_text("Path"));
+            let viewport = &mut state.viewports[state.active_viewport];
+            let result = HeadlessRenderer::new().and_then(|mut renderer| {
+                renderer.render_to_png(&mut state.scene, &mut viewport.camera, size, path)
+            });
+            // Rendering changed the aspect ratio of the camera to that of the image.
+            viewport.drawing_stuff.drawing_region_size_updated = true;
+            result.map_err(|error| format!("Couldn't render: {}", error))
+        },
+    });
+    registry.register(Operator {
+        id: "file.save_grid",
+        name: "Save grid settings",
+        description: "Write the grid settings to grid.toml in the config directory, to start \
+                      with them next time",
+        category: OperatorCategory::File,
+        parameters: no_parameters,
+        poll: |_| GridSettings::get_path().is_some(),
+        execute: |state, _| {
+            let path = GridSettings::get_path().unwrap();
+            state
+                .scene
+                .grid
+                .save(&path)
+                .map_err(|error| format!("Couldn't save: {}", error))
+        },
+    });
+    registry.register(Operator {
+        id: "file.load_grid",
+        name: "Load grid settings",
+        description: "Read the grid settings from grid.toml in the config directory",
+        category: OperatorCategory::File,
+        parameters: no_parameters,
+        poll: |_| GridSettings::get_path().is_some_and(|path| path.exists()),
+        execute: |state, _| {
+            let path = GridSettings::get_path().unwrap();
+            state.scene.grid =
+                GridSettings::load(&path).map_err(|error| format!("Couldn't load: {}", error))?;
+            Ok(())
+        },
+    });
+    registry.register(Operator {
+        id: "file.save_keymap",
+        name: "Save keymap",
+        description: "Write the shortcuts to keymap.toml in the config directory",
+        category: OperatorCategory::File,
+        parameters: no_parameters,
+        poll: |_| Keymap::get_path().is_some(),
+        execute: |state, _| {
+            let path = Keymap::get_path().unwrap();
+            state
+                .keymap
+                .save(&path)
+                .map_err(|error| format!("Couldn't save: {}", error))
+        },
+    });
+    registry.register(Operator {
+        id: "file.load_keymap",
+        name: "Load keymap",
+        description: "Read the shortcuts from keymap.toml in the config directory",
+        category: OperatorCategory::File,
+        parameters: no_parameters,
+        poll: |_| Keymap::get_path().is_some_and(|path| path.exists()),
+        execute: |state, _| {
+            let path = Keymap::get_path().unwrap();
+            state.keymap = Keymap::load(&path, &state.operators)
+                .map_err(|error| format!("Couldn't load: {}", error))?;
+            Ok(())
+        },
+    });
+    registry.register(Operator {
+        id: "file.reset_keymap",
+        name: "Reset keymap",
+        description: "Go back to the shortcuts of the Blender or Maya preset",
+        category: OperatorCategory::File,
+        parameters: || {
+            vec![Parameter::new(
+                "Preset",
+                ParameterValue::Choice {
+                    options: &["Blender", "Maya"],
+                    selected: 0,
+                },
+            )]
+        },
+        poll: always,
+        execute: |state, parameters| {
+            let preset = match parameters.get_choice("Preset") {
+                0 => KeymapPreset::Blender,
+                _ => KeymapPreset::Maya,
+            };
+            state.keymap = Keymap::from_preset(preset);
+            Ok(())
+        },
+    });
+}
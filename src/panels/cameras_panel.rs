@@ -0,0 +1,129 @@
+use eframe::egui;
+
+use crate::{
+    camera::Camera,
+    math::point::Point3,
+    scene::{Scene, SceneCamera},
+};
+
+/// Lists the cameras in the scene, with their placement and lens, and lets the viewport camera
+/// look through them or be copied into them. Returns whether anything changed that affects what
+/// gets drawn.
+pub fn show(ui: &mut egui::Ui, scene: &mut Scene, camera: &mut Camera) -> bool {
+    let mut changed = false;
+
+    ui.heading("Cameras");
+
+    let mut camera_to_delete = None;
+    for (i, scene_camera) in scene.cameras.iter_mut().enumerate() {
+        egui::CollapsingHeader::new(&scene_camera.name)
+            .id_source(("scene_camera", i))
+            .show(ui, |ui| {
+                changed |= camera_settings(ui, scene_camera);
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("Look through")
+                        .on_hover_text("Move the viewport camera to this camera")
+                        .clicked()
+                    {
+                        camera.look_through(scene_camera);
+                    }
+                    if ui
+                        .button("Align to view")
+                        .on_hover_text("Move this camera to the viewport camera")
+                        .clicked()
+                    {
+                        camera.align_scene_camera(scene_camera);
+                        changed = true;
+                    }
+                    if ui.button("Delete").clicked() {
+                        camera_to_delete = Some(i);
+                    }
+                });
+            });
+    }
+    if let Some(i) = camera_to_delete {
+        scene.cameras.remove(i);
+        changed = true;
+    }
+
+    if ui.button("Add camera from view").clicked() {
+        let mut scene_camera = SceneCamera::new("Camera");
+        camera.align_scene_camera(&mut scene_camera);
+        scene.add_camera(scene_camera);
+        changed = true;
+    }
+
+    changed
+}
+
+fn camera_settings(ui: &mut egui::Ui, scene_camera: &mut SceneCamera) -> bool {
+    let mut changed = false;
+
+    ui.horizontal(|ui| {
+        ui.label("Position");
+        let position = &scene_camera.position;
+        let mut coordinates = [position.x(), position.y(), position.z()];
+        let mut position_changed = false;
+        for coordinate in &mut coordinates {
+            position_changed |= ui
+                .add(egui::DragValue::new(coordinate).speed(0.01))
+                .changed();
+        }
+        if position_changed {
+            scene_camera.position = Point3::new(coordinates[0], coordinates[1], coordinates[2]);
+            changed = true;
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label("Focal length");
+        changed |= ui
+            .add(
+                egui::DragValue::new(&mut scene_camera.focal_length)
+                    .speed(0.5)
+                    .clamp_range(1.0..=500.0)
+                    .suffix(" mm"),
+            )
+            .changed();
+        ui.label(format!(
+            "({:.1}° vertical)",
+            scene_camera.get_vertical_fov()
+        ));
+    });
+    ui.horizontal(|ui| {
+        ui.label("Sensor");
+        changed |= ui
+            .add(
+                egui::DragValue::new(&mut scene_camera.sensor_width)
+                    .speed(0.1)
+                    .clamp_range(1.0..=100.0)
+                    .suffix(" mm"),
+            )
+            .changed();
+        ui.label("×");
+        changed |= ui
+            .add(
+                egui::DragValue::new(&mut scene_camera.sensor_height)
+                    .speed(0.1)
+                    .clamp_range(1.0..=100.0)
+                    .suffix(" mm"),
+            )
+            .changed();
+    });
+    ui.horizontal(|ui| {
+        ui.label("Clip");
+        ui.add(
+            egui::DragValue::new(&mut scene_camera.clip_start)
+                .speed(0.01)
+                .clamp_range(0.001..=scene_camera.clip_end),
+        );
+        ui.label("to");
+        ui.add(
+            egui::DragValue::new(&mut scene_camera.clip_end)
+                .speed(1.0)
+                .clamp_range(scene_camera.clip_start..=10000.0),
+        );
+    });
+
+    changed
+}
@@ -0,0 +1,208 @@
+use eframe::egui;
+
+use crate::operators::{
+    OperatorCategory, OperatorError, OperatorParameters, OperatorRegistry, Parameter,
+    ParameterValue,
+};
+
+/// Settings of the palette that are kept between frames.
+pub struct CommandPaletteState {
+    pub open: bool,
+    query: String,
+    /// Which of the operators matching the query Enter runs.
+    highlighted: usize,
+    /// The parameters of the highlighted operator, as changed in the palette.
+    parameters: Option<(&'static str, OperatorParameters)>,
+    /// Set when the palette opens, to start typing in the search field right away.
+    focus_query: bool,
+    /// Why the last operator run from the palette didn't work.
+    error: Option<OperatorError>,
+}
+
+impl CommandPaletteState {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            query: String::new(),
+            highlighted: 0,
+            parameters: None,
+            focus_query: false,
+            error: None,
+        }
+    }
+
+    /// Opens the palette with an empty search.
+    pub fn open(&mut self) {
+        *self = Self {
+            open: true,
+            focus_query: true,
+            ..Self::new()
+        };
+    }
+
+    /// Closes the palette if the operator it ran worked, and otherwise says why it didn't.
+    pub fn report(&mut self, result: Result<(), OperatorError>) {
+        match result {
+            Ok(()) => self.open = false,
+            Err(error) => self.error = Some(error),
+        }
+    }
+}
+
+/// Searches the operators by name, with the parameters of the highlighted one below the
+/// results. Only the `available` ones (see `Operator::poll()`) can be run. Returns the operator
+/// to run and its parameters, when Enter or "Run" is pressed.
+pub fn show(
+    ui: &mut egui::Ui,
+    state: &mut CommandPaletteState,
+    registry: &OperatorRegistry,
+    available: &[&'static str],
+) -> Option<(&'static str, OperatorParameters)> {
+    let response = ui.add(
+        egui::TextEdit::singleline(&mut state.query)
+            .hint_text("Search operators")
+            .desired_width(f32::INFINITY),
+    );
+    if state.focus_query {
+        response.request_focus();
+        state.focus_query = false;
+    }
+    if response.changed() {
+        state.highlighted = 0;
+        state.error = None;
+    }
+
+    let matches = registry.search(&state.query);
+    let (up, down, enter, escape) = ui.input(|i| {
+        (
+            i.key_pressed(egui::Key::ArrowUp),
+            i.key_pressed(egui::Key::ArrowDown),
+            i.key_pressed(egui::Key::Enter),
+            i.key_pressed(egui::Key::Escape),
+        )
+    });
+    if escape {
+        state.open = false;
+        return None;
+    }
+    if down {
+        state.highlighted += 1;
+    }
+    if up {
+        state.highlighted = state.highlighted.saturating_sub(1);
+    }
+    state.highlighted = state.highlighted.min(matches.len().saturating_sub(1));
+
+    egui::ScrollArea::vertical()
+        .max_height(300.0)
+        .show(ui, |ui| {
+            for (i, operator) in matches.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    let enabled = available.contains(&operator.id);
+                    let label = ui
+                        .add_enabled(
+                            enabled,
+                            egui::SelectableLabel::new(i == state.highlighted, operator.name),
+                        )
+                        .on_hover_text(operator.description);
+                    if label.clicked() {
+                        state.highlighted = i;
+                    }
+                    if i == state.highlighted && (up || down) {
+                        label.scroll_to_me(None);
+                    }
+                    ui.weak(operator.category.get_name());
+                });
+            }
+        });
+
+    let Some(operator) = matches.get(state.highlighted) else {
+        ui.label("No operator matches");
+        return None;
+    };
+
+    if state.parameters.as_ref().map(|(id, _)| *id) != Some(operator.id) {
+        state.parameters = Some((operator.id, operator.get_default_parameters()));
+    }
+    let (_, parameters) = state.parameters.as_mut().unwrap();
+
+    ui.separator();
+    ui.label(operator.description);
+    if !parameters.0.is_empty() {
+        egui::Grid::new("command_palette_parameters").show(ui, |ui| {
+            for parameter in &mut parameters.0 {
+                ui.label(parameter.name);
+                parameter_widget(ui, parameter);
+                ui.end_row();
+            }
+        });
+    }
+
+    let enabled = available.contains(&operator.id);
+    let run = ui.add_enabled(enabled, egui::Button::new("Run")).clicked();
+    if let Some(error) = &state.error {
+        ui.colored_label(ui.visuals().error_fg_color, error);
+    }
+
+    ((run || enter) && enabled).then(|| (operator.id, parameters.clone()))
+}
+
+fn parameter_widget(ui: &mut egui::Ui, parameter: &mut Parameter) {
+    match &mut parameter.value {
+        ParameterValue::Bool(value) => {
+            ui.checkbox(value, "");
+        }
+        ParameterValue::Integer { value, min, max } => {
+            ui.add(egui::DragValue::new(value).clamp_range(*min..=*max));
+        }
+        ParameterValue::Float { value, min, max } => {
+            ui.add(
+                egui::DragValue::new(value)
+                    .clamp_range(*min..=*max)
+                    .speed(0.001),
+            );
+        }
+        ParameterValue::Choice { options, selected } => {
+            egui::ComboBox::from_id_source(("command_palette_parameter", parameter.name))
+                .selected_text(options[*selected])
+                .show_ui(ui, |ui| {
+                    for (i, option) in options.iter().enumerate() {
+                        ui.selectable_value(selected, i, *option);
+                    }
+                });
+        }
+        ParameterValue::Text(text) => {
+            ui.text_edit_singleline(text);
+        }
+    }
+}
+
+/// A menu with the operators of the category, run with their default parameters. Returns the
+/// one that was clicked.
+pub fn operator_menu(
+    ui: &mut egui::Ui,
+    registry: &OperatorRegistry,
+    category: OperatorCategory,
+    available: &[&'static str],
+) -> Option<&'static str> {
+    let mut clicked = None;
+    ui.menu_button(category.get_name(), |ui| {
+        for operator in registry.get_all() {
+            if operator.category != category {
+                continue;
+            }
+            if ui
+                .add_enabled(
+                    available.contains(&operator.id),
+                    egui::Button::new(operator.name),
+                )
+                .on_hover_text(operator.description)
+                .clicked()
+            {
+                clicked = Some(operator.id);
+                ui.close_menu();
+            }
+        }
+    });
+    clicked
+}
@@ -0,0 +1,163 @@
+use eframe::egui;
+
+use crate::{
+    input_state::InputState,
+    keymap::{Action, Chord, Keymap, KeymapPreset},
+    operators::OperatorRegistry,
+};
+
+/// Settings of the panel that are kept between frames.
+pub struct KeymapPanelState {
+    pub open: bool,
+    /// The action the next chord pressed gets bound to.
+    recording: Option<Action>,
+    /// What happened when saving or loading the keymap.
+    status: Option<String>,
+}
+
+impl KeymapPanelState {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            recording: None,
+            status: None,
+        }
+    }
+
+    /// Whether a chord is being recorded, in which case shortcuts shouldn't do anything.
+    pub fn is_recording(&self) -> bool {
+        self.open && self.recording.is_some()
+    }
+}
+
+/// Lists the shortcuts of every action, to add and remove them, and any conflicts between
+/// them. Operators from the registry can be bound too. The keymap can be reset to a preset,
+/// saved and loaded.
+pub fn show(
+    ui: &mut egui::Ui,
+    state: &mut KeymapPanelState,
+    keymap: &mut Keymap,
+    operators: &OperatorRegistry,
+    input_state: &InputState,
+) {
+    if let Some(action) = state.recording {
+        if let Some(chord) = Chord::from_input(input_state) {
+            let chords = keymap.get_chords_mut(action);
+            if chord != Chord::key(egui::Key::Escape) && !chords.contains(&chord) {
+                chords.push(chord);
+            }
+            state.recording = None;
+        }
+    }
+
+    ui.horizontal(|ui| {
+        ui.label("Preset");
+        egui::ComboBox::from_id_source("keymap_preset")
+            .selected_text(keymap.preset.get_name())
+            .show_ui(ui, |ui| {
+                for preset in [KeymapPreset::Blender, KeymapPreset::Maya] {
+                    if ui
+                        .selectable_label(keymap.preset == preset, preset.get_name())
+                        .clicked()
+                    {
+                        *keymap = Keymap::from_preset(preset);
+                    }
+                }
+            });
+    });
+    ui.separator();
+
+    let get_name = |action: Action| match action {
+        Action::Operator(id) => operators.get(id).map_or(id, |operator| operator.name),
+        _ => action.get_name(),
+    };
+    let bound_operators = keymap.get_bound_operators();
+    let actions = Action::ALL
+        .into_iter()
+        .chain(bound_operators.iter().map(|id| Action::Operator(id)));
+    egui::Grid::new("keymap_bindings")
+        .striped(true)
+        .show(ui, |ui| {
+            for action in actions {
+                ui.label(get_name(action));
+                ui.horizontal(|ui| {
+                    let mut chord_to_remove = None;
+                    for (i, chord) in keymap.get_chords(action).iter().enumerate() {
+                        if ui
+                            .button(chord.to_string())
+                            .on_hover_text("Remove")
+                            .clicked()
+                        {
+                            chord_to_remove = Some(i);
+                        }
+                    }
+                    if let Some(i) = chord_to_remove {
+                        keymap.get_chords_mut(action).remove(i);
+                    }
+
+                    if state.recording == Some(action) {
+                        ui.label("Press a shortcut (Esc to stop)");
+                    } else if ui.button("+").on_hover_text("Add a shortcut").clicked() {
+                        state.recording = Some(action);
+                    }
+                });
+                ui.end_row();
+            }
+        });
+
+    egui::ComboBox::from_id_source("keymap_bind_operator")
+        .selected_text("Bind an operator")
+        .show_ui(ui, |ui| {
+            for operator in operators.get_all() {
+                if bound_operators.contains(&operator.id) {
+                    continue;
+                }
+                if ui
+                    .selectable_label(false, operator.name)
+                    .on_hover_text(operator.description)
+                    .clicked()
+                {
+                    keymap.get_chords_mut(Action::Operator(operator.id));
+                    state.recording = Some(Action::Operator(operator.id));
+                }
+            }
+        });
+
+    for conflict in keymap.get_conflicts() {
+        ui.colored_label(
+            ui.visuals().error_fg_color,
+            format!(
+                "{} does both \"{}\" and \"{}\"",
+                conflict.chord,
+                get_name(conflict.actions[0]),
+                get_name(conflict.actions[1])
+            ),
+        );
+    }
+    ui.separator();
+
+    let Some(path) = Keymap::get_path() else {
+        ui.label("There is no config directory to save the keymap to");
+        return;
+    };
+    ui.horizontal(|ui| {
+        if ui.button("Save").clicked() {
+            state.status = Some(match keymap.save(&path) {
+                Ok(()) => format!("Saved to {}", path.display()),
+                Err(error) => format!("Couldn't save: {}", error),
+            });
+        }
+        if ui.button("Reload").clicked() {
+            state.status = Some(match Keymap::load(&path, operators) {
+                Ok(loaded) => {
+                    *keymap = loaded;
+                    format!("Loaded {}", path.display())
+                }
+                Err(error) => format!("Couldn't load: {}", error),
+            });
+        }
+    });
+    if let Some(status) = &state.status {
+        ui.label(status);
+    }
+}
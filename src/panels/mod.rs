@@ -0,0 +1,8 @@
+pub mod cameras_panel;
+pub mod command_palette;
+pub mod keymap_panel;
+pub mod modifier_panel;
+pub mod normals_panel;
+pub mod objects_panel;
+pub mod symmetry_panel;
+pub mod validation_panel;
@@ -0,0 +1,234 @@
+use eframe::egui;
+
+use crate::{
+    bmesh::{bmesh_boolean::BooleanOperation, bmesh_mirror::MirrorAxis},
+    math::vector::Vector3,
+    modifiers::{
+        array::ArrayModifier, bevel::BevelModifier, boolean::BooleanModifier,
+        mirror::MirrorModifier, solidify::SolidifyModifier, subdivision::SubdivisionModifier,
+        triangulate::TriangulateModifier, Modifier,
+    },
+    scene::Scene,
+};
+
+/// Lists the modifiers of the object, with controls to add, toggle, reorder, edit, apply and
+/// remove them. Returns whether anything changed that affects what the object looks like.
+pub fn show(
+    ui: &mut egui::Ui,
+    scene: &mut Scene,
+    object_index: usize,
+    rng: &mut impl rand::Rng,
+) -> bool {
+    let mut changed = false;
+
+    // For modifiers that refer to other objects.
+    let other_object_names: Vec<String> = scene
+        .objects
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != object_index)
+        .map(|(_, o)| o.name.clone())
+        .collect();
+
+    ui.heading(format!("Modifiers ({})", scene.objects[object_index].name));
+
+    ui.menu_button("Add modifier", |ui| {
+        let new_modifier = if ui.button("Mirror").clicked() {
+            Some(Modifier::Mirror(MirrorModifier::new(MirrorAxis::X)))
+        } else if ui.button("Array").clicked() {
+            Some(Modifier::Array(ArrayModifier::new(
+                2,
+                Vector3::unit_x() * 2.0,
+            )))
+        } else if ui.button("Subdivision").clicked() {
+            Some(Modifier::Subdivision(SubdivisionModifier::new(1)))
+        } else if ui.button("Solidify").clicked() {
+            Some(Modifier::Solidify(SolidifyModifier::new(0.1)))
+        } else if ui.button("Triangulate").clicked() {
+            Some(Modifier::Triangulate(TriangulateModifier))
+        } else if ui.button("Bevel").clicked() {
+            Some(Modifier::Bevel(BevelModifier::new(0.1)))
+        } else if ui.button("Boolean").clicked() {
+            Some(Modifier::Boolean(BooleanModifier::new(
+                BooleanOperation::Difference,
+            )))
+        } else {
+            None
+        };
+
+        if let Some(modifier) = new_modifier {
+            scene.objects[object_index].modifiers.push(modifier);
+            changed = true;
+            ui.close_menu();
+        }
+    });
+
+    let modifier_count = scene.objects[object_index].modifiers.get_entries().len();
+    for i in 0..modifier_count {
+        let entry = &scene.objects[object_index].modifiers.get_entries()[i];
+        let mut enabled = entry.is_enabled();
+        let mut modifier = entry.get_modifier().clone();
+        let mut rearranged = false;
+
+        ui.push_id(i, |ui| {
+            ui.group(|ui| {
+                ui.horizontal(|ui| {
+                    let modifiers = &mut scene.objects[object_index].modifiers;
+                    if ui.checkbox(&mut enabled, modifier.get_name()).changed() {
+                        modifiers.set_enabled(i, enabled);
+                        changed = true;
+                    }
+                    if ui.add_enabled(i > 0, egui::Button::new("Up")).clicked() {
+                        modifiers.move_modifier(i, i - 1);
+                        rearranged = true;
+                    }
+                    if ui
+                        .add_enabled(i + 1 < modifier_count, egui::Button::new("Down"))
+                        .clicked()
+                    {
+                        modifiers.move_modifier(i, i + 1);
+                        rearranged = true;
+                    }
+                    if ui.button("Remove").clicked() {
+                        modifiers.remove(i);
+                        rearranged = true;
+                    }
+                    if ui.button("Apply").clicked() {
+                        scene.apply_modifier(object_index, i, rng);
+                        rearranged = true;
+                    }
+                });
+
+                if !rearranged && show_settings(ui, &mut modifier, &other_object_names) {
+                    *scene.objects[object_index].modifiers.get_modifier_mut(i) = modifier;
+                    changed = true;
+                }
+            });
+        });
+
+        // The stack was rearranged, so the indices of the remaining entries are out of date.
+        if rearranged {
+            changed = true;
+            break;
+        }
+    }
+
+    changed
+}
+
+/// Returns whether any of the settings were changed.
+fn show_settings(
+    ui: &mut egui::Ui,
+    modifier: &mut Modifier,
+    other_object_names: &[String],
+) -> bool {
+    match modifier {
+        Modifier::Mirror(mirror) => {
+            let mut changed = axis_selector(ui, &mut mirror.axis);
+            ui.horizontal(|ui| {
+                changed |= ui.checkbox(&mut mirror.merge, "Merge").changed();
+                changed |= ui
+                    .add_enabled(
+                        mirror.merge,
+                        egui::DragValue::new(&mut mirror.merge_threshold)
+                            .speed(0.001)
+                            .clamp_range(0.0..=1.0),
+                    )
+                    .changed();
+            });
+            changed
+        }
+        Modifier::Array(array) => {
+            let mut changed = false;
+            let (mut x, mut y, mut z) = (array.offset.x(), array.offset.y(), array.offset.z());
+            ui.horizontal(|ui| {
+                ui.label("Count");
+                changed |= ui
+                    .add(egui::DragValue::new(&mut array.count).clamp_range(1..=100))
+                    .changed();
+            });
+            ui.horizontal(|ui| {
+                ui.label("Offset");
+                changed |= ui.add(egui::DragValue::new(&mut x).speed(0.05)).changed();
+                changed |= ui.add(egui::DragValue::new(&mut y).speed(0.05)).changed();
+                changed |= ui.add(egui::DragValue::new(&mut z).speed(0.05)).changed();
+            });
+            array.offset = Vector3::new(x, y, z);
+            changed
+        }
+        Modifier::Subdivision(subdivision) => {
+            ui.horizontal(|ui| {
+                ui.label("Levels");
+                ui.add(egui::DragValue::new(&mut subdivision.levels).clamp_range(0..=4))
+                    .changed()
+            })
+            .inner
+        }
+        Modifier::Solidify(solidify) => {
+            ui.horizontal(|ui| {
+                ui.label("Thickness");
+                ui.add(egui::DragValue::new(&mut solidify.thickness).speed(0.01))
+                    .changed()
+            })
+            .inner
+        }
+        Modifier::Triangulate(_) => false,
+        Modifier::Bevel(bevel) => {
+            ui.horizontal(|ui| {
+                ui.label("Width");
+                ui.add(
+                    egui::DragValue::new(&mut bevel.width)
+                        .speed(0.01)
+                        .clamp_range(0.0..=10.0),
+                )
+                .changed()
+            })
+            .inner
+        }
+        Modifier::Boolean(boolean) => {
+            let mut changed = operation_selector(ui, &mut boolean.operation);
+            ui.horizontal(|ui| {
+                ui.label("Object");
+                egui::ComboBox::from_id_source("boolean_cutter")
+                    .selected_text(boolean.cutter.as_deref().unwrap_or("None"))
+                    .show_ui(ui, |ui| {
+                        for name in other_object_names {
+                            let selected = boolean.cutter.as_ref() == Some(name);
+                            if ui.selectable_label(selected, name).clicked() && !selected {
+                                boolean.cutter = Some(name.clone());
+                                changed = true;
+                            }
+                        }
+                    });
+            });
+            changed
+        }
+    }
+}
+
+/// Returns whether the axis was changed.
+pub fn axis_selector(ui: &mut egui::Ui, axis: &mut MirrorAxis) -> bool {
+    let before = *axis;
+    ui.horizontal(|ui| {
+        ui.label("Axis");
+        ui.selectable_value(axis, MirrorAxis::X, "X");
+        ui.selectable_value(axis, MirrorAxis::Y, "Y");
+        ui.selectable_value(axis, MirrorAxis::Z, "Z");
+    });
+    *axis != before
+}
+
+/// Returns whether the operation was changed.
+pub fn operation_selector(ui: &mut egui::Ui, operation: &mut BooleanOperation) -> bool {
+    let before = *operation;
+    ui.horizontal(|ui| {
+        for option in [
+            BooleanOperation::Union,
+            BooleanOperation::Difference,
+            BooleanOperation::Intersect,
+        ] {
+            ui.selectable_value(operation, option, option.get_name());
+        }
+    });
+    *operation != before
+}
@@ -0,0 +1,43 @@
+use eframe::egui;
+
+use crate::scene::SceneObject;
+
+/// Controls for how the normals of the object are split: auto smooth, and marking the selected
+/// edges as sharp. Returns whether the normals changed.
+pub fn show(ui: &mut egui::Ui, object: &mut SceneObject) -> bool {
+    let mut changed = false;
+
+    ui.heading("Normals");
+
+    let current = object.mesh.get_auto_smooth_angle();
+    let mut enabled = current.is_some();
+    let mut degrees = current.unwrap_or(30.0_f32.to_radians()).to_degrees();
+    ui.horizontal(|ui| {
+        changed |= ui.checkbox(&mut enabled, "Auto smooth").changed();
+        changed |= ui
+            .add_enabled(
+                enabled,
+                egui::DragValue::new(&mut degrees)
+                    .speed(1.0)
+                    .clamp_range(0.0..=180.0)
+                    .suffix("°"),
+            )
+            .changed();
+    });
+    if changed {
+        object
+            .mesh
+            .set_auto_smooth_angle(enabled.then_some(degrees.to_radians()));
+    }
+
+    ui.horizontal(|ui| {
+        if ui.button("Mark sharp").clicked() {
+            changed |= object.mesh.set_selected_edges_sharp(true) > 0;
+        }
+        if ui.button("Clear sharp").clicked() {
+            changed |= object.mesh.set_selected_edges_sharp(false) > 0;
+        }
+    });
+
+    changed
+}
@@ -0,0 +1,118 @@
+use eframe::egui;
+
+use crate::{
+    bmesh::bmesh_boolean::BooleanOperation,
+    math::point::Point3,
+    operators::{get_boolean_parameters, OperatorParameters},
+    scene::Scene,
+};
+
+use super::modifier_panel::operation_selector;
+
+/// Settings of the panel that are kept between frames.
+pub struct ObjectsPanelState {
+    boolean_operation: BooleanOperation,
+    boolean_cutter: Option<usize>,
+}
+
+impl ObjectsPanelState {
+    pub fn new() -> Self {
+        Self {
+            boolean_operation: BooleanOperation::Difference,
+            boolean_cutter: None,
+        }
+    }
+}
+
+/// Lists the objects in the scene, to pick the active one and hide/show them, and has the
+/// operators that work on whole objects. Returns whether anything changed that affects what
+/// gets drawn, and the operator to run with its parameters (the default ones if `None`), if one
+/// was clicked.
+pub fn show(
+    ui: &mut egui::Ui,
+    state: &mut ObjectsPanelState,
+    scene: &mut Scene,
+) -> (bool, Option<(&'static str, Option<OperatorParameters>)>) {
+    let mut changed = false;
+    let mut operator_to_run = None;
+
+    ui.heading("Objects");
+
+    for i in 0..scene.objects.len() {
+        ui.horizontal(|ui| {
+            changed |= ui
+                .checkbox(&mut scene.objects[i].visible, "")
+                .on_hover_text("Visible")
+                .changed();
+            changed |= ui
+                .color_edit_button_rgb(&mut scene.objects[i].color)
+                .changed();
+            let is_active = scene.active_object == Some(i);
+            if ui
+                .selectable_label(is_active, &scene.objects[i].name)
+                .clicked()
+            {
+                scene.active_object = Some(i);
+            }
+        });
+    }
+
+    if ui.button("Add cube").clicked() {
+        operator_to_run = Some(("object.add_cube", None));
+    }
+
+    ui.horizontal(|ui| {
+        ui.label("3D cursor");
+        let mut coordinates = [scene.cursor.x(), scene.cursor.y(), scene.cursor.z()];
+        let mut cursor_changed = false;
+        for coordinate in &mut coordinates {
+            cursor_changed |= ui
+                .add(egui::DragValue::new(coordinate).speed(0.01))
+                .changed();
+        }
+        if cursor_changed {
+            scene.cursor = Point3::new(coordinates[0], coordinates[1], coordinates[2]);
+            changed = true;
+        }
+    });
+
+    let Some(active_object) = scene.active_object else {
+        return (changed, operator_to_run);
+    };
+
+    ui.separator();
+    ui.label(format!("Boolean ({})", scene.objects[active_object].name));
+    operation_selector(ui, &mut state.boolean_operation);
+    ui.horizontal(|ui| {
+        ui.label("With");
+        let selected_name = state
+            .boolean_cutter
+            .and_then(|i| scene.objects.get(i))
+            .map_or("None", |o| o.name.as_str());
+        egui::ComboBox::from_id_source("boolean_operator_cutter")
+            .selected_text(selected_name)
+            .show_ui(ui, |ui| {
+                for (i, object) in scene.objects.iter().enumerate() {
+                    if i != active_object {
+                        ui.selectable_value(&mut state.boolean_cutter, Some(i), &object.name);
+                    }
+                }
+            });
+    });
+
+    let cutter = state
+        .boolean_cutter
+        .filter(|i| *i != active_object && *i < scene.objects.len());
+    if ui
+        .add_enabled(cutter.is_some(), egui::Button::new("Apply boolean"))
+        .clicked()
+    {
+        let parameters = get_boolean_parameters(
+            state.boolean_operation,
+            &scene.objects[cutter.unwrap()].name,
+        );
+        operator_to_run = Some(("object.boolean", Some(parameters)));
+    }
+
+    (changed, operator_to_run)
+}
@@ -0,0 +1,47 @@
+use eframe::egui;
+
+use crate::{
+    bmesh::bmesh_mirror::{MirrorAxis, MirrorMatching, Symmetry},
+    scene::SceneObject,
+};
+
+use super::modifier_panel::axis_selector;
+
+/// Controls for editing the object with symmetry (see `Symmetry`).
+pub fn show(ui: &mut egui::Ui, object: &mut SceneObject) {
+    ui.heading("Symmetry");
+
+    let mut enabled = object.symmetry.is_some();
+    if ui.checkbox(&mut enabled, "Edit symmetrically").changed() {
+        object.symmetry = enabled.then_some(Symmetry {
+            axis: MirrorAxis::X,
+            matching: MirrorMatching::Positional,
+            threshold: 0.001,
+        });
+    }
+
+    if let Some(symmetry) = &mut object.symmetry {
+        axis_selector(ui, &mut symmetry.axis);
+        ui.horizontal(|ui| {
+            ui.label("Match by");
+            ui.selectable_value(
+                &mut symmetry.matching,
+                MirrorMatching::Positional,
+                "Position",
+            );
+            ui.selectable_value(
+                &mut symmetry.matching,
+                MirrorMatching::Topological,
+                "Topology",
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label("Threshold");
+            ui.add(
+                egui::DragValue::new(&mut symmetry.threshold)
+                    .speed(0.001)
+                    .clamp_range(0.0..=1.0),
+            );
+        });
+    }
+}
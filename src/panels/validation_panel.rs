@@ -0,0 +1,54 @@
+use eframe::egui;
+
+use crate::{bmesh::bmesh_validate::MeshProblem, scene::SceneObject};
+
+/// The problems found in the active object, kept until its mesh changes.
+pub struct ValidationPanelState {
+    checked: Option<(String, u64)>,
+    problems: Vec<MeshProblem>,
+}
+
+impl ValidationPanelState {
+    pub fn new() -> Self {
+        Self {
+            checked: None,
+            problems: Vec::new(),
+        }
+    }
+}
+
+/// Lists the problems with the mesh of the object (see `BMesh::validate()`). Clicking one
+/// selects the elements involved. Returns whether the selection changed.
+pub fn show(ui: &mut egui::Ui, state: &mut ValidationPanelState, object: &mut SceneObject) -> bool {
+    ui.heading("Mesh problems");
+
+    let key = (object.name.clone(), object.mesh.get_revision());
+    if state.checked.as_ref() != Some(&key) {
+        state.problems = object.mesh.validate();
+        state.checked = Some(key);
+    }
+
+    if state.problems.is_empty() {
+        ui.label("None found");
+        return false;
+    }
+
+    let mut changed = false;
+    egui::ScrollArea::vertical()
+        .id_source("mesh_problems")
+        .max_height(150.0)
+        .show(ui, |ui| {
+            for problem in &state.problems {
+                if ui
+                    .selectable_label(false, problem.get_description())
+                    .on_hover_text("Select the elements involved")
+                    .clicked()
+                {
+                    object.mesh.select_problem(problem);
+                    changed = true;
+                }
+            }
+        });
+
+    changed
+}
@@ -5,10 +5,16 @@ pub struct DepthTexture {
 }
 
 impl DepthTexture {
-    const DEPTH_FORMAT: eframe::wgpu::TextureFormat = eframe::wgpu::TextureFormat::Depth32Float;
+    pub(super) const DEPTH_FORMAT: eframe::wgpu::TextureFormat =
+        eframe::wgpu::TextureFormat::Depth32Float;
     const COMPARE_FUNCTION: eframe::wgpu::CompareFunction = eframe::wgpu::CompareFunction::Less;
 
-    pub fn new(device: &eframe::wgpu::Device, window_size: &eframe::egui::Vec2) -> Self {
+    /// The sample count needs to be the same as that of the color target it is used with.
+    pub fn new(
+        device: &eframe::wgpu::Device,
+        window_size: &eframe::egui::Vec2,
+        sample_count: u32,
+    ) -> Self {
         let size = eframe::wgpu::Extent3d {
             width: window_size.x as u32,
             height: window_size.y as u32,
@@ -18,7 +24,7 @@ impl DepthTexture {
             label: Some("depth texture"),
             size,
             mip_level_count: 1,
-            sample_count: 1,
+            sample_count,
             dimension: eframe::wgpu::TextureDimension::D2,
             format: Self::DEPTH_FORMAT,
             usage: eframe::wgpu::TextureUsages::RENDER_ATTACHMENT
@@ -61,7 +67,7 @@ impl DepthTexture {
 
     pub fn create_depth_stencil_attachment(
         &self,
-    ) -> eframe::wgpu::RenderPassDepthStencilAttachment {
+    ) -> eframe::wgpu::RenderPassDepthStencilAttachment<'_> {
         eframe::wgpu::RenderPassDepthStencilAttachment {
             view: &self.texture_view,
             depth_ops: Some(eframe::wgpu::Operations {
@@ -1,4 +1,4 @@
-use crate::meshes::Mesh;
+use crate::{math::point::Point3, meshes::Mesh};
 use std::sync::Arc;
 
 use super::wgpu_mesh::WgpuMesh;
@@ -9,7 +9,8 @@ pub struct DrawCommand {
 }
 
 pub enum DrawCommandKind {
-    // Do a "draw indexed" call on all the indices
+    // Do a "draw indexed" call on all the indices, one per mesh so that each gets its own
+    // instance data
     DrawIndexedAll,
 }
 
@@ -17,10 +18,15 @@ impl DrawCommand {
     /// Prepare a collection of meshes to be drawn. The output is a single
     /// `DrawCommand` that can be executed to draw the specified meshes.
     /// The order in which the meshes are drawn (within the same GPU draw
-    /// call) is not well-defined.
-    pub fn from_meshes(device: &eframe::wgpu::Device, meshes: &Vec<Arc<Mesh>>) -> DrawCommand {
+    /// call) is not well-defined, unless `sort_from` is given (see
+    /// `WgpuMesh::from_meshes()`).
+    pub fn from_meshes(
+        device: &eframe::wgpu::Device,
+        meshes: &[Arc<Mesh>],
+        sort_from: Option<Point3>,
+    ) -> DrawCommand {
         DrawCommand {
-            wgpu_mesh: WgpuMesh::from_meshes(device, meshes),
+            wgpu_mesh: WgpuMesh::from_meshes(device, meshes, sort_from),
             kind: DrawCommandKind::DrawIndexedAll,
         }
     }
@@ -1,17 +1,40 @@
 use std::sync::Arc;
 
-use crate::{camera::Camera, math::vector::Vector2, meshes::Mesh};
+use crate::{camera::Camera, edges::EdgeMesh, math::vector::Vector2, meshes::Mesh, scene::Scene};
 
-use super::{grid::GridUniform, viewport::ViewportUniform};
+use super::{
+    grid::{GridSettings, GridUniform},
+    overlays::{OverlayMesh, OverlaySettings, OverlayUniform},
+    viewport::{ViewportShading, ViewportUniform},
+    wireframe::WireframeUniform,
+};
 
 /// This encapsulates things that relate to drawing but are still CPU-specific.
 #[derive(Clone)]
 pub struct DrawingStuff {
     pub meshes_to_draw: Vec<Arc<Mesh>>,
+    /// The edges of the same objects as `meshes_to_draw`, for the wireframe.
+    pub edges_to_draw: Vec<Arc<EdgeMesh>>,
+    /// The edges of the object in edit mode (also in `edges_to_draw`), which are shown even
+    /// when the shading mode doesn't draw edges.
+    pub edited_edges: Option<Arc<EdgeMesh>>,
+    /// The vertices, face dots and normals of the object in edit mode.
+    pub overlay_to_draw: Option<Arc<OverlayMesh>>,
+    /// The frustums of the scene cameras, which are shown whatever the shading mode.
+    pub gizmo_edges: Option<Arc<EdgeMesh>>,
     pub drawing_region_size: Vector2,
     pub drawing_region_size_updated: bool,
+    /// To go from the size of the drawing region to the size of the texture it is drawn into.
+    pub pixels_per_point: f32,
+    /// How many samples per pixel the viewport is drawn with (see `viewport_target.rs`).
+    pub sample_count: u32,
+    /// What the viewport is cleared to. Transparent lets whatever egui drew behind it show.
+    pub background: eframe::wgpu::Color,
     pub camera_uniform: ViewportUniform,
     pub grid_uniform: GridUniform,
+    pub wireframe_uniform: WireframeUniform,
+    pub overlay_uniform: OverlayUniform,
+    pub shading: ViewportShading,
     // depth_texture: DepthTexture,
 }
 
@@ -19,10 +42,103 @@ impl DrawingStuff {
     pub fn initialize(camera: &Camera) -> Self {
         DrawingStuff {
             meshes_to_draw: Vec::new(),
+            edges_to_draw: Vec::new(),
+            edited_edges: None,
+            overlay_to_draw: None,
+            gizmo_edges: None,
             drawing_region_size: Vector2::new(0.0, 0.0),
             drawing_region_size_updated: true,
+            pixels_per_point: 1.0,
+            sample_count: 4,
+            background: eframe::wgpu::Color::TRANSPARENT,
             camera_uniform: ViewportUniform::new(camera),
-            grid_uniform: GridUniform::new(camera),
+            grid_uniform: GridUniform::new(camera, &GridSettings::new()),
+            wireframe_uniform: WireframeUniform::new(camera),
+            overlay_uniform: OverlayUniform::new(camera),
+            shading: ViewportShading::new(),
         }
     }
+
+    /// Regenerates what gets drawn from the objects in the scene. Call this after changing any
+    /// of them.
+    pub fn refresh_from_scene(&mut self, scene: &mut Scene, overlays: &OverlaySettings) {
+        let rng = &mut rand::thread_rng();
+        let mut meshes_to_draw = Vec::new();
+        let mut edges_to_draw = Vec::new();
+        let mut edited_edges = None;
+        let mut overlay_to_draw = None;
+        for i in 0..scene.objects.len() {
+            if !scene.objects[i].visible {
+                continue;
+            }
+
+            let is_edited = scene.edit_mode && scene.active_object == Some(i);
+            if is_edited {
+                // The overlays show the mesh being edited, before any modifiers.
+                overlay_to_draw = Some(Arc::new(OverlayMesh::from_bmesh(
+                    &scene.objects[i].mesh,
+                    scene.select_mode,
+                    overlays,
+                )));
+            }
+
+            let color = scene.objects[i].color;
+            let mesh = scene.get_evaluated_mesh(i, rng);
+            meshes_to_draw.push(Arc::new(Mesh::from_bmesh(mesh, color)));
+            let edges = Arc::new(EdgeMesh::from_bmesh(mesh, is_edited));
+            if is_edited {
+                edited_edges = Some(edges.clone());
+            }
+            edges_to_draw.push(edges);
+        }
+        self.meshes_to_draw = meshes_to_draw;
+        self.edges_to_draw = edges_to_draw;
+        self.edited_edges = edited_edges;
+        self.overlay_to_draw = overlay_to_draw;
+        self.gizmo_edges = Self::create_gizmo_edges(scene);
+    }
+
+    fn create_gizmo_edges(scene: &Scene) -> Option<Arc<EdgeMesh>> {
+        let mut lines = scene
+            .cameras
+            .iter()
+            .flat_map(|camera| camera.get_frustum_lines())
+            .chain(scene.get_cursor_lines())
+            .map(|(p0, p1)| EdgeMesh::new_from_two_points(&p0, &p1, [false, false]));
+        let mut edges = lines.next()?;
+        lines.for_each(|line| edges.append(line));
+        Some(Arc::new(edges))
+    }
+
+    /// Draws the same as the other, which has been refreshed already, e.g. in another viewport.
+    pub fn share_meshes_from(&mut self, other: &DrawingStuff) {
+        self.meshes_to_draw = other.meshes_to_draw.clone();
+        self.edges_to_draw = other.edges_to_draw.clone();
+        self.edited_edges = other.edited_edges.clone();
+        self.overlay_to_draw = other.overlay_to_draw.clone();
+        self.gizmo_edges = other.gizmo_edges.clone();
+    }
+
+    /// Updates the CPU side uniforms for the camera and the current settings. The size of the
+    /// drawing region needs to be up to date.
+    pub fn update_uniforms(&mut self, camera: &Camera, grid: &GridSettings) {
+        self.camera_uniform.update_view_projection_matrix(camera);
+        self.camera_uniform.set_lighting(self.shading.lighting);
+        let opacity = if self.shading.x_ray {
+            self.shading.x_ray_opacity
+        } else {
+            1.0
+        };
+        self.camera_uniform.set_opacity(opacity);
+
+        // Line widths and point sizes are in pixels of the viewport texture.
+        let size_in_pixels = Vector2::new(
+            self.drawing_region_size.x() * self.pixels_per_point,
+            self.drawing_region_size.y() * self.pixels_per_point,
+        );
+        self.wireframe_uniform
+            .update(camera, &size_in_pixels, &self.shading);
+        self.overlay_uniform.update(camera, &size_in_pixels);
+        self.grid_uniform.update(camera, grid);
+    }
 }
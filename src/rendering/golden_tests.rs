@@ -0,0 +1,212 @@
+//! Render regression tests. Each test draws a scene with the headless renderer and compares the
+//! image with a reference ("golden") image checked in under `tests/golden/`.
+//!
+//! When an image doesn't match, the image that was drawn and an image of the differences are
+//! written to `target/golden/`. Running the tests with `UPDATE_GOLDEN=1` writes the references
+//! instead. Look at them before checking them in. A missing reference is a failure otherwise, as
+//! is not having an adapter to draw with.
+
+use std::path::{Path, PathBuf};
+
+use rand::SeedableRng;
+
+use crate::{
+    bmesh::bmesh::BMesh,
+    camera::{Camera, ViewPreset},
+    math::vector::Vector2,
+    scene::{Scene, SceneObject},
+};
+
+use super::{
+    headless::{HeadlessError, HeadlessRenderer, RenderedImage},
+    viewport::ShadingMode,
+};
+
+/// Small enough for the software adapter to draw quickly.
+const SIZE: [u32; 2] = [320, 240];
+
+/// How far apart the channels of a pixel can be from the reference before the pixel counts as
+/// different. Adapters don't all rasterize and blend exactly the same.
+const CHANNEL_TOLERANCE: u8 = 8;
+
+/// The share of the pixels that can be different, for the edges of triangles and lines, where
+/// adapters disagree the most.
+const MAX_DIFFERENT_PIXELS: f32 = 0.002;
+
+fn create_renderer() -> HeadlessRenderer {
+    match HeadlessRenderer::new() {
+        Ok(renderer) => renderer,
+        Err(HeadlessError::NoAdapter) => panic!(
+            "There is no adapter to render with, not even the fallback one. On Linux, Mesa's \
+             llvmpipe provides one."
+        ),
+        Err(error) => panic!("Couldn't create the headless renderer: {}", error),
+    }
+}
+
+/// The startup scene: a cube at the origin. The random number generator is seeded so that the
+/// mesh comes out the same every time.
+fn create_cube_scene() -> Scene {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+    let mut scene = Scene::new();
+    scene.add_object(SceneObject::new("Cube", BMesh::create_cube(&mut rng)));
+    scene
+}
+
+/// The camera the app starts with, turned as if dragged by `delta_mouse` on a viewport of
+/// `SIZE`.
+fn create_camera(delta_mouse: Vector2) -> Camera {
+    let (width, height) = (SIZE[0] as f32, SIZE[1] as f32);
+    let mut camera = Camera::initialize(width, height);
+    camera.turntable_rotate(delta_mouse, (width, height));
+    camera.solidify_view_info();
+    camera
+}
+
+fn get_golden_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.png", name))
+}
+
+fn get_failure_path(name: &str, kind: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("target/golden")
+        .join(format!("{}.{}.png", name, kind))
+}
+
+/// Black where the pixels match, getting brighter the further apart they are, and red where
+/// they are too far apart.
+fn create_difference_image(actual: &RenderedImage, expected: &RenderedImage) -> RenderedImage {
+    let pixels = actual
+        .pixels
+        .chunks(4)
+        .zip(expected.pixels.chunks(4))
+        .flat_map(|(a, e)| {
+            let difference = a.iter().zip(e).map(|(a, e)| a.abs_diff(*e)).max().unwrap();
+            if difference > CHANNEL_TOLERANCE {
+                [255, 0, 0, 255]
+            } else {
+                let brightness = difference * (255 / CHANNEL_TOLERANCE);
+                [brightness, brightness, brightness, 255]
+            }
+        })
+        .collect();
+
+    RenderedImage {
+        size: actual.size,
+        pixels,
+    }
+}
+
+/// Panics if the image doesn't match the reference called `name`, after writing the failure
+/// images (see the top of the file).
+fn assert_matches_golden(name: &str, actual: &RenderedImage) {
+    let golden_path = get_golden_path(name);
+    if std::env::var_os("UPDATE_GOLDEN").is_some_and(|value| value == "1") {
+        std::fs::create_dir_all(golden_path.parent().unwrap()).unwrap();
+        actual.write_png(&golden_path).unwrap();
+        eprintln!("Wrote the reference image {}", golden_path.display());
+        return;
+    }
+    assert!(
+        golden_path.exists(),
+        "There is no reference image {}. Run the tests with UPDATE_GOLDEN=1 to write it.",
+        golden_path.display()
+    );
+
+    let expected = RenderedImage::read_png(&golden_path).unwrap();
+    let different_pixels = if actual.size == expected.size {
+        actual
+            .pixels
+            .chunks(4)
+            .zip(expected.pixels.chunks(4))
+            .filter(|(a, e)| {
+                a.iter()
+                    .zip(*e)
+                    .any(|(a, e)| a.abs_diff(*e) > CHANNEL_TOLERANCE)
+            })
+            .count()
+    } else {
+        actual.pixels.len() / 4
+    };
+    let pixel_count = (actual.size[0] * actual.size[1]) as f32;
+    if different_pixels as f32 <= MAX_DIFFERENT_PIXELS * pixel_count {
+        return;
+    }
+
+    std::fs::create_dir_all(get_failure_path(name, "").parent().unwrap()).unwrap();
+    let actual_path = get_failure_path(name, "actual");
+    actual.write_png(&actual_path).unwrap();
+    if actual.size == expected.size {
+        create_difference_image(actual, &expected)
+            .write_png(&get_failure_path(name, "difference"))
+            .unwrap();
+    }
+    panic!(
+        "The image ({:?}) doesn't match {} ({:?}): {} of {} pixels are different. What was \
+         drawn is in {}.",
+        actual.size,
+        golden_path.display(),
+        expected.size,
+        different_pixels,
+        pixel_count,
+        actual_path.display(),
+    );
+}
+
+/// Draws the scene with the given shading, and compares it with the reference called `name`.
+fn check_render(name: &str, scene: &mut Scene, camera: &mut Camera, mode: ShadingMode) {
+    let mut renderer = create_renderer();
+    renderer.drawing_stuff.shading.mode = mode;
+    // Adapters resolve multisampled images differently, and GL's fallback adapter not at all.
+    renderer.drawing_stuff.sample_count = 1;
+    let image = renderer.render(scene, camera, SIZE).unwrap();
+    assert_matches_golden(name, &image);
+}
+
+#[test]
+fn cube_solid() {
+    let mut camera = create_camera(Vector2::new(40.0, 30.0));
+    check_render(
+        "cube_solid",
+        &mut create_cube_scene(),
+        &mut camera,
+        ShadingMode::SolidWithWireframe,
+    );
+}
+
+#[test]
+fn cube_wireframe() {
+    let mut camera = create_camera(Vector2::new(-60.0, 45.0));
+    check_render(
+        "cube_wireframe",
+        &mut create_cube_scene(),
+        &mut camera,
+        ShadingMode::Wireframe,
+    );
+}
+
+#[test]
+fn grid_from_above() {
+    let mut camera = create_camera(Vector2::new(30.0, 90.0));
+    check_render(
+        "grid_from_above",
+        &mut Scene::new(),
+        &mut camera,
+        ShadingMode::Solid,
+    );
+}
+
+#[test]
+fn grid_side_view() {
+    // Looking straight down the Z axis, where the grid switches to the XY plane.
+    let mut camera = create_camera(Vector2::new(0.0, 0.0));
+    camera.set_view_preset(ViewPreset::Front);
+    check_render(
+        "grid_side_view",
+        &mut Scene::new(),
+        &mut camera,
+        ShadingMode::Solid,
+    );
+}
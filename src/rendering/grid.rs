@@ -1,41 +1,211 @@
-use std::sync::Arc;
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
 
 use eframe::wgpu::util::DeviceExt;
+use serde::{Deserialize, Serialize};
 
-use crate::{camera::Camera, math::matrix::Matrix4};
+use crate::{
+    camera::Camera,
+    math::{vector::Vector3, Float},
+};
 
-use super::depth_texture::DepthTexture;
+use super::{depth_texture::DepthTexture, viewport_target::ViewportTarget};
+
+/// How the grid is drawn. Stored in the scene, since it depends on the scale of what is being
+/// modeled. The settings can be saved to `grid.toml` in the config directory, where they are
+/// loaded from on startup.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default = "GridSettings::new")]
+pub struct GridSettings {
+    /// The distance between the grid lines, in world units. The grid gets coarser by 10x at a
+    /// time as the camera moves away from it.
+    pub spacing: f32,
+    /// How many cells each cell is split into by fainter lines. 1 for none.
+    pub minor_subdivisions: u32,
+    /// Every how many cells a brighter line is drawn. 1 for none.
+    pub major_subdivisions: u32,
+    /// Whether the lines along the axes are drawn in their color.
+    pub show_axes: bool,
+    /// How far from the camera the grid fades out, in world units. It grows with the spacing as
+    /// the grid gets coarser.
+    pub fade_distance: f32,
+}
+
+impl GridSettings {
+    pub fn new() -> Self {
+        Self {
+            spacing: 1.0,
+            minor_subdivisions: 10,
+            major_subdivisions: 10,
+            show_axes: true,
+            fade_distance: 20.0,
+        }
+    }
+
+    /// Where the settings are saved, if there is a config directory on this platform.
+    pub fn get_path() -> Option<PathBuf> {
+        Some(dirs::config_dir()?.join("fe").join("grid.toml"))
+    }
+
+    /// The saved settings, or the default ones if there are none or they can't be loaded.
+    pub fn load_or_default() -> Self {
+        let Some(path) = Self::get_path().filter(|path| path.exists()) else {
+            return Self::new();
+        };
+        Self::load(&path).unwrap_or_else(|error| {
+            log::error!(
+                "Couldn't load the grid settings from {}: {}",
+                path.display(),
+                error
+            );
+            Self::new()
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, GridSettingsError> {
+        let text = std::fs::read_to_string(path).map_err(GridSettingsError::Io)?;
+        toml::from_str(&text).map_err(GridSettingsError::Parsing)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), GridSettingsError> {
+        let text = toml::to_string_pretty(self).map_err(GridSettingsError::Serializing)?;
+        if let Some(directory) = path.parent() {
+            std::fs::create_dir_all(directory).map_err(GridSettingsError::Io)?;
+        }
+        std::fs::write(path, text).map_err(GridSettingsError::Io)
+    }
+}
+
+#[derive(Debug)]
+pub enum GridSettingsError {
+    Io(std::io::Error),
+    Parsing(toml::de::Error),
+    Serializing(toml::ser::Error),
+}
+
+impl fmt::Display for GridSettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GridSettingsError::Io(error) => write!(f, "{}", error),
+            GridSettingsError::Parsing(error) => write!(f, "invalid grid settings file: {}", error),
+            GridSettingsError::Serializing(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for GridSettingsError {}
+
+/// Which plane the grid lies on. It lies on the ground unless the view is orthographic and looks
+/// straight along the X or Z axis, from where the ground would only be seen edge-on.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GridPlane {
+    XZ,
+    XY,
+    YZ,
+}
+
+impl GridPlane {
+    /// How close the view direction needs to be to an axis for the view to count as a side view.
+    const SIDE_VIEW_ALIGNMENT: Float = 0.999;
+
+    pub fn for_camera(camera: &Camera) -> Self {
+        if !camera.is_orthographic() {
+            return GridPlane::XZ;
+        }
+
+        let (right, up) = camera.get_right_and_up();
+        let forward = Vector3::cross(up, right);
+        if forward.x().abs() > Self::SIDE_VIEW_ALIGNMENT {
+            GridPlane::YZ
+        } else if forward.z().abs() > Self::SIDE_VIEW_ALIGNMENT {
+            GridPlane::XY
+        } else {
+            GridPlane::XZ
+        }
+    }
+
+    /// The index of the axis the plane is perpendicular to.
+    fn get_normal_axis(&self) -> u32 {
+        match self {
+            GridPlane::XZ => 1,
+            GridPlane::XY => 2,
+            GridPlane::YZ => 0,
+        }
+    }
+}
+
+/// The colors of the X, Y and Z axis lines.
+const AXIS_COLORS: [[f32; 4]; 3] = [
+    [0.9, 0.2, 0.25, 1.0],
+    [0.45, 0.8, 0.1, 1.0],
+    [0.15, 0.45, 0.95, 1.0],
+];
+
+/// How far from the grid (in cells) the camera can get before the grid gets coarser.
+const LOD_DISTANCE: Float = 10.0;
 
 #[repr(C)]
 #[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct GridUniform {
     view_projection_matrix: [[f32; 4]; 4],
     view_projection_matrix_inverse: [[f32; 4]; 4],
-    z_near: f32,
-    z_far: f32,
-    // Warning: The alignment is sizeof([f32; 4]) = 16, but this does NOT mean that each f32
-    // needs 12 bytes of padding. It seems them next to each other, as long as we end on the
-    // right alignment size. For example, here we need 8 bytes of padding, but if we had a
-    // single f32 followed by a vec4<f32>, then the f32 would need 12 bytes of padding.
-    _padding: [i32; 2],
+    camera_position: [f32; 4],
+    axis_colors: [[f32; 4]; 3],
+    /// After the level of detail is applied, as is `fade_distance`.
+    spacing: f32,
+    fade_distance: f32,
+    minor_subdivisions: f32,
+    major_subdivisions: f32,
+    normal_axis: u32,
+    show_axes: u32,
+    // The struct is aligned to 16 bytes, like the vec4s in it.
+    _padding: [u32; 2],
 }
 
 impl GridUniform {
-    pub fn new(camera: &Camera) -> Self {
-        Self {
-            view_projection_matrix: camera.get_view_projection_matrix().into(),
-            view_projection_matrix_inverse: camera.get_view_projection_matrix_inverse().into(),
-            z_near: camera.get_z_near(),
-            z_far: camera.get_z_far(),
+    pub fn new(camera: &Camera, settings: &GridSettings) -> Self {
+        let mut uniform = Self {
+            view_projection_matrix: [[0.0; 4]; 4],
+            view_projection_matrix_inverse: [[0.0; 4]; 4],
+            camera_position: [0.0; 4],
+            axis_colors: AXIS_COLORS,
+            spacing: 1.0,
+            fade_distance: 1.0,
+            minor_subdivisions: 1.0,
+            major_subdivisions: 1.0,
+            normal_axis: 1,
+            show_axes: 0,
             _padding: [0; 2],
-        }
+        };
+        uniform.update(camera, settings);
+        uniform
     }
 
-    pub fn update_matrix(&mut self, camera: &Camera) {
+    pub fn update(&mut self, camera: &Camera, settings: &GridSettings) {
         self.view_projection_matrix = camera.get_view_projection_matrix().into();
         self.view_projection_matrix_inverse = camera.get_view_projection_matrix_inverse().into();
-        self.z_near = camera.get_z_near();
-        self.z_far = camera.get_z_far();
+        let position = camera.get_position();
+        self.camera_position = [position.x(), position.y(), position.z(), 1.0];
+
+        let plane = GridPlane::for_camera(camera);
+        self.normal_axis = plane.get_normal_axis();
+
+        // Each time the camera gets 10x further from the grid, so do the lines, which keeps
+        // about as many of them on the screen. The grid never gets finer than the spacing set.
+        let distance = [position.x(), position.y(), position.z()][self.normal_axis as usize].abs();
+        let level = (distance / (settings.spacing * LOD_DISTANCE))
+            .log10()
+            .floor()
+            .max(0.0);
+        let scale = Float::powf(10.0, level);
+        self.spacing = settings.spacing * scale;
+        self.fade_distance = settings.fade_distance * scale;
+
+        self.minor_subdivisions = settings.minor_subdivisions.max(1) as f32;
+        self.major_subdivisions = settings.major_subdivisions.max(1) as f32;
+        self.show_axes = settings.show_axes as u32;
     }
 }
 
@@ -48,20 +218,19 @@ pub(super) struct GridRenderResources {
 }
 
 pub(super) struct GridRenderResourcesInitializeArgs<'a> {
-    pub device: &'a Arc<eframe::wgpu::Device>,
+    pub device: &'a eframe::wgpu::Device,
     pub surface_format: eframe::wgpu::TextureFormat,
-    pub view_projection_matrix: Matrix4,
-    pub camera: &'a Camera,
+    pub sample_count: u32,
+    pub uniform: &'a GridUniform,
 }
 
 impl GridRenderResources {
     pub fn initialize(args: GridRenderResourcesInitializeArgs) -> Self {
-        let uniform = GridUniform::new(args.camera);
         let buffer = args
             .device
             .create_buffer_init(&eframe::wgpu::util::BufferInitDescriptor {
                 label: Some("grid buffer"),
-                contents: bytemuck::cast_slice(&[uniform]),
+                contents: bytemuck::cast_slice(&[*args.uniform]),
                 usage: eframe::wgpu::BufferUsages::UNIFORM | eframe::wgpu::BufferUsages::COPY_DST,
             });
 
@@ -139,7 +308,7 @@ impl GridRenderResources {
                         conservative: false,
                     },
                     depth_stencil: Some(DepthTexture::create_depth_stencil_state()),
-                    multisample: eframe::wgpu::MultisampleState::default(),
+                    multisample: ViewportTarget::create_multisample_state(args.sample_count),
                     multiview: None,
                 });
 
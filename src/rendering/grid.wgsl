@@ -3,8 +3,16 @@
 struct UnprojectUniform {
     view_projection_matrix: mat4x4<f32>,
     view_projection_matrix_inv: mat4x4<f32>,
-    z_near: f32,
-    z_far: f32,
+    camera_position: vec4<f32>,
+    // X, Y and Z.
+    axis_colors: array<vec4<f32>, 3>,
+    spacing: f32,
+    fade_distance: f32,
+    minor_subdivisions: f32,
+    major_subdivisions: f32,
+    // The index of the axis the grid plane is perpendicular to.
+    normal_axis: u32,
+    show_axes: u32,
 };
 
 @group(0) @binding(0)
@@ -46,26 +54,23 @@ fn vs_main(
 
 // S==== Fragment shader {{{1
 
-// `scale` must be integral (TODO: determined the precise restriction, e.g. 0.5 is also 
-// valid).
-fn grid(fragPos3D: vec3<f32>, scale: f32) -> vec4<f32> {
-    // Every fragment (projected onto the XZ-plane) will become a point on the grid.
-    let point_on_grid: vec2<f32> = fragPos3D.xz * scale;
-
-    // Affects how thick the lines on the grid are (higher -> thicker).
-    let grid_line_scale: f32 = 3.;
+// How much of the pixel is covered by the lines every `cell` units along both coordinates of
+// `point_on_grid`, between 0 and 1. `derivative` is `fwidth(point_on_grid)`, which is taken once
+// up front since it can't be taken in non-uniform control flow.
+fn grid_lines(point_on_grid: vec2<f32>, derivative: vec2<f32>, cell: f32, thickness: f32) -> f32 {
+    // Dividing by the cell size makes every line integral.
+    let scaled_point: vec2<f32> = point_on_grid / cell;
     // The derivative on its own specifies the opacity falloff. By modifying it by the
-    // grid line scale, we create a affect the thickness / range of the falloff.
-    let scaled_derivative: vec2<f32> = grid_line_scale * fwidth(point_on_grid);
+    // thickness, we affect the range of the falloff.
+    let scaled_derivative: vec2<f32> = thickness * derivative / cell;
 
     // The reason for the repeated subtraction of 0.5 is to handle negative numbers; 
     // for the purpose of the grid, we don't care about the sign of the points on the
     // grid, e.g. we want to treat -0.8 like we treat 0.2.
     //
-    // For a point on a grid line, at least one of the x/z terms is integral, and so the 
+    // For a point on a grid line, at least one of the terms is integral, and so the 
     // absolute value term evaluates to 0 and so full opacity is maintained.
     //
-    // Dividing by the (scaled) derivative accounts for the scale passed into the function.
     // For a point in the center of a grid square close to the camera, which we would want 
     // to be transparent, the derivative, which would be the "distance" to the point on 
     // the grid the pixel one right one up projects to, would be small, hence this value
@@ -75,20 +80,42 @@ fn grid(fragPos3D: vec3<f32>, scale: f32) -> vec4<f32> {
     // small again but the absolute value term would also be close to 0, so the result would
     // be a number that is not very large, which would account for the blending we want to 
     // have for anti-aliasing.
-    let grid: vec2<f32> = abs(fract(point_on_grid - 0.5) - 0.5) / scaled_derivative;
+    let grid: vec2<f32> = abs(fract(scaled_point - 0.5) - 0.5) / scaled_derivative;
     let the_line: f32 = min(grid.x, grid.y);
-    let minimum_z: f32 = min(scaled_derivative.y, 1.0);
-    let minimum_x: f32 = min(scaled_derivative.x, 1.0);
-    var color: vec4<f32> = vec4(0.2, 0.2, 0.2, 1.0 - min(the_line, 1.0));
-
-    let axis_scale: f32 = 0.3; // higher -> thicker axis lines
-    // z axis
-    if (fragPos3D.x > -axis_scale * minimum_x && fragPos3D.x < axis_scale * minimum_x) {
-        color.z = 1.0;
-    }
-    // x axis
-    if (fragPos3D.z > -axis_scale * minimum_z && fragPos3D.z < axis_scale * minimum_z) {
-        color.x = 1.0;
+
+    return 1.0 - min(the_line, 1.0);
+}
+
+// How much of the pixel is covered by the line where `coordinate` is 0.
+fn axis_line(coordinate: f32, derivative: f32) -> f32 {
+    let axis_thickness: f32 = 2.0; // higher -> thicker axis lines
+    return 1.0 - min(abs(coordinate) / (axis_thickness * derivative), 1.0);
+}
+
+// `point_on_grid` holds the coordinates along the axes `axis_u` and `axis_v` of the plane, and
+// `derivative` is `fwidth(point_on_grid)`. It is taken in `fs_main()`, since the GLSL backend
+// puts this function in the vertex shader too, where there are no derivatives.
+fn grid(point_on_grid: vec2<f32>, derivative: vec2<f32>, axis_u: u32, axis_v: u32) -> vec4<f32> {
+    let spacing: f32 = unproject_uniform.spacing;
+
+    // The minor lines fade out as they get close enough together on the screen to alias.
+    let minor_cell: f32 = spacing / unproject_uniform.minor_subdivisions;
+    let minor_cell_in_pixels: f32 = minor_cell / max(max(derivative.x, derivative.y), 1e-6);
+    let minor_fading: f32 = clamp((minor_cell_in_pixels - 2.0) / 6.0, 0.0, 1.0);
+
+    let minor: f32 = 0.3 * minor_fading * grid_lines(point_on_grid, derivative, minor_cell, 1.0);
+    let regular: f32 = 0.6 * grid_lines(point_on_grid, derivative, spacing, 1.5);
+    let major: f32 = grid_lines(
+        point_on_grid, derivative, spacing * unproject_uniform.major_subdivisions, 2.0
+    );
+    var color: vec4<f32> = vec4(0.2, 0.2, 0.2, max(minor, max(regular, major)));
+
+    if (unproject_uniform.show_axes != 0u) {
+        // The line along the u axis is where the v coordinate is 0, and the other way around.
+        let along_u: f32 = axis_line(point_on_grid.y, derivative.y);
+        let along_v: f32 = axis_line(point_on_grid.x, derivative.x);
+        color = mix(color, unproject_uniform.axis_colors[axis_u], along_u);
+        color = mix(color, unproject_uniform.axis_colors[axis_v], along_v);
     }
 
     return color;
@@ -102,26 +129,23 @@ struct FragmentShaderOutput {
 @fragment
 fn fs_main(in: VertexOut) -> FragmentShaderOutput {
     var output: FragmentShaderOutput;
-    
-    // Solve for y=0 to determine to point where the fragment projects onto the XZ-plane.
-    var t: f32 = -in.nearPoint.y / (in.farPoint.y - in.nearPoint.y);    
-    let fragment_on_xz_plane: vec3<f32> = in.nearPoint + t * (in.farPoint - in.nearPoint);
-    let fragment_on_xz_plane_clip_space: vec4<f32> = 
-        unproject_uniform.view_projection_matrix * vec4<f32>(fragment_on_xz_plane.xyz, 1.0);
 
-    let depth: f32 = fragment_on_xz_plane_clip_space.z / fragment_on_xz_plane_clip_space.w;
+    let normal_axis: u32 = unproject_uniform.normal_axis;
+    let axis_u: u32 = (normal_axis + 1u) % 3u;
+    let axis_v: u32 = (normal_axis + 2u) % 3u;
+
+    // Solve for the coordinate along the normal being 0 to determine the point where the
+    // fragment projects onto the grid plane.
+    var t: f32 = -in.nearPoint[normal_axis] / (in.farPoint[normal_axis] - in.nearPoint[normal_axis]);
+    let fragment_on_plane: vec3<f32> = in.nearPoint + t * (in.farPoint - in.nearPoint);
+    let fragment_on_plane_clip_space: vec4<f32> = 
+        unproject_uniform.view_projection_matrix * vec4<f32>(fragment_on_plane.xyz, 1.0);
+
+    let depth: f32 = fragment_on_plane_clip_space.z / fragment_on_plane_clip_space.w;
     output.fragDepth = depth;
-    let transformed_depth: f32 = 2. * depth - 1.; // between -1 and 1
-    let linear_depth: f32 = 
-        (2.0 * unproject_uniform.z_near * unproject_uniform.z_far) 
-        / (
-            unproject_uniform.z_far + unproject_uniform.z_near 
-            - transformed_depth * (unproject_uniform.z_far - unproject_uniform.z_near)
-        );
-    let normalized_linear_depth: f32 = linear_depth / unproject_uniform.z_far; 
-
-    let fading_scale: f32 = 5.; // higher -> more fading
-    let fading: f32 = max(0., (1. - fading_scale * normalized_linear_depth));
+
+    let distance_to_camera: f32 = distance(fragment_on_plane, unproject_uniform.camera_position.xyz);
+    let fading: f32 = max(0., 1. - distance_to_camera / unproject_uniform.fade_distance);
     
     var visibility: f32;
     if (t > 0.0) {
@@ -129,11 +153,11 @@ fn fs_main(in: VertexOut) -> FragmentShaderOutput {
     } else {
         visibility = 0.0;
     }
-    output.color = grid(fragment_on_xz_plane, 1.) * visibility;
+    let point_on_grid = vec2<f32>(fragment_on_plane[axis_u], fragment_on_plane[axis_v]);
+    output.color = grid(point_on_grid, fwidth(point_on_grid), axis_u, axis_v) * visibility;
     output.color.w = output.color.w * fading;
 
     return output;
 }
 
 // E==== FRAGMENT SHADER }}}1
-
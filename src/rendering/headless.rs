@@ -0,0 +1,251 @@
+//! Drawing scenes into images without a window or eframe, e.g. for thumbnails, documentation
+//! images and render regression tests. The scene goes through the same pipelines as in the
+//! viewport (see `RenderResources`), into a texture whose pixels are then copied back to the CPU.
+
+#[cfg(test)]
+use std::io::BufReader;
+use std::{fmt, fs::File, io::BufWriter, num::NonZeroU32, path::Path};
+
+use crate::{camera::Camera, math::vector::Vector2, scene::Scene};
+
+use super::{
+    drawing_stuff::DrawingStuff, overlays::OverlaySettings, render_resources::RenderResources,
+};
+
+/// What images are drawn as, which is also how they are written to PNGs.
+const TEXTURE_FORMAT: eframe::wgpu::TextureFormat = eframe::wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/// The images have an opaque background, since there is nothing behind them. This is the color
+/// of egui's dark panels, in linear space.
+const BACKGROUND: eframe::wgpu::Color = eframe::wgpu::Color {
+    r: 0.011,
+    g: 0.011,
+    b: 0.011,
+    a: 1.0,
+};
+
+#[derive(Debug)]
+pub enum HeadlessError {
+    /// There is no adapter at all, not even a software one.
+    NoAdapter,
+    RequestDevice(eframe::wgpu::RequestDeviceError),
+    /// The pixels couldn't be copied back from the GPU.
+    Readback(eframe::wgpu::BufferAsyncError),
+    Io(std::io::Error),
+    PngEncoding(png::EncodingError),
+    #[cfg(test)]
+    PngDecoding(png::DecodingError),
+    /// The PNG isn't 8 bit RGBA, as written by `RenderedImage::write_png()`.
+    #[cfg(test)]
+    UnsupportedPng,
+}
+
+impl fmt::Display for HeadlessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HeadlessError::NoAdapter => write!(f, "there is no adapter to render with"),
+            HeadlessError::RequestDevice(error) => write!(f, "couldn't get a device: {}", error),
+            HeadlessError::Readback(error) => {
+                write!(f, "couldn't copy the image back from the GPU: {}", error)
+            }
+            HeadlessError::Io(error) => write!(f, "{}", error),
+            HeadlessError::PngEncoding(error) => write!(f, "couldn't write the PNG: {}", error),
+            #[cfg(test)]
+            HeadlessError::PngDecoding(error) => write!(f, "couldn't read the PNG: {}", error),
+            #[cfg(test)]
+            HeadlessError::UnsupportedPng => write!(f, "the PNG isn't 8 bit RGBA"),
+        }
+    }
+}
+
+impl std::error::Error for HeadlessError {}
+
+/// The pixels of a drawn image, in RGBA with 8 bits per channel (sRGB), row by row from the top.
+pub struct RenderedImage {
+    pub size: [u32; 2],
+    pub pixels: Vec<u8>,
+}
+
+impl RenderedImage {
+    pub fn write_png(&self, path: &Path) -> Result<(), HeadlessError> {
+        let file = File::create(path).map_err(HeadlessError::Io)?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.size[0], self.size[1]);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_srgb(png::SrgbRenderingIntent::Perceptual);
+        let mut writer = encoder.write_header().map_err(HeadlessError::PngEncoding)?;
+        writer
+            .write_image_data(&self.pixels)
+            .map_err(HeadlessError::PngEncoding)
+    }
+
+    /// Only the render regression tests read images back.
+    #[cfg(test)]
+    pub fn read_png(path: &Path) -> Result<Self, HeadlessError> {
+        let file = File::open(path).map_err(HeadlessError::Io)?;
+        let mut reader = png::Decoder::new(BufReader::new(file))
+            .read_info()
+            .map_err(HeadlessError::PngDecoding)?;
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader
+            .next_frame(&mut pixels)
+            .map_err(HeadlessError::PngDecoding)?;
+        if info.color_type != png::ColorType::Rgba || info.bit_depth != png::BitDepth::Eight {
+            return Err(HeadlessError::UnsupportedPng);
+        }
+        pixels.truncate(info.buffer_size());
+
+        Ok(Self {
+            size: [info.width, info.height],
+            pixels,
+        })
+    }
+}
+
+pub struct HeadlessRenderer {
+    device: eframe::wgpu::Device,
+    queue: eframe::wgpu::Queue,
+    /// How the scene is drawn: the shading and sample count can be changed before rendering.
+    /// What is drawn and the uniforms are filled in by `render()`.
+    pub drawing_stuff: DrawingStuff,
+    /// Shown for the object in edit mode, if any.
+    pub overlays: OverlaySettings,
+    /// Created by the first render, and again whenever the sample count changes.
+    resources: Option<RenderResources>,
+}
+
+impl HeadlessRenderer {
+    /// Prefers wgpu's fallback adapter, which draws on the CPU, so that images come out the same
+    /// on machines without a GPU, such as CI runners. Any other adapter is used if there is none.
+    pub fn new() -> Result<Self, HeadlessError> {
+        let instance = eframe::wgpu::Instance::new(eframe::wgpu::InstanceDescriptor::default());
+        let adapter = [true, false]
+            .into_iter()
+            .find_map(|force_fallback_adapter| {
+                pollster::block_on(
+                    instance.request_adapter(&eframe::wgpu::RequestAdapterOptions {
+                        power_preference: eframe::wgpu::PowerPreference::default(),
+                        force_fallback_adapter,
+                        compatible_surface: None,
+                    }),
+                )
+            })
+            .ok_or(HeadlessError::NoAdapter)?;
+        log::info!("Rendering headless on {:?}", adapter.get_info());
+
+        // Needed for sample counts other than 1 and 4, like in `main.rs`.
+        let features =
+            adapter.features() & eframe::wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES;
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &eframe::wgpu::DeviceDescriptor {
+                label: Some("headless device"),
+                features,
+                limits:
+                    eframe::wgpu::Limits::downlevel_defaults().using_resolution(adapter.limits()),
+            },
+            None,
+        ))
+        .map_err(HeadlessError::RequestDevice)?;
+
+        let mut drawing_stuff = DrawingStuff::initialize(&Camera::initialize(1.0, 1.0));
+        drawing_stuff.background = BACKGROUND;
+
+        Ok(Self {
+            device,
+            queue,
+            drawing_stuff,
+            overlays: OverlaySettings::new(),
+            resources: None,
+        })
+    }
+
+    /// Draws the scene as seen by the camera into an image of the given size (in pixels). The
+    /// aspect ratio of the camera is changed to match it.
+    pub fn render(
+        &mut self,
+        scene: &mut Scene,
+        camera: &mut Camera,
+        size: [u32; 2],
+    ) -> Result<RenderedImage, HeadlessError> {
+        let (width, height) = (size[0] as f32, size[1] as f32);
+        camera.handle_window_resize(width, height);
+        self.drawing_stuff.drawing_region_size = Vector2::new(width, height);
+        self.drawing_stuff.pixels_per_point = 1.0;
+        self.drawing_stuff.refresh_from_scene(scene, &self.overlays);
+        self.drawing_stuff.update_uniforms(camera, &scene.grid);
+
+        let resources = self.resources.get_or_insert_with(|| {
+            RenderResources::create(&self.device, TEXTURE_FORMAT, &self.drawing_stuff)
+        });
+        let mut encoder =
+            self.device
+                .create_command_encoder(&eframe::wgpu::CommandEncoderDescriptor {
+                    label: Some("headless encoder"),
+                });
+        resources.prepare(
+            &self.device,
+            &self.queue,
+            &mut encoder,
+            self.drawing_stuff.clone(),
+        );
+
+        // Rows in the buffer need to be padded to a multiple of the alignment.
+        let bytes_per_row = 4 * size[0];
+        let alignment = eframe::wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row = bytes_per_row.div_ceil(alignment) * alignment;
+        let buffer = self.device.create_buffer(&eframe::wgpu::BufferDescriptor {
+            label: Some("headless readback buffer"),
+            size: (padded_bytes_per_row * size[1]) as eframe::wgpu::BufferAddress,
+            usage: eframe::wgpu::BufferUsages::COPY_DST | eframe::wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let target = resources.get_target().unwrap();
+        encoder.copy_texture_to_buffer(
+            target.resolved_texture.as_image_copy(),
+            eframe::wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: eframe::wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(padded_bytes_per_row),
+                    rows_per_image: None,
+                },
+            },
+            eframe::wgpu::Extent3d {
+                width: size[0],
+                height: size[1],
+                depth_or_array_layers: 1,
+            },
+        );
+        self.queue.submit(Some(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(eframe::wgpu::MapMode::Read, move |result| {
+            sender.send(result).unwrap();
+        });
+        self.device.poll(eframe::wgpu::Maintain::Wait);
+        receiver.recv().unwrap().map_err(HeadlessError::Readback)?;
+
+        let pixels = slice
+            .get_mapped_range()
+            .chunks(padded_bytes_per_row as usize)
+            .flat_map(|row| &row[..bytes_per_row as usize])
+            .copied()
+            .collect();
+        buffer.unmap();
+
+        Ok(RenderedImage { size, pixels })
+    }
+
+    /// Like `render()`, writing the image to a PNG file.
+    pub fn render_to_png(
+        &mut self,
+        scene: &mut Scene,
+        camera: &mut Camera,
+        size: [u32; 2],
+        path: &Path,
+    ) -> Result<(), HeadlessError> {
+        self.render(scene, camera, size)?.write_png(path)
+    }
+}
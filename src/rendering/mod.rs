@@ -1,7 +1,13 @@
 mod depth_texture;
 mod draw_command;
 pub mod drawing_stuff;
-mod grid;
+#[cfg(test)]
+mod golden_tests;
+pub mod grid;
+pub mod headless;
+pub mod overlays;
 pub mod render_resources;
-mod viewport;
+pub mod viewport;
+pub mod viewport_target;
 mod wgpu_mesh;
+pub mod wireframe;
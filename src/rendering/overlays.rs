@@ -0,0 +1,392 @@
+//! Overlays drawn over the mesh being edited so that its individual elements can be seen:
+//! vertices as small squares, dots at the center of the faces, and lines along the normals.
+//!
+//! Everything is a quad with its corners pushed out in screen space, like the edges (see
+//! `edges.rs`): points around a single position, lines sideways from the line between two.
+
+use std::rc::Rc;
+
+use eframe::wgpu::util::DeviceExt;
+
+use crate::{
+    bmesh::bmesh::BMesh,
+    camera::Camera,
+    math::{point::Point3, vector::Vector2},
+    scene::SelectMode,
+};
+
+use super::{depth_texture::DepthTexture, viewport_target::ViewportTarget};
+
+/// Sizes in pixels.
+const VERTEX_SIZE: f32 = 6.0;
+const FACE_DOT_SIZE: f32 = 4.0;
+const NORMAL_WIDTH: f32 = 1.5;
+
+const UNSELECTED_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+const SELECTED_COLOR: [f32; 4] = [1.0, 0.55, 0.1, 1.0];
+const ACTIVE_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const FACE_NORMAL_COLOR: [f32; 4] = [0.2, 0.8, 1.0, 1.0];
+const VERTEX_NORMAL_COLOR: [f32; 4] = [0.5, 0.4, 1.0, 1.0];
+
+/// Same as for the edges (see `wireframe.rs`), so the overlays aren't hidden by the faces they
+/// are on.
+const DEPTH_BIAS: f32 = 0.002;
+
+/// Which of the optional overlays are shown.
+#[derive(Clone, Copy)]
+pub struct OverlaySettings {
+    pub face_normals: bool,
+    pub vertex_normals: bool,
+    /// In world units.
+    pub normal_length: f32,
+}
+
+impl OverlaySettings {
+    pub fn new() -> Self {
+        Self {
+            face_normals: false,
+            vertex_normals: false,
+            normal_length: 0.2,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct OverlayVertex {
+    /// The line the vertex belongs to. Both ends are the same for points.
+    start: [f32; 3],
+    end: [f32; 3],
+    /// 0 for a vertex at the start of the line, 1 for one at the end.
+    at_end: f32,
+    /// How far the vertex is pushed out on the screen, in pixels: x across the line, y along
+    /// it. Points have no direction, so for them it is x up and y right.
+    offset: [f32; 2],
+    color: [f32; 4],
+}
+
+impl OverlayVertex {
+    pub fn get_descriptor<'a>() -> eframe::wgpu::VertexBufferLayout<'a> {
+        const ATTRIBUTES: [eframe::wgpu::VertexAttribute; 5] = eframe::wgpu::vertex_attr_array![
+            0 => Float32x3,
+            1 => Float32x3,
+            2 => Float32,
+            3 => Float32x2,
+            4 => Float32x4,
+        ];
+
+        eframe::wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<OverlayVertex>() as eframe::wgpu::BufferAddress,
+            step_mode: eframe::wgpu::VertexStepMode::Vertex,
+            attributes: &ATTRIBUTES,
+        }
+    }
+}
+
+pub struct OverlayMesh {
+    pub vertices: Vec<OverlayVertex>,
+    pub indices: Vec<u32>,
+}
+
+impl OverlayMesh {
+    /// The overlays for the mesh being edited. Vertices are always shown, face dots only when
+    /// selecting faces, and the normals if turned on in the settings.
+    pub fn from_bmesh(bmesh: &BMesh, select_mode: SelectMode, settings: &OverlaySettings) -> Self {
+        let mut mesh = Self {
+            vertices: Vec::new(),
+            indices: Vec::new(),
+        };
+
+        let active_vertex = bmesh.get_active_vertex();
+        let active_face = bmesh.get_active_face();
+
+        let face_centers: Vec<Point3> = bmesh
+            .get_faces()
+            .iter()
+            .map(|face| {
+                let positions: Vec<Point3> = face
+                    .as_ref()
+                    .borrow()
+                    .get_vertices()
+                    .iter()
+                    .map(|v| v.as_ref().borrow().get_position())
+                    .collect();
+                Point3::centroid(&positions)
+            })
+            .collect();
+        let face_selected: Vec<bool> = bmesh
+            .get_faces()
+            .iter()
+            .map(|face| {
+                face.as_ref()
+                    .borrow()
+                    .get_vertices()
+                    .iter()
+                    .all(|v| v.as_ref().borrow().is_selected())
+            })
+            .collect();
+
+        if settings.face_normals {
+            for ((normal, center), selected) in bmesh
+                .get_face_normals()
+                .iter()
+                .zip(&face_centers)
+                .zip(&face_selected)
+            {
+                let end = *center + *normal * settings.normal_length;
+                mesh.add_line(center, &end, tint(FACE_NORMAL_COLOR, *selected));
+            }
+        }
+
+        if settings.vertex_normals {
+            for vertex in bmesh.get_vertices() {
+                let vertex = vertex.as_ref().borrow();
+                if let Some(normal) = bmesh.get_vertex_normal(vertex.get_id()) {
+                    let start = vertex.get_position();
+                    let end = start + normal * settings.normal_length;
+                    let color = tint(VERTEX_NORMAL_COLOR, vertex.is_selected());
+                    mesh.add_line(&start, &end, color);
+                }
+            }
+        }
+
+        // Points after the lines, so that they are drawn on top of the ends of the normals.
+        if select_mode == SelectMode::Face {
+            for (i, face) in bmesh.get_faces().iter().enumerate() {
+                let is_active = active_face.as_ref().is_some_and(|f| Rc::ptr_eq(f, face));
+                let color = element_color(face_selected[i], is_active);
+                mesh.add_point(&face_centers[i], FACE_DOT_SIZE, color);
+            }
+        }
+
+        for vertex in bmesh.get_vertices() {
+            let is_active = active_vertex
+                .as_ref()
+                .is_some_and(|v| Rc::ptr_eq(v, vertex));
+            let vertex = vertex.as_ref().borrow();
+            let color = element_color(vertex.is_selected(), is_active);
+            mesh.add_point(&vertex.get_position(), VERTEX_SIZE, color);
+        }
+
+        mesh
+    }
+
+    fn add_point(&mut self, position: &Point3, size: f32, color: [f32; 4]) {
+        let p: [f32; 3] = (*position).into();
+        // Half a pixel more on each side for the anti-aliased falloff.
+        let half = size * 0.5 + 0.5;
+        self.add_quad(
+            [[-half, -half], [-half, half], [half, -half], [half, half]].map(|offset| {
+                OverlayVertex {
+                    start: p,
+                    end: p,
+                    at_end: 0.0,
+                    offset,
+                    color,
+                }
+            }),
+        );
+    }
+
+    fn add_line(&mut self, start: &Point3, end: &Point3, color: [f32; 4]) {
+        let (start, end): ([f32; 3], [f32; 3]) = ((*start).into(), (*end).into());
+        let half = NORMAL_WIDTH * 0.5 + 0.5;
+        self.add_quad([(0.0, half), (0.0, -half), (1.0, half), (1.0, -half)].map(
+            |(at_end, side)| OverlayVertex {
+                start,
+                end,
+                at_end,
+                offset: [side, 0.0],
+                color,
+            },
+        ));
+    }
+
+    /// The corners go start left, start right, end left, end right.
+    fn add_quad(&mut self, corners: [OverlayVertex; 4]) {
+        let index_offset = self.vertices.len() as u32;
+        self.vertices.extend_from_slice(&corners);
+        self.indices
+            .extend([0, 1, 2, 1, 3, 2].iter().map(|i| i + index_offset));
+    }
+}
+
+fn element_color(selected: bool, active: bool) -> [f32; 4] {
+    if active {
+        ACTIVE_COLOR
+    } else if selected {
+        SELECTED_COLOR
+    } else {
+        UNSELECTED_COLOR
+    }
+}
+
+/// Brings the color halfway to the selection color if the element is selected.
+fn tint(color: [f32; 4], selected: bool) -> [f32; 4] {
+    if !selected {
+        return color;
+    }
+    let mut tinted = color;
+    for (c, s) in tinted.iter_mut().zip(SELECTED_COLOR) {
+        *c = (*c + s) * 0.5;
+    }
+    tinted
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct OverlayUniform {
+    view_projection_matrix: [[f32; 4]; 4],
+    /// The w is unused.
+    camera_position: [f32; 4],
+    /// In pixels, to go between pixels and normalized device coordinates.
+    viewport_size: [f32; 2],
+    depth_bias: f32,
+    _padding: f32,
+}
+
+impl OverlayUniform {
+    pub fn new(camera: &Camera) -> Self {
+        let mut uniform = Self {
+            view_projection_matrix: [[0.0; 4]; 4],
+            camera_position: [0.0; 4],
+            viewport_size: [1.0, 1.0],
+            depth_bias: DEPTH_BIAS,
+            _padding: 0.0,
+        };
+        uniform.update(camera, &Vector2::new(1.0, 1.0));
+        uniform
+    }
+
+    pub fn update(&mut self, camera: &Camera, viewport_size: &Vector2) {
+        self.view_projection_matrix = camera.get_view_projection_matrix().into();
+        let position = camera.get_position();
+        self.camera_position = [position.x(), position.y(), position.z(), 1.0];
+        self.viewport_size = [viewport_size.x(), viewport_size.y()];
+    }
+}
+
+pub(super) struct WgpuOverlayMesh {
+    pub vertex_buffer: eframe::wgpu::Buffer,
+    pub index_buffer: eframe::wgpu::Buffer,
+    pub num_indices: u32,
+}
+
+impl WgpuOverlayMesh {
+    pub fn from_overlay_mesh(device: &eframe::wgpu::Device, mesh: &OverlayMesh) -> Self {
+        let vertex_buffer = device.create_buffer_init(&eframe::wgpu::util::BufferInitDescriptor {
+            label: Some("overlay vertex buffer"),
+            contents: bytemuck::cast_slice(mesh.vertices.as_slice()),
+            usage: eframe::wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&eframe::wgpu::util::BufferInitDescriptor {
+            label: Some("overlay index buffer"),
+            contents: bytemuck::cast_slice(mesh.indices.as_slice()),
+            usage: eframe::wgpu::BufferUsages::INDEX,
+        });
+
+        Self {
+            vertex_buffer,
+            index_buffer,
+            num_indices: mesh.indices.len() as u32,
+        }
+    }
+}
+
+pub(super) struct OverlayRenderResources {
+    pub pipeline: eframe::wgpu::RenderPipeline,
+    pub bind_group: eframe::wgpu::BindGroup,
+    pub buffer: eframe::wgpu::Buffer,
+}
+
+impl OverlayRenderResources {
+    pub fn initialize(
+        device: &eframe::wgpu::Device,
+        surface_format: eframe::wgpu::TextureFormat,
+        sample_count: u32,
+        uniform: &OverlayUniform,
+    ) -> Self {
+        let buffer = device.create_buffer_init(&eframe::wgpu::util::BufferInitDescriptor {
+            label: Some("overlay buffer"),
+            contents: bytemuck::cast_slice(&[*uniform]),
+            usage: eframe::wgpu::BufferUsages::UNIFORM | eframe::wgpu::BufferUsages::COPY_DST,
+        });
+
+        let shader = device.create_shader_module(eframe::wgpu::ShaderModuleDescriptor {
+            label: Some("overlay shader"),
+            source: eframe::wgpu::ShaderSource::Wgsl(include_str!("overlays.wgsl").into()),
+        });
+
+        let bind_group_layout =
+            device.create_bind_group_layout(&eframe::wgpu::BindGroupLayoutDescriptor {
+                entries: &[eframe::wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: eframe::wgpu::ShaderStages::VERTEX,
+                    ty: eframe::wgpu::BindingType::Buffer {
+                        ty: eframe::wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+                label: Some("overlay bind group layout"),
+            });
+        let bind_group = device.create_bind_group(&eframe::wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[eframe::wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+            label: Some("overlay bind group"),
+        });
+
+        let pipeline_layout =
+            device.create_pipeline_layout(&eframe::wgpu::PipelineLayoutDescriptor {
+                label: Some("overlay render pipeline layout"),
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        let pipeline = device.create_render_pipeline(&eframe::wgpu::RenderPipelineDescriptor {
+            label: Some("overlay render pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: eframe::wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[OverlayVertex::get_descriptor()],
+            },
+            fragment: Some(eframe::wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(eframe::wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(eframe::wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: eframe::wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: eframe::wgpu::PrimitiveState {
+                topology: eframe::wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: eframe::wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: eframe::wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            // The faded sides shouldn't hide what is behind them. The points come after the lines
+            // in the mesh, so they are drawn over them.
+            depth_stencil: Some(eframe::wgpu::DepthStencilState {
+                depth_write_enabled: false,
+                ..DepthTexture::create_depth_stencil_state()
+            }),
+            multisample: ViewportTarget::create_multisample_state(sample_count),
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            bind_group,
+            buffer,
+        }
+    }
+}
@@ -0,0 +1,93 @@
+// Vertex shader
+
+struct OverlayUniform {
+    view_projection_matrix: mat4x4<f32>,
+    // The w is unused.
+    camera_position: vec4<f32>,
+    viewport_size: vec2<f32>,
+    depth_bias: f32,
+    padding: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> overlay: OverlayUniform;
+
+struct VertexInput {
+    @location(0) start: vec3<f32>,
+    @location(1) end: vec3<f32>,
+    @location(2) at_end: f32,
+    @location(3) offset: vec2<f32>,
+    @location(4) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // Where the fragment is relative to the middle of the point or line, in pixels.
+    @location(0) distance: vec2<f32>,
+    // How far out the quad goes, in pixels, the same for all of its corners.
+    @location(1) extent: vec2<f32>,
+    @location(2) color: vec4<f32>,
+};
+
+// Anything closer to the camera plane than this is treated as behind the camera.
+const NEAR_W: f32 = 0.0001;
+
+// See wireframe.wgsl.
+fn clip_to_near(p: vec4<f32>, other: vec4<f32>) -> vec4<f32> {
+    if (p.w >= NEAR_W) {
+        return p;
+    }
+    let t = (NEAR_W - p.w) / (other.w - p.w);
+    return mix(p, other, t);
+}
+
+// See wireframe.wgsl.
+fn towards_camera(p: vec3<f32>) -> vec4<f32> {
+    let biased = mix(p, overlay.camera_position.xyz, overlay.depth_bias);
+    return overlay.view_projection_matrix * vec4<f32>(biased, 1.0);
+}
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.color = in.color;
+    out.distance = in.offset;
+    // Lines have no offset along them, which shouldn't fade them out.
+    out.extent = max(abs(in.offset), vec2<f32>(1.0, 1.0));
+
+    let start = towards_camera(in.start);
+    let end = towards_camera(in.end);
+    if (start.w < NEAR_W && end.w < NEAR_W) {
+        // Entirely behind the camera; put it outside of the clip volume.
+        out.clip_position = vec4<f32>(2.0, 2.0, 2.0, 1.0);
+        return out;
+    }
+    let clipped_start = clip_to_near(start, end);
+    let clipped_end = clip_to_near(end, start);
+
+    let half_size = overlay.viewport_size * 0.5;
+    let start_on_screen = clipped_start.xy / clipped_start.w * half_size;
+    let end_on_screen = clipped_end.xy / clipped_end.w * half_size;
+    var direction = vec2<f32>(1.0, 0.0);
+    if (distance(start_on_screen, end_on_screen) > 0.0001) {
+        direction = normalize(end_on_screen - start_on_screen);
+    }
+    let normal = vec2<f32>(-direction.y, direction.x);
+
+    let clip_position = mix(clipped_start, clipped_end, in.at_end);
+    let offset = (normal * in.offset.x + direction * in.offset.y) / half_size;
+    out.clip_position = vec4<f32>(
+        clip_position.xy + offset * clip_position.w,
+        clip_position.zw,
+    );
+
+    return out;
+}
+
+// Fragment shader
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let coverage = clamp(in.extent - abs(in.distance), vec2<f32>(0.0), vec2<f32>(1.0));
+    return vec4<f32>(in.color.rgb, in.color.a * coverage.x * coverage.y);
+}
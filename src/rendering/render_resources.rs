@@ -1,41 +1,106 @@
-use crate::{camera::Camera, vertex::Vertex};
+use crate::vertex::{Instance, Vertex};
 
 use super::{
     depth_texture::DepthTexture,
     draw_command::{DrawCommand, DrawCommandKind},
     drawing_stuff::DrawingStuff,
     grid::{GridRenderResources, GridRenderResourcesInitializeArgs},
-    viewport::{ViewportRenderResources, ViewportUniform},
+    overlays::{OverlayRenderResources, WgpuOverlayMesh},
+    viewport::{ViewportRenderResources, ViewportShading},
+    viewport_target::{BlitRenderResources, ViewportTarget},
+    wireframe::{WgpuEdgeMesh, WireframeRenderResources},
 };
 
-/// These are the things that eframe will give us an *immutable* reference to each frame. The only things
-/// that should go here are those whose lifetime needs to be the same as the egui render pass.
+/// These are the things that eframe will give us an *immutable* reference to each frame (through
+/// `RenderResourcesPerViewport`), for a single viewport. The only things that should go here are
+/// those whose lifetime needs to be the same as the egui render pass.
 pub struct RenderResources {
     render_pipeline: eframe::wgpu::RenderPipeline,
+    /// Like `render_pipeline`, but shading each triangle with a single normal.
+    flat_render_pipeline: eframe::wgpu::RenderPipeline,
+    /// Like the two above, but blending the faces over what is behind them (see
+    /// `ViewportShading::x_ray`).
+    x_ray_render_pipeline: eframe::wgpu::RenderPipeline,
+    x_ray_flat_render_pipeline: eframe::wgpu::RenderPipeline,
     camera_info: ViewportRenderResources,
     grid: GridRenderResources,
+    wireframe: WireframeRenderResources,
+    overlay: OverlayRenderResources,
+    blit: BlitRenderResources,
     draw_commands: Vec<DrawCommand>,
-    depth_texture: DepthTexture,
+    edges: Option<WgpuEdgeMesh>,
+    overlay_mesh: Option<WgpuOverlayMesh>,
+    shading: ViewportShading,
+    surface_format: eframe::wgpu::TextureFormat,
+    /// What the pipelines above were created for. Changing it means creating them again.
+    sample_count: u32,
+    /// Created once the size of the viewport is known, and again whenever it changes.
+    target: Option<ViewportTarget>,
     // TODO: store vertex/index buffers for reuse
 }
 
-impl RenderResources {
-    /// Creates all the render resources and gives them to eframe.
-    ///
-    /// - `camera`: needed to initialize the grid uniform buffer (TODO: pass grid_uniform)
-    /// - `camera_uniform`: needed so that we can initialize the corresponding uniform buffer.
-    pub fn initialize<'a>(
-        eframe_creation_context: &'a eframe::CreationContext<'a>,
-        camera: &Camera,
-        camera_uniform: &ViewportUniform,
-    ) {
+/// What eframe keeps for us between frames: the render resources of each viewport, created the
+/// first time it is drawn. Each viewport has pipelines of its own as well as buffers, which keeps
+/// them independent at the cost of some memory.
+pub struct RenderResourcesPerViewport {
+    surface_format: eframe::wgpu::TextureFormat,
+    viewports: Vec<RenderResources>,
+}
+
+impl RenderResourcesPerViewport {
+    /// Gives eframe somewhere to keep the render resources.
+    pub fn initialize<'a>(eframe_creation_context: &'a eframe::CreationContext<'a>) {
         // Get the WGPU render state from the eframe creation context. This can also be retrieved
         // from `eframe::Frame` when you don't have a `CreationContext` available.
         let wgpu_render_state = eframe_creation_context.wgpu_render_state.as_ref().unwrap();
 
-        let device = &wgpu_render_state.device;
-        let window_size = eframe_creation_context.integration_info.window_info.size;
-        let surface_format = wgpu_render_state.target_format;
+        let resources = Self {
+            surface_format: wgpu_render_state.target_format,
+            viewports: Vec::new(),
+        };
+
+        // Because the graphics pipeline must have the same lifetime as the egui render pass,
+        // instead of storing the pipeline in our `Custom3D` struct, we insert it into the
+        // `paint_callback_resources` type map, which is stored alongside the render pass.
+        wgpu_render_state
+            .renderer
+            .write()
+            .paint_callback_resources
+            .insert(resources);
+    }
+
+    /// See `RenderResources::prepare()`. `index` is that of the viewport.
+    pub fn prepare(
+        &mut self,
+        index: usize,
+        device: &eframe::wgpu::Device,
+        queue: &eframe::wgpu::Queue,
+        encoder: &mut eframe::wgpu::CommandEncoder,
+        drawing_stuff: DrawingStuff,
+    ) {
+        while self.viewports.len() <= index {
+            let resources = RenderResources::create(device, self.surface_format, &drawing_stuff);
+            self.viewports.push(resources);
+        }
+        self.viewports[index].prepare(device, queue, encoder, drawing_stuff);
+    }
+
+    /// See `RenderResources::paint()`.
+    pub fn paint<'rp>(&'rp self, index: usize, render_pass: &mut eframe::wgpu::RenderPass<'rp>) {
+        if let Some(resources) = self.viewports.get(index) {
+            resources.paint(render_pass);
+        }
+    }
+}
+
+impl RenderResources {
+    /// Creates the render resources for drawing into textures of the given format.
+    pub(super) fn create(
+        device: &eframe::wgpu::Device,
+        surface_format: eframe::wgpu::TextureFormat,
+        drawing_stuff: &DrawingStuff,
+    ) -> Self {
+        let sample_count = drawing_stuff.sample_count;
 
         // Render pipeline
         let shader = device.create_shader_module(eframe::wgpu::ShaderModuleDescriptor {
@@ -44,10 +109,8 @@ impl RenderResources {
         });
 
         // Camera
-        let camera_info = ViewportRenderResources::initialize(&device, camera_uniform);
-
-        // Depth buffer
-        let depth_texture = DepthTexture::new(&device, &window_size);
+        let camera_info =
+            ViewportRenderResources::initialize(device, &drawing_stuff.camera_uniform);
 
         // Main render pipeline
         let render_pipeline_layout =
@@ -57,75 +120,138 @@ impl RenderResources {
                 push_constant_ranges: &[],
             });
 
-        let render_pipeline =
-            device.create_render_pipeline(&eframe::wgpu::RenderPipelineDescriptor {
-                label: Some("Render Pipeline"),
-                layout: Some(&render_pipeline_layout),
-                vertex: eframe::wgpu::VertexState {
-                    module: &shader,
-                    entry_point: "vs_main",
-                    buffers: &[Vertex::get_descriptor()],
-                },
-                fragment: Some(eframe::wgpu::FragmentState {
-                    // 3.
-                    module: &shader,
-                    entry_point: "fs_main",
-                    targets: &[Some(eframe::wgpu::ColorTargetState {
-                        // 4.
-                        format: surface_format, //surface_config.format,
-                        blend: Some(eframe::wgpu::BlendState::REPLACE),
-                        write_mask: eframe::wgpu::ColorWrites::ALL,
-                    })],
-                }),
-                primitive: eframe::wgpu::PrimitiveState {
-                    topology: eframe::wgpu::PrimitiveTopology::TriangleList, // 1.
-                    strip_index_format: None,
-                    front_face: eframe::wgpu::FrontFace::Ccw, // 2.
-                    cull_mode: Some(eframe::wgpu::Face::Back),
-                    // Setting this to anything other than Fill requires Features::NON_FILL_POLYGON_MODE
-                    polygon_mode: eframe::wgpu::PolygonMode::Fill,
-                    // Requires Features::DEPTH_CLIP_CONTROL
-                    unclipped_depth: false,
-                    // Requires Features::CONSERVATIVE_RASTERIZATION
-                    conservative: false,
-                },
-                depth_stencil: Some(DepthTexture::create_depth_stencil_state()),
-                multisample: eframe::wgpu::MultisampleState::default(),
-                multiview: None, // 5.
-            });
+        let create_shape_pipeline = |fragment_entry_point, x_ray| {
+            Self::create_shape_pipeline(
+                device,
+                &render_pipeline_layout,
+                &shader,
+                fragment_entry_point,
+                x_ray,
+                surface_format,
+                sample_count,
+            )
+        };
+        let render_pipeline = create_shape_pipeline("fs_main", false);
+        let flat_render_pipeline = create_shape_pipeline("fs_flat", false);
+        let x_ray_render_pipeline = create_shape_pipeline("fs_main", true);
+        let x_ray_flat_render_pipeline = create_shape_pipeline("fs_flat", true);
 
         let grid = GridRenderResources::initialize(GridRenderResourcesInitializeArgs {
-            device: &device,
-            surface_format: surface_format.clone(),
-            view_projection_matrix: camera.get_view_projection_matrix(),
-            camera: &camera,
+            device,
+            surface_format,
+            sample_count,
+            uniform: &drawing_stuff.grid_uniform,
         });
 
-        // Because the graphics pipeline must have the same lifetime as the egui render pass,
-        // instead of storing the pipeline in our `Custom3D` struct, we insert it into the
-        // `paint_callback_resources` type map, which is stored alongside the render pass.
-        wgpu_render_state
-            .renderer
-            .write()
-            .paint_callback_resources
-            .insert(RenderResources {
-                render_pipeline,
-                camera_info,
-                grid,
-                draw_commands: Vec::new(),
-                depth_texture,
-            });
+        let wireframe = WireframeRenderResources::initialize(
+            device,
+            surface_format,
+            sample_count,
+            &drawing_stuff.wireframe_uniform,
+        );
+        let overlay = OverlayRenderResources::initialize(
+            device,
+            surface_format,
+            sample_count,
+            &drawing_stuff.overlay_uniform,
+        );
+        let blit = BlitRenderResources::initialize(device, surface_format);
+
+        Self {
+            render_pipeline,
+            flat_render_pipeline,
+            x_ray_render_pipeline,
+            x_ray_flat_render_pipeline,
+            camera_info,
+            grid,
+            wireframe,
+            overlay,
+            blit,
+            draw_commands: Vec::new(),
+            edges: None,
+            overlay_mesh: None,
+            shading: drawing_stuff.shading,
+            surface_format,
+            sample_count,
+            target: None,
+        }
+    }
+
+    /// The pipeline for the faces of the meshes, which only differ in how the fragments are
+    /// shaded, and in whether they are see-through. See-through faces are blended over what is
+    /// behind them and don't hide anything (their depth isn't written), so they need to be drawn
+    /// back to front. Their back faces are drawn too.
+    fn create_shape_pipeline(
+        device: &eframe::wgpu::Device,
+        layout: &eframe::wgpu::PipelineLayout,
+        shader: &eframe::wgpu::ShaderModule,
+        fragment_entry_point: &str,
+        x_ray: bool,
+        surface_format: eframe::wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> eframe::wgpu::RenderPipeline {
+        let (blend, cull_mode) = if x_ray {
+            (eframe::wgpu::BlendState::ALPHA_BLENDING, None)
+        } else {
+            (
+                eframe::wgpu::BlendState::REPLACE,
+                Some(eframe::wgpu::Face::Back),
+            )
+        };
+
+        device.create_render_pipeline(&eframe::wgpu::RenderPipelineDescriptor {
+            label: Some(fragment_entry_point),
+            layout: Some(layout),
+            vertex: eframe::wgpu::VertexState {
+                module: shader,
+                entry_point: "vs_main",
+                buffers: &[Vertex::get_descriptor(), Instance::get_descriptor()],
+            },
+            fragment: Some(eframe::wgpu::FragmentState {
+                module: shader,
+                entry_point: fragment_entry_point,
+                targets: &[Some(eframe::wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(blend),
+                    write_mask: eframe::wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: eframe::wgpu::PrimitiveState {
+                topology: eframe::wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: eframe::wgpu::FrontFace::Ccw,
+                cull_mode,
+                // Setting this to anything other than Fill requires Features::NON_FILL_POLYGON_MODE
+                polygon_mode: eframe::wgpu::PolygonMode::Fill,
+                // Requires Features::DEPTH_CLIP_CONTROL
+                unclipped_depth: false,
+                // Requires Features::CONSERVATIVE_RASTERIZATION
+                conservative: false,
+            },
+            depth_stencil: Some(eframe::wgpu::DepthStencilState {
+                depth_write_enabled: !x_ray,
+                ..DepthTexture::create_depth_stencil_state()
+            }),
+            multisample: ViewportTarget::create_multisample_state(sample_count),
+            multiview: None,
+        })
     }
 
     /// The app calls this function after eframe gives it an immutable reference to this struct (it does
     /// this every frame). This is called before painting/drawing, and is supposed to be for updating
-    /// things such as buffers and uniforms before rendering.
+    /// things such as buffers and uniforms before rendering. The scene is drawn here too, into the
+    /// viewport texture, since that needs a render pass of its own.
     pub fn prepare(
         &mut self,
         device: &eframe::wgpu::Device,
         queue: &eframe::wgpu::Queue,
+        encoder: &mut eframe::wgpu::CommandEncoder,
         drawing_stuff: DrawingStuff,
     ) {
+        if drawing_stuff.sample_count != self.sample_count {
+            *self = Self::create(device, self.surface_format, &drawing_stuff);
+        }
+
         // Update the uniform with the view projection matrix. The CPU-side version (`CameraUniform`)
         // is assumed to already have been updated. We just need to copy that into the GPU uniform.
         queue.write_buffer(
@@ -142,38 +268,152 @@ impl RenderResources {
             bytemuck::cast_slice(&[drawing_stuff.grid_uniform]),
         );
 
+        queue.write_buffer(
+            &self.wireframe.buffer,
+            0,
+            bytemuck::cast_slice(&[drawing_stuff.wireframe_uniform]),
+        );
+        queue.write_buffer(
+            &self.overlay.buffer,
+            0,
+            bytemuck::cast_slice(&[drawing_stuff.overlay_uniform]),
+        );
+        self.shading = drawing_stuff.shading;
+
+        // The draw list is sent over every frame, so the commands from last frame are stale.
+        self.draw_commands.clear();
         if drawing_stuff.meshes_to_draw.len() > 0 {
-            let draw_command = DrawCommand::from_meshes(device, &drawing_stuff.meshes_to_draw);
+            let sort_from = self
+                .shading
+                .x_ray
+                .then(|| drawing_stuff.camera_uniform.get_camera_position());
+            let draw_command =
+                DrawCommand::from_meshes(device, &drawing_stuff.meshes_to_draw, sort_from);
             self.draw_commands.push(draw_command);
         }
 
-        // TODO
-        // if renderer.drawing_region_resized {
-        //     renderer.depth_texture = DepthTexture::new(device, renderer.drawing_region_size);
-        // }
+        let mut edges_to_draw = if self.shading.mode.draws_edges() {
+            drawing_stuff.edges_to_draw
+        } else {
+            drawing_stuff.edited_edges.into_iter().collect()
+        };
+        edges_to_draw.extend(drawing_stuff.gizmo_edges);
+        self.edges = (!edges_to_draw.is_empty())
+            .then(|| WgpuEdgeMesh::from_edge_meshes(device, &edges_to_draw));
+        self.overlay_mesh = drawing_stuff
+            .overlay_to_draw
+            .as_ref()
+            .map(|mesh| WgpuOverlayMesh::from_overlay_mesh(device, mesh));
+
+        let region_size = drawing_stuff.drawing_region_size;
+        let size = [
+            (region_size.x() * drawing_stuff.pixels_per_point)
+                .round()
+                .max(1.0) as u32,
+            (region_size.y() * drawing_stuff.pixels_per_point)
+                .round()
+                .max(1.0) as u32,
+        ];
+        let target_is_stale = self
+            .target
+            .as_ref()
+            .is_none_or(|target| target.size != size || target.sample_count != self.sample_count);
+        if target_is_stale {
+            self.target = Some(ViewportTarget::new(
+                device,
+                self.surface_format,
+                size,
+                self.sample_count,
+                &self.blit,
+            ));
+        }
+
+        let target = self.target.as_ref().unwrap();
+        let mut render_pass = encoder.begin_render_pass(&eframe::wgpu::RenderPassDescriptor {
+            label: Some("viewport render pass"),
+            color_attachments: &[Some(
+                target.create_color_attachment(drawing_stuff.background),
+            )],
+            depth_stencil_attachment: Some(target.depth_texture.create_depth_stencil_attachment()),
+        });
+        self.draw_scene(&mut render_pass);
+    }
+
+    /// What `prepare()` last drew into, if it was called.
+    pub(super) fn get_target(&self) -> Option<&ViewportTarget> {
+        self.target.as_ref()
     }
 
-    /// This is called after `prepare()` when eframe gives us the render pass. This is where we do the
-    /// draw calls.
+    /// This is called after `prepare()` when eframe gives us the render pass. The scene has
+    /// already been drawn by then, so this only copies it over.
     pub fn paint<'rp>(&'rp self, render_pass: &mut eframe::wgpu::RenderPass<'rp>) {
+        if let Some(target) = &self.target {
+            render_pass.set_pipeline(&self.blit.pipeline);
+            render_pass.set_bind_group(0, &target.blit_bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+    }
+
+    /// This is where we do the draw calls.
+    fn draw_scene<'rp>(&'rp self, render_pass: &mut eframe::wgpu::RenderPass<'rp>) {
         // SHAPES : i.e. everything in the draw list maintained by the app
-        render_pass.set_pipeline(&self.render_pipeline);
-        render_pass.set_bind_group(0, &self.camera_info.bind_group, &[]);
+        if self.shading.mode.draws_faces() {
+            let pipeline = match (self.shading.x_ray, self.shading.smooth) {
+                (false, true) => &self.render_pipeline,
+                (false, false) => &self.flat_render_pipeline,
+                (true, true) => &self.x_ray_render_pipeline,
+                (true, false) => &self.x_ray_flat_render_pipeline,
+            };
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(0, &self.camera_info.bind_group, &[]);
 
-        for command in &self.draw_commands {
-            render_pass.set_vertex_buffer(0, command.wgpu_mesh.vertex_buffer.slice(..));
-            render_pass.set_index_buffer(
-                command.wgpu_mesh.index_buffer.slice(..),
-                eframe::wgpu::IndexFormat::Uint16,
-            );
+            for command in &self.draw_commands {
+                render_pass.set_vertex_buffer(0, command.wgpu_mesh.vertex_buffer.slice(..));
+                render_pass.set_vertex_buffer(1, command.wgpu_mesh.instance_buffer.slice(..));
+                render_pass.set_index_buffer(
+                    command.wgpu_mesh.index_buffer.slice(..),
+                    eframe::wgpu::IndexFormat::Uint32,
+                );
 
-            match command.kind {
-                DrawCommandKind::DrawIndexedAll => {
-                    render_pass.draw_indexed(0..command.wgpu_mesh.num_indices, 0, 0..1);
+                match command.kind {
+                    DrawCommandKind::DrawIndexedAll => {
+                        for (i, index_range) in command.wgpu_mesh.index_ranges.iter().enumerate() {
+                            let instance = i as u32;
+                            render_pass.draw_indexed(
+                                index_range.clone(),
+                                0,
+                                instance..instance + 1,
+                            );
+                        }
+                    }
                 }
             }
         }
 
+        // EDGES : before the grid, which would otherwise hide the edges below it
+        if let Some(edges) = &self.edges {
+            render_pass.set_pipeline(&self.wireframe.pipeline);
+            render_pass.set_bind_group(0, &self.wireframe.bind_group, &[]);
+            render_pass.set_vertex_buffer(0, edges.vertex_buffer.slice(..));
+            render_pass.set_index_buffer(
+                edges.index_buffer.slice(..),
+                eframe::wgpu::IndexFormat::Uint32,
+            );
+            render_pass.draw_indexed(0..edges.num_indices, 0, 0..1);
+        }
+
+        // OVERLAYS : the elements of the mesh being edited, over its faces and edges
+        if let Some(overlay_mesh) = &self.overlay_mesh {
+            render_pass.set_pipeline(&self.overlay.pipeline);
+            render_pass.set_bind_group(0, &self.overlay.bind_group, &[]);
+            render_pass.set_vertex_buffer(0, overlay_mesh.vertex_buffer.slice(..));
+            render_pass.set_index_buffer(
+                overlay_mesh.index_buffer.slice(..),
+                eframe::wgpu::IndexFormat::Uint32,
+            );
+            render_pass.draw_indexed(0..overlay_mesh.num_indices, 0, 0..1);
+        }
+
         // GRID
         render_pass.set_pipeline(&self.grid.pipeline);
         render_pass.set_bind_group(0, &self.grid.bind_group, &[]);
@@ -2,6 +2,14 @@
 
 struct CameraUniform {
     view_projection_matrix: mat4x4<f32>,
+    // The w of these is unused.
+    position: vec4<f32>,
+    right: vec4<f32>,
+    up: vec4<f32>,
+    // x: 0 for studio lighting, 1 for the matcap.
+    lighting: vec4<u32>,
+    // x: the opacity of the faces, less than 1 in x-ray.
+    opacity: vec4<f32>,
 };
 
 @group(0) @binding(0)
@@ -9,25 +17,35 @@ var<uniform> camera: CameraUniform;
 
 struct VertexInput {
     @location(0) position: vec3<f32>,
-    @location(1) color: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) color: vec3<f32>,
+};
+
+struct InstanceInput {
+    @location(3) base_color: vec3<f32>,
 };
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>, // gl_Position
     @location(0) color: vec3<f32>,
     @location(1) fragment_depth: f32,
+    @location(2) world_position: vec3<f32>,
+    @location(3) normal: vec3<f32>,
 };
 
 @vertex
 fn vs_main(
     model: VertexInput,
+    instance: InstanceInput,
 ) -> VertexOutput {
     var out: VertexOutput;
 
-    out.color = model.color;
-    let clip_position = camera.view_projection_matrix * vec4<f32>(model.position, 1.0);    
+    out.color = model.color * instance.base_color;
+    out.world_position = model.position;
+    out.normal = model.normal;
+    let clip_position = camera.view_projection_matrix * vec4<f32>(model.position, 1.0);
     out.fragment_depth = clip_position.z / clip_position.w;
-    out.clip_position = clip_position; 
+    out.clip_position = clip_position;
 
     return out;
 }
@@ -39,13 +57,76 @@ struct FragmentShaderOutput {
     @builtin(frag_depth) fragDepth: f32,
 }
 
-@fragment
-fn fs_main(in: VertexOutput) -> FragmentShaderOutput {
+// A light at the camera so that whatever we look at is lit, plus a key light from above and a
+// weaker fill light from the other side, fixed in the world so that the shading changes (and
+// gives a sense of the shape) as the view rotates. The ambient term is brighter from above than
+// from below.
+fn studio_lighting(normal: vec3<f32>, to_eye: vec3<f32>) -> f32 {
+    let key_direction = normalize(vec3<f32>(0.4, 0.8, 0.6));
+    let fill_direction = normalize(vec3<f32>(-0.6, 0.2, -0.4));
+
+    let headlight = max(dot(normal, to_eye), 0.0);
+    let key = max(dot(normal, key_direction), 0.0);
+    let fill = max(dot(normal, fill_direction), 0.0);
+    let ambient = mix(0.08, 0.16, normal.y * 0.5 + 0.5);
+    let specular = pow(headlight, 32.0);
+
+    return ambient + 0.45 * headlight + 0.35 * key + 0.15 * fill + 0.08 * specular;
+}
+
+// A procedural studio matcap: a soft light from the upper left, a darker floor and a bright
+// rim, looked up with the normal as seen from the camera.
+fn matcap_lighting(normal: vec3<f32>) -> f32 {
+    let x = dot(normal, camera.right.xyz);
+    let y = dot(normal, camera.up.xyz);
+    let facing = sqrt(max(1.0 - x * x - y * y, 0.0));
+    let screen_normal = vec3<f32>(x, y, facing);
+
+    let light = max(dot(screen_normal, normalize(vec3<f32>(-0.4, 0.5, 0.75))), 0.0);
+    let floor = smoothstep(-1.0, 0.3, y);
+    let rim = pow(1.0 - facing, 3.0);
+    let highlight = pow(light, 24.0);
+
+    return 0.12 + 0.5 * light * floor + 0.15 * floor + 0.2 * rim + 0.35 * highlight;
+}
+
+fn shade(in: VertexOutput, surface_normal: vec3<f32>) -> FragmentShaderOutput {
     var out: FragmentShaderOutput;
 
-    out.fragDepth = in.fragment_depth; 
-    out.color = vec4<f32>(in.color, 1.0);
+    let to_eye = normalize(camera.position.xyz - in.world_position);
+    // Degenerate faces have no normal; light them as if they faced the camera.
+    var normal = to_eye;
+    if (length(surface_normal) > 0.0001) {
+        normal = normalize(surface_normal);
+    }
+    // Back faces are only drawn in x-ray, where they are lit like the front ones.
+    if (dot(normal, to_eye) < 0.0) {
+        normal = -normal;
+    }
+
+    var brightness: f32;
+    if (camera.lighting.x == 1u) {
+        brightness = matcap_lighting(normal);
+    } else {
+        brightness = studio_lighting(normal, to_eye);
+    }
+
+    out.fragDepth = in.fragment_depth;
+    out.color = vec4<f32>(in.color * brightness, camera.opacity.x);
 
     return out;
 }
 
+// Smooth shading, with the normals given by the mesh.
+@fragment
+fn fs_main(in: VertexOutput) -> FragmentShaderOutput {
+    return shade(in, in.normal);
+}
+
+// Flat shading, with the normal of the triangle the fragment is on. Which way it points depends
+// on the screen derivatives, but `shade()` turns it to face the camera anyway.
+@fragment
+fn fs_flat(in: VertexOutput) -> FragmentShaderOutput {
+    let normal = cross(dpdx(in.world_position), dpdy(in.world_position));
+    return shade(in, normal);
+}
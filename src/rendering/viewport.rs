@@ -1,8 +1,92 @@
-use std::sync::Arc;
-
 use eframe::wgpu::util::DeviceExt;
 
-use crate::camera::Camera;
+use crate::{
+    camera::Camera,
+    math::{point::Point3, vector::Vector3},
+};
+
+/// How the solid shading lights the objects.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Lighting {
+    /// A light at the camera, plus a key and a fill light fixed in the world.
+    Studio,
+    /// A studio-like environment that moves with the camera, looked up by the direction of
+    /// the normal on the screen (a "material capture").
+    Matcap,
+}
+
+impl Lighting {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Self::Studio => "Studio",
+            Self::Matcap => "Matcap",
+        }
+    }
+}
+
+/// What gets drawn of the objects.
+#[derive(Clone, Copy, PartialEq)]
+pub enum ShadingMode {
+    /// Only the edges, so that everything can be seen through.
+    Wireframe,
+    Solid,
+    /// Shaded faces with the edges drawn over them.
+    SolidWithWireframe,
+}
+
+impl ShadingMode {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Self::Wireframe => "Wireframe",
+            Self::Solid => "Solid",
+            Self::SolidWithWireframe => "Solid + wireframe",
+        }
+    }
+
+    pub fn draws_faces(&self) -> bool {
+        !matches!(self, Self::Wireframe)
+    }
+
+    pub fn draws_edges(&self) -> bool {
+        !matches!(self, Self::Solid)
+    }
+}
+
+/// The settings for how the viewport draws the scene.
+#[derive(Clone, Copy)]
+pub struct ViewportShading {
+    pub mode: ShadingMode,
+    pub lighting: Lighting,
+    /// Whether the faces are shaded with the (split) normals of their corners, rather than each
+    /// face being flat.
+    pub smooth: bool,
+    /// How wide the edges are drawn, in pixels.
+    pub line_width: f32,
+    /// Whether the faces are drawn see-through, so that what is behind them can be seen and
+    /// selected.
+    pub x_ray: bool,
+    /// How opaque the faces are in x-ray, from 0 to 1.
+    pub x_ray_opacity: f32,
+}
+
+impl ViewportShading {
+    pub fn new() -> Self {
+        Self {
+            mode: ShadingMode::Solid,
+            lighting: Lighting::Studio,
+            smooth: true,
+            line_width: 1.5,
+            x_ray: false,
+            x_ray_opacity: 0.4,
+        }
+    }
+
+    /// Whether the elements hidden behind faces can be seen, either because the faces are
+    /// see-through or because they aren't drawn at all.
+    pub fn sees_through(&self) -> bool {
+        self.x_ray || !self.mode.draws_faces()
+    }
+}
 
 /// The CPU version of the uniform, which can be directly copied over to the GPU uniform
 /// by bytemucking it.
@@ -10,19 +94,56 @@ use crate::camera::Camera;
 #[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct ViewportUniform {
     view_projection_matrix: [[f32; 4]; 4],
+    // The camera vectors are vec4s on the GPU side because of alignment, their w is unused.
+    camera_position: [f32; 4],
+    camera_right: [f32; 4],
+    camera_up: [f32; 4],
+    /// x is the lighting (see `Lighting`), the rest is padding.
+    lighting: [u32; 4],
+    /// x is the opacity of the faces, the rest is padding.
+    opacity: [f32; 4],
 }
 
 impl ViewportUniform {
     pub fn new(camera: &Camera) -> Self {
-        Self {
+        let mut uniform = Self {
             view_projection_matrix: camera.get_view_projection_matrix().into(),
-        }
+            camera_position: [0.0; 4],
+            camera_right: [0.0; 4],
+            camera_up: [0.0; 4],
+            lighting: [0; 4],
+            opacity: [1.0, 0.0, 0.0, 0.0],
+        };
+        uniform.update_view_projection_matrix(camera);
+        uniform
     }
 
-    /// Copies the view projection matrix from the camera into itself, overwriting the
-    /// previous value.
+    /// Copies the view projection matrix and the rest of the camera information used for
+    /// lighting into itself, overwriting the previous values.
     pub fn update_view_projection_matrix(&mut self, camera: &Camera) {
+        let to_vec4 = |v: Vector3| [v.x(), v.y(), v.z(), 0.0];
+        let (right, up) = camera.get_right_and_up();
+
         self.view_projection_matrix = camera.get_view_projection_matrix().into();
+        self.camera_position = to_vec4(camera.get_position().to_vec3());
+        self.camera_right = to_vec4(right);
+        self.camera_up = to_vec4(up);
+    }
+
+    pub fn set_lighting(&mut self, lighting: Lighting) {
+        self.lighting[0] = match lighting {
+            Lighting::Studio => 0,
+            Lighting::Matcap => 1,
+        };
+    }
+
+    pub fn set_opacity(&mut self, opacity: f32) {
+        self.opacity[0] = opacity;
+    }
+
+    pub fn get_camera_position(&self) -> Point3 {
+        let [x, y, z, _] = self.camera_position;
+        Point3::new(x, y, z)
     }
 }
 
@@ -35,10 +156,7 @@ pub struct ViewportRenderResources {
 }
 
 impl ViewportRenderResources {
-    pub fn initialize(
-        device: &Arc<eframe::wgpu::Device>,
-        camera_uniform: &ViewportUniform,
-    ) -> Self {
+    pub fn initialize(device: &eframe::wgpu::Device, camera_uniform: &ViewportUniform) -> Self {
         let buffer = device.create_buffer_init(&eframe::wgpu::util::BufferInitDescriptor {
             label: Some("Camera Buffer"),
             contents: bytemuck::cast_slice(&[*camera_uniform]),
@@ -49,7 +167,8 @@ impl ViewportRenderResources {
             device.create_bind_group_layout(&eframe::wgpu::BindGroupLayoutDescriptor {
                 entries: &[eframe::wgpu::BindGroupLayoutEntry {
                     binding: 0,
-                    visibility: eframe::wgpu::ShaderStages::VERTEX,
+                    visibility: eframe::wgpu::ShaderStages::VERTEX
+                        | eframe::wgpu::ShaderStages::FRAGMENT,
                     ty: eframe::wgpu::BindingType::Buffer {
                         ty: eframe::wgpu::BufferBindingType::Uniform,
                         has_dynamic_offset: false,
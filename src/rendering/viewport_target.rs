@@ -0,0 +1,251 @@
+//! The scene isn't drawn straight into egui's render pass, which can't be multisampled (nor can
+//! its depth buffer). Instead it is drawn into a texture of our own, multisampled if asked to,
+//! which is resolved and then copied into egui's render pass.
+
+use super::depth_texture::DepthTexture;
+
+/// The sample counts the viewport can be drawn with, 1 meaning no multisampling.
+pub const SAMPLE_COUNTS: [u32; 4] = [1, 2, 4, 8];
+
+/// The sample counts the device can draw the viewport with, into a color texture of the surface
+/// format and a depth texture. Without the device feature requested in `main.rs` only the
+/// sample counts WebGPU guarantees for those formats can be used, otherwise those the adapter
+/// supports.
+pub fn get_supported_sample_counts(
+    adapter: &eframe::wgpu::Adapter,
+    device: &eframe::wgpu::Device,
+    surface_format: eframe::wgpu::TextureFormat,
+) -> Vec<u32> {
+    let has_feature = device
+        .features()
+        .contains(eframe::wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES);
+    let format_flags = |format: eframe::wgpu::TextureFormat| {
+        if has_feature {
+            adapter.get_texture_format_features(format).flags
+        } else {
+            format.describe().guaranteed_format_features.flags
+        }
+    };
+    let surface_flags = format_flags(surface_format);
+    let depth_flags = format_flags(DepthTexture::DEPTH_FORMAT);
+
+    SAMPLE_COUNTS
+        .into_iter()
+        .filter(|count| {
+            surface_flags.sample_count_supported(*count)
+                && depth_flags.sample_count_supported(*count)
+        })
+        .collect()
+}
+
+pub(super) struct ViewportTarget {
+    /// In pixels.
+    pub size: [u32; 2],
+    pub sample_count: u32,
+    /// What gets drawn into when multisampling, resolved into `resolved_view`.
+    multisampled_view: Option<eframe::wgpu::TextureView>,
+    /// What ends up holding the image. It can be copied out of, for drawing without a window.
+    pub resolved_texture: eframe::wgpu::Texture,
+    resolved_view: eframe::wgpu::TextureView,
+    pub depth_texture: DepthTexture,
+    /// Samples `resolved_view`, for `BlitRenderResources`.
+    pub blit_bind_group: eframe::wgpu::BindGroup,
+}
+
+impl ViewportTarget {
+    pub fn new(
+        device: &eframe::wgpu::Device,
+        surface_format: eframe::wgpu::TextureFormat,
+        size: [u32; 2],
+        sample_count: u32,
+        blit: &BlitRenderResources,
+    ) -> Self {
+        let create_texture = |label, sample_count, usage| {
+            device.create_texture(&eframe::wgpu::TextureDescriptor {
+                label: Some(label),
+                size: eframe::wgpu::Extent3d {
+                    width: size[0],
+                    height: size[1],
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count,
+                dimension: eframe::wgpu::TextureDimension::D2,
+                format: surface_format,
+                usage,
+                view_formats: &[],
+            })
+        };
+
+        let resolved_texture = create_texture(
+            "viewport texture",
+            1,
+            eframe::wgpu::TextureUsages::RENDER_ATTACHMENT
+                | eframe::wgpu::TextureUsages::TEXTURE_BINDING
+                | eframe::wgpu::TextureUsages::COPY_SRC,
+        );
+        let resolved_view =
+            resolved_texture.create_view(&eframe::wgpu::TextureViewDescriptor::default());
+        let multisampled_view = (sample_count > 1).then(|| {
+            create_texture(
+                "multisampled viewport texture",
+                sample_count,
+                eframe::wgpu::TextureUsages::RENDER_ATTACHMENT,
+            )
+            .create_view(&eframe::wgpu::TextureViewDescriptor::default())
+        });
+
+        let depth_texture = DepthTexture::new(
+            device,
+            &eframe::egui::vec2(size[0] as f32, size[1] as f32),
+            sample_count,
+        );
+
+        let blit_bind_group = device.create_bind_group(&eframe::wgpu::BindGroupDescriptor {
+            layout: &blit.bind_group_layout,
+            entries: &[
+                eframe::wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: eframe::wgpu::BindingResource::TextureView(&resolved_view),
+                },
+                eframe::wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: eframe::wgpu::BindingResource::Sampler(&blit.sampler),
+                },
+            ],
+            label: Some("blit bind group"),
+        });
+
+        Self {
+            size,
+            sample_count,
+            multisampled_view,
+            resolved_texture,
+            resolved_view,
+            depth_texture,
+            blit_bind_group,
+        }
+    }
+
+    /// Clears the target to `background` (see `DrawingStuff::background`).
+    pub fn create_color_attachment(
+        &self,
+        background: eframe::wgpu::Color,
+    ) -> eframe::wgpu::RenderPassColorAttachment<'_> {
+        let (view, resolve_target) = match &self.multisampled_view {
+            Some(multisampled_view) => (multisampled_view, Some(&self.resolved_view)),
+            None => (&self.resolved_view, None),
+        };
+
+        eframe::wgpu::RenderPassColorAttachment {
+            view,
+            resolve_target,
+            ops: eframe::wgpu::Operations {
+                load: eframe::wgpu::LoadOp::Clear(background),
+                store: true,
+            },
+        }
+    }
+
+    /// For the pipelines that draw into the target.
+    pub fn create_multisample_state(sample_count: u32) -> eframe::wgpu::MultisampleState {
+        eframe::wgpu::MultisampleState {
+            count: sample_count,
+            ..Default::default()
+        }
+    }
+}
+
+/// Copies the viewport texture into egui's render pass, over the area egui gave the viewport.
+pub(super) struct BlitRenderResources {
+    pub pipeline: eframe::wgpu::RenderPipeline,
+    pub bind_group_layout: eframe::wgpu::BindGroupLayout,
+    pub sampler: eframe::wgpu::Sampler,
+}
+
+impl BlitRenderResources {
+    pub fn initialize(
+        device: &eframe::wgpu::Device,
+        surface_format: eframe::wgpu::TextureFormat,
+    ) -> Self {
+        let shader = device.create_shader_module(eframe::wgpu::ShaderModuleDescriptor {
+            label: Some("blit shader"),
+            source: eframe::wgpu::ShaderSource::Wgsl(include_str!("blit.wgsl").into()),
+        });
+
+        let bind_group_layout =
+            device.create_bind_group_layout(&eframe::wgpu::BindGroupLayoutDescriptor {
+                entries: &[
+                    eframe::wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: eframe::wgpu::ShaderStages::FRAGMENT,
+                        ty: eframe::wgpu::BindingType::Texture {
+                            sample_type: eframe::wgpu::TextureSampleType::Float {
+                                filterable: true,
+                            },
+                            view_dimension: eframe::wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    eframe::wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: eframe::wgpu::ShaderStages::FRAGMENT,
+                        ty: eframe::wgpu::BindingType::Sampler(
+                            eframe::wgpu::SamplerBindingType::Filtering,
+                        ),
+                        count: None,
+                    },
+                ],
+                label: Some("blit bind group layout"),
+            });
+
+        // The texture is the same size as the area it is copied to, so there is nothing to
+        // filter.
+        let sampler = device.create_sampler(&eframe::wgpu::SamplerDescriptor {
+            label: Some("blit sampler"),
+            mag_filter: eframe::wgpu::FilterMode::Nearest,
+            min_filter: eframe::wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let pipeline_layout =
+            device.create_pipeline_layout(&eframe::wgpu::PipelineLayoutDescriptor {
+                label: Some("blit render pipeline layout"),
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        let pipeline = device.create_render_pipeline(&eframe::wgpu::RenderPipelineDescriptor {
+            label: Some("blit render pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: eframe::wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(eframe::wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(eframe::wgpu::ColorTargetState {
+                    format: surface_format,
+                    // What was drawn with alpha blending over the transparent clear color ends
+                    // up premultiplied.
+                    blend: Some(eframe::wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                    write_mask: eframe::wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: eframe::wgpu::PrimitiveState::default(),
+            // egui's render pass has neither a depth buffer nor multisampling.
+            depth_stencil: None,
+            multisample: eframe::wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            sampler,
+        }
+    }
+}
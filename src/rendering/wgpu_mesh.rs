@@ -1,31 +1,50 @@
-use std::sync::Arc;
+use std::{cmp::Ordering, ops::Range, sync::Arc};
 
 use eframe::wgpu::util::DeviceExt;
 
+use crate::math::point::Point3;
 use crate::meshes::Mesh;
-use crate::vertex::Vertex;
+use crate::vertex::{Instance, Vertex};
 
 /// A representation of the mesh that can basically be passed directly to WGPU.
 /// It stores things directly in `wgpu::Buffer`s.
 pub struct WgpuMesh {
     pub vertex_buffer: eframe::wgpu::Buffer,
     pub index_buffer: eframe::wgpu::Buffer,
-    pub num_indices: u32,
+    /// One `Instance` per mesh, in the same order as `index_ranges`.
+    pub instance_buffer: eframe::wgpu::Buffer,
+    /// The indices of each mesh, which is drawn as the instance with the same index.
+    pub index_ranges: Vec<Range<u32>>,
 }
 
 impl WgpuMesh {
     /// Collects the vertex/index data for a collection of meshes into a
     /// single vertex/index buffer. This is a utility function for preparing
     /// a single draw call for a collection of meshes.
-    pub fn from_meshes(device: &eframe::wgpu::Device, meshes: &Vec<Arc<Mesh>>) -> Self {
+    ///
+    /// If `sort_from` is given, the meshes and the triangles within each of
+    /// them go from the farthest from that point to the closest, so that they
+    /// blend correctly when drawn see-through.
+    pub fn from_meshes(
+        device: &eframe::wgpu::Device,
+        meshes: &[Arc<Mesh>],
+        sort_from: Option<Point3>,
+    ) -> Self {
+        let mut meshes: Vec<&Arc<Mesh>> = meshes.iter().collect();
+        if let Some(eye) = sort_from {
+            meshes.sort_by(|a, b| {
+                let distance = |mesh: &Mesh| squared_distance(&center(&mesh.vertices), &eye);
+                distance(b)
+                    .partial_cmp(&distance(a))
+                    .unwrap_or(Ordering::Equal)
+            });
+        }
+
         let mut vertices = Vec::<Vertex>::new();
-        let mut indices = Vec::<u16>::new();
+        let mut indices = Vec::<u32>::new();
+        let mut instances = Vec::<Instance>::new();
+        let mut index_ranges = Vec::new();
         for mesh in meshes {
-            // println!(
-            //     "drawing {} verts, {} indices",
-            //     mesh.vertices.len(),
-            //     mesh.indices.len()
-            // );
             // We need to copy the elements over anyways since we are
             // sending the data to the GPU. `append()` destructs the
             // parameter given to it, but cloning the entire vertex
@@ -33,10 +52,20 @@ impl WgpuMesh {
             // if we just copied references, we would have to perform
             // a copy when sending it to the GPU. Here, we copy up front
             // and just move later.
+            // The indices of each mesh are relative to its own vertices, so they need to be
+            // offset by the vertices of the meshes that came before it.
+            let index_offset = vertices.len() as u32;
             vertices.append(&mut mesh.vertices.clone());
-
-            // TODO: NEED TO ADJUST INDEX NUMBERS
-            indices.append(&mut mesh.indices.clone());
+            let first_index = indices.len() as u32;
+            let mesh_indices = match sort_from {
+                Some(eye) => triangles_back_to_front(mesh, &eye),
+                None => mesh.indices.clone(),
+            };
+            indices.extend(mesh_indices.iter().map(|i| i + index_offset));
+            index_ranges.push(first_index..indices.len() as u32);
+            instances.push(Instance {
+                base_color: mesh.base_color,
+            });
         }
 
         let vertex_buffer = device.create_buffer_init(&eframe::wgpu::util::BufferInitDescriptor {
@@ -49,12 +78,54 @@ impl WgpuMesh {
             contents: bytemuck::cast_slice(indices.as_slice()),
             usage: eframe::wgpu::BufferUsages::INDEX,
         });
-        let num_indices = indices.len() as u32;
+        let instance_buffer =
+            device.create_buffer_init(&eframe::wgpu::util::BufferInitDescriptor {
+                label: Some("Instance Buffer"),
+                contents: bytemuck::cast_slice(instances.as_slice()),
+                usage: eframe::wgpu::BufferUsages::VERTEX,
+            });
 
         WgpuMesh {
             vertex_buffer,
             index_buffer,
-            num_indices,
+            instance_buffer,
+            index_ranges,
         }
     }
 }
+
+fn squared_distance(a: &[f32; 3], b: &Point3) -> f32 {
+    let (dx, dy, dz) = (a[0] - b.x(), a[1] - b.y(), a[2] - b.z());
+    dx * dx + dy * dy + dz * dz
+}
+
+fn center(vertices: &[Vertex]) -> [f32; 3] {
+    let mut sum = [0.0; 3];
+    for vertex in vertices {
+        for (s, p) in sum.iter_mut().zip(vertex.position) {
+            *s += p;
+        }
+    }
+    sum.map(|s| s / vertices.len().max(1) as f32)
+}
+
+/// The indices of the mesh, with its triangles ordered from the farthest from `eye` to the
+/// closest.
+fn triangles_back_to_front(mesh: &Mesh, eye: &Point3) -> Vec<u32> {
+    let mut triangles: Vec<(f32, &[u32])> = mesh
+        .indices
+        .chunks_exact(3)
+        .map(|triangle| {
+            let corners: Vec<Vertex> = triangle
+                .iter()
+                .map(|i| mesh.vertices[*i as usize])
+                .collect();
+            (squared_distance(&center(&corners), eye), triangle)
+        })
+        .collect();
+    triangles.sort_by(|(a, _), (b, _)| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+    triangles
+        .into_iter()
+        .flat_map(|(_, triangle)| triangle.iter().copied())
+        .collect()
+}
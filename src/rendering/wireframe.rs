@@ -0,0 +1,203 @@
+use std::sync::Arc;
+
+use eframe::wgpu::util::DeviceExt;
+
+use crate::{
+    camera::Camera,
+    edges::{EdgeMesh, EdgeVertex},
+    math::vector::Vector2,
+};
+
+use super::{
+    depth_texture::DepthTexture,
+    viewport::{ShadingMode, ViewportShading},
+    viewport_target::ViewportTarget,
+};
+
+/// How far the lines are moved towards the camera, as a fraction of their distance to it, so
+/// that they aren't hidden by the faces they are the sides of. Being relative to the distance,
+/// it works the same for close up and far away meshes.
+const DEPTH_BIAS: f32 = 0.002;
+/// The color of the edges whose vertices are selected.
+const SELECTED_COLOR: [f32; 4] = [1.0, 0.55, 0.1, 1.0];
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct WireframeUniform {
+    view_projection_matrix: [[f32; 4]; 4],
+    color: [f32; 4],
+    selected_color: [f32; 4],
+    /// The w is unused.
+    camera_position: [f32; 4],
+    /// In pixels, to go between pixels and normalized device coordinates.
+    viewport_size: [f32; 2],
+    /// In pixels.
+    width: f32,
+    depth_bias: f32,
+}
+
+impl WireframeUniform {
+    pub fn new(camera: &Camera) -> Self {
+        let shading = ViewportShading::new();
+        let mut uniform = Self {
+            view_projection_matrix: [[0.0; 4]; 4],
+            color: [0.0; 4],
+            selected_color: SELECTED_COLOR,
+            camera_position: [0.0; 4],
+            viewport_size: [1.0, 1.0],
+            width: shading.line_width,
+            depth_bias: DEPTH_BIAS,
+        };
+        uniform.update(camera, &Vector2::new(1.0, 1.0), &shading);
+        uniform
+    }
+
+    pub fn update(&mut self, camera: &Camera, viewport_size: &Vector2, shading: &ViewportShading) {
+        self.view_projection_matrix = camera.get_view_projection_matrix().into();
+        let position = camera.get_position();
+        self.camera_position = [position.x(), position.y(), position.z(), 1.0];
+        self.viewport_size = [viewport_size.x(), viewport_size.y()];
+        self.width = shading.line_width;
+        // Dark lines over the shaded faces, light ones when there is nothing behind them but
+        // the background.
+        self.color = match shading.mode {
+            ShadingMode::Wireframe => [0.85, 0.85, 0.85, 1.0],
+            ShadingMode::Solid | ShadingMode::SolidWithWireframe => [0.05, 0.05, 0.05, 1.0],
+        };
+    }
+}
+
+/// The edges to draw, collected into one vertex/index buffer.
+pub(super) struct WgpuEdgeMesh {
+    pub vertex_buffer: eframe::wgpu::Buffer,
+    pub index_buffer: eframe::wgpu::Buffer,
+    pub num_indices: u32,
+}
+
+impl WgpuEdgeMesh {
+    pub fn from_edge_meshes(device: &eframe::wgpu::Device, meshes: &[Arc<EdgeMesh>]) -> Self {
+        let mut vertices = Vec::<EdgeVertex>::new();
+        let mut indices = Vec::<u32>::new();
+        for mesh in meshes {
+            let index_offset = vertices.len() as u32;
+            vertices.extend_from_slice(&mesh.vertices);
+            indices.extend(mesh.indices.iter().map(|i| i + index_offset));
+        }
+
+        let vertex_buffer = device.create_buffer_init(&eframe::wgpu::util::BufferInitDescriptor {
+            label: Some("edge vertex buffer"),
+            contents: bytemuck::cast_slice(vertices.as_slice()),
+            usage: eframe::wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&eframe::wgpu::util::BufferInitDescriptor {
+            label: Some("edge index buffer"),
+            contents: bytemuck::cast_slice(indices.as_slice()),
+            usage: eframe::wgpu::BufferUsages::INDEX,
+        });
+
+        Self {
+            vertex_buffer,
+            index_buffer,
+            num_indices: indices.len() as u32,
+        }
+    }
+}
+
+pub(super) struct WireframeRenderResources {
+    pub pipeline: eframe::wgpu::RenderPipeline,
+    pub bind_group: eframe::wgpu::BindGroup,
+    pub buffer: eframe::wgpu::Buffer,
+}
+
+impl WireframeRenderResources {
+    pub fn initialize(
+        device: &eframe::wgpu::Device,
+        surface_format: eframe::wgpu::TextureFormat,
+        sample_count: u32,
+        uniform: &WireframeUniform,
+    ) -> Self {
+        let buffer = device.create_buffer_init(&eframe::wgpu::util::BufferInitDescriptor {
+            label: Some("wireframe buffer"),
+            contents: bytemuck::cast_slice(&[*uniform]),
+            usage: eframe::wgpu::BufferUsages::UNIFORM | eframe::wgpu::BufferUsages::COPY_DST,
+        });
+
+        let shader = device.create_shader_module(eframe::wgpu::ShaderModuleDescriptor {
+            label: Some("wireframe shader"),
+            source: eframe::wgpu::ShaderSource::Wgsl(include_str!("wireframe.wgsl").into()),
+        });
+
+        let bind_group_layout =
+            device.create_bind_group_layout(&eframe::wgpu::BindGroupLayoutDescriptor {
+                entries: &[eframe::wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: eframe::wgpu::ShaderStages::VERTEX
+                        | eframe::wgpu::ShaderStages::FRAGMENT,
+                    ty: eframe::wgpu::BindingType::Buffer {
+                        ty: eframe::wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+                label: Some("wireframe bind group layout"),
+            });
+        let bind_group = device.create_bind_group(&eframe::wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[eframe::wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+            label: Some("wireframe bind group"),
+        });
+
+        let pipeline_layout =
+            device.create_pipeline_layout(&eframe::wgpu::PipelineLayoutDescriptor {
+                label: Some("wireframe render pipeline layout"),
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        let pipeline = device.create_render_pipeline(&eframe::wgpu::RenderPipelineDescriptor {
+            label: Some("wireframe render pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: eframe::wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[EdgeVertex::get_descriptor()],
+            },
+            fragment: Some(eframe::wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(eframe::wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(eframe::wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: eframe::wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: eframe::wgpu::PrimitiveState {
+                topology: eframe::wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: eframe::wgpu::FrontFace::Ccw,
+                // Which way the quads face depends on the direction of the edge on the screen.
+                cull_mode: None,
+                polygon_mode: eframe::wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            // The faded sides of the lines shouldn't hide the lines behind them.
+            depth_stencil: Some(eframe::wgpu::DepthStencilState {
+                depth_write_enabled: false,
+                ..DepthTexture::create_depth_stencil_state()
+            }),
+            multisample: ViewportTarget::create_multisample_state(sample_count),
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            bind_group,
+            buffer,
+        }
+    }
+}
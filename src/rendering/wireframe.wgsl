@@ -0,0 +1,101 @@
+// Vertex shader
+
+struct WireframeUniform {
+    view_projection_matrix: mat4x4<f32>,
+    color: vec4<f32>,
+    selected_color: vec4<f32>,
+    // The w is unused.
+    camera_position: vec4<f32>,
+    viewport_size: vec2<f32>,
+    width: f32,
+    depth_bias: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> wireframe: WireframeUniform;
+
+struct VertexInput {
+    @location(0) start: vec3<f32>,
+    @location(1) end: vec3<f32>,
+    @location(2) at_end: f32,
+    @location(3) side: f32,
+    @location(4) selected: f32,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // How far the fragment is from the middle of the line, in pixels.
+    @location(0) distance: f32,
+    // Interpolated along the edge, so that an edge with one selected end fades between colors.
+    @location(1) selected: f32,
+};
+
+// Anything closer to the camera plane than this is treated as behind the camera.
+const NEAR_W: f32 = 0.0001;
+
+// Moves the point along the line towards the other end until it is in front of the camera, so
+// that lines going past the camera still point the right way on the screen.
+fn clip_to_near(p: vec4<f32>, other: vec4<f32>) -> vec4<f32> {
+    if (p.w >= NEAR_W) {
+        return p;
+    }
+    let t = (NEAR_W - p.w) / (other.w - p.w);
+    return mix(p, other, t);
+}
+
+// Moves the point towards the camera by a fraction of its distance to it. Along the line of
+// sight, so it stays at the same place on the screen, but ends up in front of the faces it is on
+// however close or far away they are.
+fn towards_camera(p: vec3<f32>) -> vec4<f32> {
+    let biased = mix(p, wireframe.camera_position.xyz, wireframe.depth_bias);
+    return wireframe.view_projection_matrix * vec4<f32>(biased, 1.0);
+}
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.selected = in.selected;
+
+    let start = towards_camera(in.start);
+    let end = towards_camera(in.end);
+    if (start.w < NEAR_W && end.w < NEAR_W) {
+        // Entirely behind the camera; put it outside of the clip volume.
+        out.clip_position = vec4<f32>(2.0, 2.0, 2.0, 1.0);
+        out.distance = 0.0;
+        return out;
+    }
+    let clipped_start = clip_to_near(start, end);
+    let clipped_end = clip_to_near(end, start);
+
+    let half_size = wireframe.viewport_size * 0.5;
+    let start_on_screen = clipped_start.xy / clipped_start.w * half_size;
+    let end_on_screen = clipped_end.xy / clipped_end.w * half_size;
+    var direction = vec2<f32>(1.0, 0.0);
+    if (distance(start_on_screen, end_on_screen) > 0.0001) {
+        direction = normalize(end_on_screen - start_on_screen);
+    }
+    let normal = vec2<f32>(-direction.y, direction.x);
+
+    // One more pixel on either side for the anti-aliased falloff.
+    let half_width = wireframe.width * 0.5 + 1.0;
+    var clip_position = mix(clipped_start, clipped_end, in.at_end);
+    let offset = normal * in.side * half_width / half_size;
+    clip_position = vec4<f32>(
+        clip_position.xy + offset * clip_position.w,
+        clip_position.zw,
+    );
+
+    out.clip_position = clip_position;
+    out.distance = in.side * half_width;
+
+    return out;
+}
+
+// Fragment shader
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let coverage = clamp(wireframe.width * 0.5 + 0.5 - abs(in.distance), 0.0, 1.0);
+    let color = mix(wireframe.color, wireframe.selected_color, in.selected);
+    return vec4<f32>(color.rgb, color.a * coverage);
+}
@@ -0,0 +1,332 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    bmesh::{
+        bmesh::BMesh, bmesh_boolean::BooleanOperation, bmesh_mirror::Symmetry,
+        bmesh_ray_cast::BMeshRayHit, bmesh_vertex::BMeshVertex,
+    },
+    math::{point::Point3, quaternion::Quaternion, ray::Ray, vector::Vector3, Float},
+    modifiers::{ModifierContext, ModifierStack},
+    rendering::grid::GridSettings,
+};
+
+/// Something in the scene with a mesh of its own.
+pub struct SceneObject {
+    /// Unique within the scene, since modifiers refer to other objects by name.
+    pub name: String,
+    /// The mesh that gets edited, before any modifiers.
+    pub mesh: BMesh,
+    pub modifiers: ModifierStack,
+    /// Set when edits to the mesh should be mirrored (see `BMesh::set_vertex_positions()`).
+    pub symmetry: Option<Symmetry>,
+    pub visible: bool,
+    /// The color the object is shaded with.
+    pub color: [f32; 3],
+}
+
+impl SceneObject {
+    pub fn new(name: &str, mesh: BMesh) -> Self {
+        Self {
+            name: name.to_string(),
+            mesh,
+            modifiers: ModifierStack::new(),
+            symmetry: None,
+            visible: true,
+            color: [0.8, 0.8, 0.8],
+        }
+    }
+}
+
+/// A camera placed in the scene, e.g. to set up presentation shots. It is drawn as its frustum,
+/// and viewports can look through it (see `Camera::look_through()`).
+pub struct SceneCamera {
+    /// Unique among the cameras of the scene.
+    pub name: String,
+    pub position: Point3,
+    /// From the space of the camera, where it looks down -Z with +Y up, to the world.
+    pub rotation: Quaternion,
+    /// In millimeters, like the sensor size. Together they give the field of view.
+    pub focal_length: Float,
+    pub sensor_width: Float,
+    pub sensor_height: Float,
+    /// The distances to the near and far clipping planes.
+    pub clip_start: Float,
+    pub clip_end: Float,
+}
+
+impl SceneCamera {
+    /// How far from the camera the frustum is drawn.
+    const FRUSTUM_DISPLAY_DEPTH: Float = 0.5;
+
+    /// A 50mm lens on a full frame sensor, at the origin looking down -Z.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            position: Point3::origin(),
+            rotation: Quaternion::identity(),
+            focal_length: 50.0,
+            sensor_width: 36.0,
+            sensor_height: 24.0,
+            clip_start: 0.1,
+            clip_end: 100.0,
+        }
+    }
+
+    /// In degrees.
+    pub fn get_vertical_fov(&self) -> Float {
+        2.0 * (self.sensor_height / (2.0 * self.focal_length))
+            .atan()
+            .to_degrees()
+    }
+
+    /// Changes the focal length to get the field of view (in degrees).
+    pub fn set_vertical_fov(&mut self, degrees: Float) {
+        self.focal_length = self.sensor_height / (2.0 * (degrees.to_radians() / 2.0).tan());
+    }
+
+    /// The lines to draw the camera with: from the camera to the corners of its frustum, the
+    /// frustum some way in front of it, and a triangle on top to show which way is up.
+    pub fn get_frustum_lines(&self) -> Vec<(Point3, Point3)> {
+        let depth = Self::FRUSTUM_DISPLAY_DEPTH;
+        let half_width = depth * self.sensor_width / (2.0 * self.focal_length);
+        let half_height = depth * self.sensor_height / (2.0 * self.focal_length);
+        let to_world = |x: Float, y: Float, z: Float| {
+            self.position + self.rotation.rotate_vector(Vector3::new(x, y, z))
+        };
+
+        let corners = [
+            to_world(-half_width, -half_height, -depth),
+            to_world(half_width, -half_height, -depth),
+            to_world(half_width, half_height, -depth),
+            to_world(-half_width, half_height, -depth),
+        ];
+        let up_left = to_world(-0.6 * half_width, 1.1 * half_height, -depth);
+        let up_right = to_world(0.6 * half_width, 1.1 * half_height, -depth);
+        let up_top = to_world(0.0, 1.6 * half_height, -depth);
+
+        let mut lines = Vec::new();
+        for i in 0..4 {
+            lines.push((self.position, corners[i]));
+            lines.push((corners[i], corners[(i + 1) % 4]));
+        }
+        lines.extend([(up_left, up_right), (up_right, up_top), (up_top, up_left)]);
+        lines
+    }
+}
+
+/// Which elements of the mesh are worked with in edit mode.
+#[derive(Clone, Copy, PartialEq)]
+pub enum SelectMode {
+    Vertex,
+    Edge,
+    Face,
+}
+
+impl SelectMode {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            SelectMode::Vertex => "Vertex",
+            SelectMode::Edge => "Edge",
+            SelectMode::Face => "Face",
+        }
+    }
+}
+
+pub struct Scene {
+    pub objects: Vec<SceneObject>,
+    pub cameras: Vec<SceneCamera>,
+    /// Index of the object being edited.
+    pub active_object: Option<usize>,
+    /// Whether the mesh of the active object is being edited, as opposed to the objects as a
+    /// whole.
+    pub edit_mode: bool,
+    pub select_mode: SelectMode,
+    pub grid: GridSettings,
+    /// The 3D cursor, e.g. where vertices can be merged at (see `MergeTarget::Cursor`).
+    pub cursor: Point3,
+}
+
+impl Scene {
+    /// How far the lines of the 3D cursor reach from it along each axis.
+    const CURSOR_DISPLAY_SIZE: Float = 0.2;
+
+    pub fn new() -> Self {
+        Self {
+            objects: Vec::new(),
+            cameras: Vec::new(),
+            active_object: None,
+            edit_mode: false,
+            select_mode: SelectMode::Vertex,
+            grid: GridSettings::new(),
+            cursor: Point3::origin(),
+        }
+    }
+
+    /// The lines to draw the 3D cursor with, one along each axis through it.
+    pub fn get_cursor_lines(&self) -> Vec<(Point3, Point3)> {
+        [Vector3::unit_x(), Vector3::unit_y(), Vector3::unit_z()]
+            .into_iter()
+            .map(|axis| {
+                let offset = axis * Self::CURSOR_DISPLAY_SIZE;
+                (self.cursor - offset, self.cursor + offset)
+            })
+            .collect()
+    }
+
+    /// Adds the object and makes it the active one. If the name is taken, a number is added to
+    /// it, e.g. "Cube.001".
+    pub fn add_object(&mut self, mut object: SceneObject) {
+        object.name = get_unique_name(&object.name, |name| {
+            self.objects.iter().any(|o| o.name == name)
+        });
+
+        self.objects.push(object);
+        self.active_object = Some(self.objects.len() - 1);
+    }
+
+    /// Adds a cube placed next to the last object, rather than on top of it, and makes it the
+    /// active one.
+    pub fn add_cube(&mut self, rng: &mut impl rand::Rng) {
+        let mut cube = BMesh::create_cube(rng);
+        cube.select_all();
+        cube.translate_selected(
+            Vector3::new(0.5, 0.5, 0.5) * self.objects.len() as f32,
+            None,
+        );
+        cube.deselect_all();
+        self.add_object(SceneObject::new("Cube", cube));
+    }
+
+    /// Adds the camera, renaming it like `add_object()` does if the name is taken.
+    pub fn add_camera(&mut self, mut camera: SceneCamera) {
+        camera.name = get_unique_name(&camera.name, |name| {
+            self.cameras.iter().any(|c| c.name == name)
+        });
+
+        self.cameras.push(camera);
+    }
+
+    pub fn get_active_object(&self) -> Option<&SceneObject> {
+        self.active_object.and_then(|i| self.objects.get(i))
+    }
+
+    pub fn get_active_object_mut(&mut self) -> Option<&mut SceneObject> {
+        self.active_object.and_then(|i| self.objects.get_mut(i))
+    }
+
+    /// The mesh of the object with its modifiers applied, which is what gets displayed and
+    /// exported.
+    pub fn get_evaluated_mesh(&mut self, index: usize, rng: &mut impl rand::Rng) -> &BMesh {
+        let (before, rest) = self.objects.split_at_mut(index);
+        let (object, after) = rest.split_first_mut().unwrap();
+        if !object.modifiers.has_enabled_modifiers() {
+            return &object.mesh;
+        }
+
+        let context = ModifierContext {
+            other_objects: before.iter().chain(after.iter()).collect(),
+        };
+        object.modifiers.evaluate(&object.mesh, &context, rng)
+    }
+
+    /// Where the ray first hits one of the visible objects, with their modifiers applied.
+    pub fn ray_cast(&mut self, ray: &Ray, rng: &mut impl rand::Rng) -> Option<Point3> {
+        let mut closest: Option<BMeshRayHit> = None;
+        for i in 0..self.objects.len() {
+            if !self.objects[i].visible {
+                continue;
+            }
+            let hit = match self.get_evaluated_mesh(i, rng).ray_cast(ray) {
+                Some(hit) => hit,
+                None => continue,
+            };
+            if closest
+                .as_ref()
+                .is_none_or(|closest| hit.distance < closest.distance)
+            {
+                closest = Some(hit);
+            }
+        }
+
+        closest.map(|hit| hit.position)
+    }
+
+    /// The center of the selected vertices in edit mode, or of the active object otherwise.
+    pub fn get_selection_center(&self) -> Option<Point3> {
+        let mesh = &self.get_active_object()?.mesh;
+        let get_position = |v: &Rc<RefCell<BMeshVertex>>| v.as_ref().borrow().get_position();
+        let positions: Vec<Point3> = if self.edit_mode {
+            mesh.get_selected_vertices()
+                .iter()
+                .map(get_position)
+                .collect()
+        } else {
+            mesh.get_vertices().iter().map(get_position).collect()
+        };
+
+        (!positions.is_empty()).then(|| Point3::centroid(&positions))
+    }
+
+    /// Bakes the modifier into the mesh of the object and removes it from the stack. The
+    /// modifier is applied to the mesh directly, so if it isn't the first one, the result may be
+    /// different from what was displayed. Disabled modifiers are only removed.
+    pub fn apply_modifier(
+        &mut self,
+        object_index: usize,
+        modifier_index: usize,
+        rng: &mut impl rand::Rng,
+    ) {
+        let (before, rest) = self.objects.split_at_mut(object_index);
+        let (object, after) = rest.split_first_mut().unwrap();
+
+        let entry = object.modifiers.remove(modifier_index);
+        if entry.is_enabled() {
+            let context = ModifierContext {
+                other_objects: before.iter().chain(after.iter()).collect(),
+            };
+            let auto_smooth_angle = object.mesh.get_auto_smooth_angle();
+            object.mesh = entry.get_modifier().apply(&object.mesh, &context, rng);
+            object.mesh.set_auto_smooth_angle(auto_smooth_angle);
+        }
+    }
+
+    /// Bakes the whole stack of the object into its mesh, leaving the stack empty.
+    pub fn apply_all_modifiers(&mut self, object_index: usize, rng: &mut impl rand::Rng) {
+        while !self.objects[object_index]
+            .modifiers
+            .get_entries()
+            .is_empty()
+        {
+            self.apply_modifier(object_index, 0, rng);
+        }
+    }
+
+    /// Replaces the mesh of the object with the result of the boolean operation with the
+    /// cutter object, which gets hidden.
+    pub fn apply_boolean(
+        &mut self,
+        object_index: usize,
+        cutter_index: usize,
+        operation: BooleanOperation,
+        rng: &mut impl rand::Rng,
+    ) {
+        let result = self.objects[object_index].mesh.boolean(
+            &self.objects[cutter_index].mesh,
+            operation,
+            rng,
+        );
+        self.objects[object_index].mesh = result;
+        self.objects[cutter_index].visible = false;
+    }
+}
+
+/// The name, with a number added to it if it is taken, e.g. "Cube.001".
+fn get_unique_name(base_name: &str, is_taken: impl Fn(&str) -> bool) -> String {
+    let mut name = base_name.to_string();
+    let mut suffix = 0;
+    while is_taken(&name) {
+        suffix += 1;
+        name = format!("{}.{:03}", base_name, suffix);
+    }
+    name
+}
@@ -0,0 +1,141 @@
+//! First person navigation: the camera moves like in a game, looking around with the mouse and
+//! moving with the keys, which is easier than orbiting to get around large scenes. The camera
+//! stays an orbiting one (see `Camera::set_pose()`), so orbiting picks up where flying stopped.
+
+use crate::{
+    camera::Camera,
+    math::{
+        point::Point3,
+        quaternion::Quaternion,
+        vector::{Vector2, Vector3},
+        Float, Radians,
+    },
+};
+
+/// How much the camera turns (in radians) per point the mouse moves.
+const LOOK_SENSITIVITY: Float = 0.004;
+
+/// Looking straight up or down would make turning left and right spin the camera around.
+const MAX_PITCH: Float = 89.0 * std::f32::consts::PI / 180.0;
+
+/// How long (in seconds) moving without stopping takes to get to the top speed.
+const RAMP_UP_TIME: Float = 2.0;
+
+/// The top speed, as a multiple of the speed when starting to move.
+const MAX_SPEED_FACTOR: Float = 4.0;
+
+/// How much one notch of the scroll wheel changes the speed.
+const SPEED_STEP: Float = 1.2;
+
+/// Where to go this frame, from the keys held down.
+#[derive(Clone, Copy)]
+pub struct FlyMovement {
+    /// Right (positive) or left, relative to where the camera looks.
+    pub sideways: Float,
+    /// Forward (positive) or backward, along where the camera looks.
+    pub forward: Float,
+    /// Up (positive) or down, along the Y axis of the world.
+    pub vertical: Float,
+}
+
+impl FlyMovement {
+    fn is_moving(&self) -> bool {
+        self.sideways != 0.0 || self.forward != 0.0 || self.vertical != 0.0
+    }
+}
+
+pub struct FlyNavigation {
+    /// The viewport whose camera is flying.
+    pub viewport: usize,
+    start_position: Point3,
+    start_rotation: Quaternion,
+    start_orthographic_half_height: Option<f32>,
+    position: Point3,
+    /// Turning left (positive) or right, around the Y axis of the world, in radians.
+    yaw: Float,
+    /// Looking up (positive) or down, in radians.
+    pitch: Float,
+    /// In units per second, before ramping up.
+    speed: Float,
+    /// How long the camera has been moving without stopping, in seconds.
+    moving_time: Float,
+}
+
+impl FlyNavigation {
+    /// Starts flying from where the camera is. Orthographic views switch to perspective, since
+    /// moving forward wouldn't show.
+    pub fn new(viewport: usize, camera: &mut Camera) -> Self {
+        let position = camera.get_position();
+        let rotation = camera.get_rotation();
+        let forward = rotation.rotate_vector(-Vector3::unit_z());
+        let start_orthographic_half_height = camera.get_orthographic_half_height();
+        camera.set_orthographic_half_height(None);
+
+        Self {
+            viewport,
+            start_position: position,
+            start_rotation: rotation,
+            start_orthographic_half_height,
+            position,
+            yaw: Float::atan2(-forward.x(), -forward.z()),
+            pitch: forward
+                .y()
+                .clamp(-1.0, 1.0)
+                .asin()
+                .clamp(-MAX_PITCH, MAX_PITCH),
+            // Crossing the distance to the point the camera orbits around in a second is about
+            // right whatever the size of the scene.
+            speed: camera.get_orbit_distance(),
+            moving_time: 0.0,
+        }
+    }
+
+    /// Moves and turns the camera for one frame.
+    ///
+    /// - `look_delta`: how far the mouse moved, in points.
+    /// - `scroll_notches`: how far the scroll wheel turned up, to speed up (or slow down).
+    /// - `dt`: how long the frame took, in seconds.
+    pub fn update(
+        &mut self,
+        camera: &mut Camera,
+        movement: FlyMovement,
+        look_delta: Vector2,
+        scroll_notches: Float,
+        dt: Float,
+    ) {
+        self.speed *= SPEED_STEP.powf(scroll_notches);
+
+        self.yaw -= look_delta.x() * LOOK_SENSITIVITY;
+        self.pitch = (self.pitch - look_delta.y() * LOOK_SENSITIVITY).clamp(-MAX_PITCH, MAX_PITCH);
+        let rotation = self.get_rotation();
+
+        if movement.is_moving() {
+            self.moving_time += dt;
+            let ramp = (self.moving_time / RAMP_UP_TIME).min(1.0);
+            let speed = self.speed * (1.0 + ramp * (MAX_SPEED_FACTOR - 1.0));
+
+            let right = rotation.rotate_vector(Vector3::unit_x());
+            let forward = rotation.rotate_vector(-Vector3::unit_z());
+            let direction = right * movement.sideways
+                + forward * movement.forward
+                + Vector3::unit_y() * movement.vertical;
+            self.position = self.position + direction.normalize() * (speed * dt);
+        } else {
+            self.moving_time = 0.0;
+        }
+
+        camera.set_pose(self.position, rotation);
+    }
+
+    /// Puts the camera back where it was when flying started.
+    pub fn cancel(self, camera: &mut Camera) {
+        camera.set_pose(self.start_position, self.start_rotation);
+        camera.set_orthographic_half_height(self.start_orthographic_half_height);
+    }
+
+    fn get_rotation(&self) -> Quaternion {
+        let yaw = Quaternion::rotation_from_axis_angle(Vector3::unit_y(), Radians(self.yaw));
+        let pitch = Quaternion::rotation_from_axis_angle(Vector3::unit_x(), Radians(self.pitch));
+        yaw * pitch
+    }
+}
@@ -0,0 +1,165 @@
+//! The interactive part of the knife: picking points on the mesh, snapping them, and showing
+//! the cut before it is made. The cutting itself is `BMesh::knife_cut()`.
+
+use crate::{
+    bmesh::bmesh::BMesh,
+    camera::Camera,
+    math::{point::Point3, vector::Vector2, Float},
+};
+
+/// How close (in pixels) the cursor needs to be to a vertex or edge midpoint to snap to it.
+const SNAP_RADIUS: Float = 10.0;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum KnifeSnap {
+    Vertex,
+    EdgeMidpoint,
+    /// Not snapped to anything, just somewhere on a face.
+    Face,
+}
+
+#[derive(Clone, Copy)]
+pub struct KnifePoint {
+    pub position: Point3,
+    pub snap: KnifeSnap,
+}
+
+pub struct KnifeTool {
+    points: Vec<KnifePoint>,
+    /// Where the next point would go, if the cursor is over the mesh.
+    hovered: Option<KnifePoint>,
+}
+
+impl KnifeTool {
+    pub fn new() -> Self {
+        Self {
+            points: Vec::new(),
+            hovered: None,
+        }
+    }
+
+    /// Finds the point on the mesh under the cursor, snapping it to a nearby vertex or edge
+    /// midpoint of the face under the cursor.
+    ///
+    /// - `cursor`: relative to the drawing region, `None` if the cursor is not over it.
+    pub fn update_hovered(
+        &mut self,
+        mesh: &BMesh,
+        camera: &Camera,
+        cursor: Option<Vector2>,
+        screen_size: &Vector2,
+    ) {
+        self.hovered = None;
+        let cursor = match cursor {
+            Some(cursor) => cursor,
+            None => return,
+        };
+
+        let ray = camera.get_ray_through_screen_point(&cursor, screen_size);
+        let hit = match mesh.ray_cast(&ray) {
+            Some(hit) => hit,
+            None => return,
+        };
+
+        let face = hit.face.as_ref().borrow();
+        let corners: Vec<Point3> = face
+            .get_vertices()
+            .iter()
+            .map(|v| v.as_ref().borrow().get_position())
+            .collect();
+        let midpoints: Vec<Point3> = (0..corners.len())
+            .map(|i| corners[i].lerp(&corners[(i + 1) % corners.len()], 0.5))
+            .collect();
+
+        let closest_on_screen = |candidates: &[Point3]| -> Option<Point3> {
+            candidates
+                .iter()
+                .filter_map(|p| {
+                    let on_screen = camera.project_to_screen(*p, screen_size)?;
+                    let distance =
+                        Float::hypot(on_screen.x() - cursor.x(), on_screen.y() - cursor.y());
+                    (distance < SNAP_RADIUS).then_some((*p, distance))
+                })
+                .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+                .map(|(p, _)| p)
+        };
+
+        // Vertices take precedence over edge midpoints when both are in range.
+        let hovered = if let Some(position) = closest_on_screen(&corners) {
+            KnifePoint {
+                position,
+                snap: KnifeSnap::Vertex,
+            }
+        } else if let Some(position) = closest_on_screen(&midpoints) {
+            KnifePoint {
+                position,
+                snap: KnifeSnap::EdgeMidpoint,
+            }
+        } else {
+            KnifePoint {
+                position: hit.position,
+                snap: KnifeSnap::Face,
+            }
+        };
+
+        self.hovered = Some(hovered);
+    }
+
+    /// Adds the hovered point to the path of the cut.
+    pub fn add_hovered_point(&mut self) {
+        if let Some(hovered) = self.hovered {
+            self.points.push(hovered);
+        }
+    }
+
+    /// Makes the cut. Returns the number of faces that were split.
+    pub fn confirm(self, mesh: &mut BMesh, camera: &Camera, rng: &mut impl rand::Rng) -> usize {
+        let path: Vec<Point3> = self.points.iter().map(|p| p.position).collect();
+        mesh.knife_cut(&path, camera.get_position(), rng)
+    }
+
+    /// Draws the path of the cut so far, along with the segment that would be added by
+    /// clicking, on top of the viewport.
+    pub fn paint_preview(
+        &self,
+        painter: &eframe::egui::Painter,
+        camera: &Camera,
+        rect: eframe::egui::Rect,
+    ) {
+        let screen_size = Vector2::from(rect.size());
+        let to_screen = |point: &KnifePoint| -> Option<eframe::egui::Pos2> {
+            camera
+                .project_to_screen(point.position, &screen_size)
+                .map(|p| rect.min + eframe::egui::vec2(p.x(), p.y()))
+        };
+
+        let line_stroke = eframe::egui::Stroke::new(2.0, PREVIEW_LINE_COLOR);
+        let path: Vec<&KnifePoint> = self.points.iter().chain(self.hovered.iter()).collect();
+        for segment in path.windows(2) {
+            if let (Some(start), Some(end)) = (to_screen(segment[0]), to_screen(segment[1])) {
+                painter.line_segment([start, end], line_stroke);
+            }
+        }
+
+        for point in &self.points {
+            if let Some(center) = to_screen(point) {
+                painter.circle_filled(center, 3.0, PREVIEW_LINE_COLOR);
+            }
+        }
+        if let Some(hovered) = &self.hovered {
+            if let Some(center) = to_screen(hovered) {
+                let color = match hovered.snap {
+                    KnifeSnap::Vertex => SNAP_VERTEX_COLOR,
+                    KnifeSnap::EdgeMidpoint => SNAP_EDGE_MIDPOINT_COLOR,
+                    KnifeSnap::Face => PREVIEW_LINE_COLOR,
+                };
+                painter.circle_filled(center, 4.0, color);
+            }
+        }
+    }
+}
+
+const PREVIEW_LINE_COLOR: eframe::egui::Color32 = eframe::egui::Color32::from_rgb(0, 200, 0);
+const SNAP_VERTEX_COLOR: eframe::egui::Color32 = eframe::egui::Color32::from_rgb(255, 200, 0);
+const SNAP_EDGE_MIDPOINT_COLOR: eframe::egui::Color32 =
+    eframe::egui::Color32::from_rgb(0, 200, 255);
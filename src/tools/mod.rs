@@ -0,0 +1,3 @@
+pub mod fly;
+pub mod knife;
+pub mod select;
@@ -0,0 +1,119 @@
+//! Picking the elements of the mesh being edited by clicking on them.
+
+use crate::{
+    bmesh::bmesh::BMesh,
+    camera::Camera,
+    math::{point::Point3, vector::Vector2, Float},
+    scene::SelectMode,
+};
+
+/// How close (in pixels) the cursor needs to be to a vertex, edge or face dot to pick it.
+const PICK_RADIUS: Float = 12.0;
+
+/// The vertices of the element under the cursor, if any. Vertices and edges are picked by how
+/// close they are on the screen. Faces are picked by casting a ray, unless the faces can be
+/// seen through, in which case the closest face dot is picked.
+///
+/// - `cursor`: relative to the drawing region.
+/// - `see_through`: whether elements hidden behind faces can be picked.
+pub fn pick(
+    mesh: &BMesh,
+    camera: &Camera,
+    cursor: &Vector2,
+    screen_size: &Vector2,
+    select_mode: SelectMode,
+    see_through: bool,
+) -> Option<Vec<u32>> {
+    let eye = camera.get_position();
+    let is_visible = |point: Point3| see_through || mesh.is_point_visible(eye, point);
+    let screen_distance = |point: Point3| -> Option<Float> {
+        let on_screen = camera.project_to_screen(point, screen_size)?;
+        Some(Float::hypot(
+            on_screen.x() - cursor.x(),
+            on_screen.y() - cursor.y(),
+        ))
+    };
+
+    // Each candidate is the vertices of an element, with the point it is picked by.
+    let candidates: Vec<(Vec<u32>, Point3)> = match select_mode {
+        SelectMode::Vertex => mesh
+            .get_vertices()
+            .iter()
+            .map(|v| {
+                let v = v.as_ref().borrow();
+                (vec![v.get_id()], v.get_position())
+            })
+            .collect(),
+        SelectMode::Edge => mesh
+            .get_edges()
+            .iter()
+            .map(|e| {
+                let e = e.as_ref().borrow();
+                let (v0, v1) = (e.get_v0(), e.get_v1());
+                let (v0, v1) = (v0.as_ref().borrow(), v1.as_ref().borrow());
+                let midpoint = v0.get_position().lerp(&v1.get_position(), 0.5);
+                (vec![v0.get_id(), v1.get_id()], midpoint)
+            })
+            .collect(),
+        SelectMode::Face if !see_through => {
+            let ray = camera.get_ray_through_screen_point(cursor, screen_size);
+            let hit = mesh.ray_cast(&ray)?;
+            let ids = hit.face.as_ref().borrow().get_vertex_ids();
+            return Some(ids);
+        }
+        SelectMode::Face => mesh
+            .get_faces()
+            .iter()
+            .map(|f| {
+                let f = f.as_ref().borrow();
+                let positions: Vec<Point3> = f
+                    .get_vertices()
+                    .iter()
+                    .map(|v| v.as_ref().borrow().get_position())
+                    .collect();
+                (f.get_vertex_ids(), Point3::centroid(&positions))
+            })
+            .collect(),
+    };
+
+    candidates
+        .into_iter()
+        .filter_map(|(ids, point)| {
+            let distance = screen_distance(point)?;
+            (distance < PICK_RADIUS && is_visible(point)).then_some((ids, distance))
+        })
+        .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+        .map(|(ids, _)| ids)
+}
+
+/// Selects the picked vertices. Unless `extend` is set, everything else is deselected first.
+/// When extending, picking an element that is already selected deselects it instead.
+pub fn select_picked(mesh: &mut BMesh, picked: Option<Vec<u32>>, extend: bool) {
+    let ids = match picked {
+        Some(ids) => ids,
+        None => {
+            if !extend {
+                mesh.deselect_all();
+            }
+            return;
+        }
+    };
+
+    let all_selected = ids.iter().all(|id| {
+        mesh.get_vertex(*id)
+            .is_some_and(|v| v.as_ref().borrow().is_selected())
+    });
+    if extend && all_selected {
+        for id in ids {
+            mesh.deselect_vertex(id);
+        }
+        return;
+    }
+
+    if !extend {
+        mesh.deselect_all();
+    }
+    for id in ids {
+        mesh.select_vertex(id);
+    }
+}
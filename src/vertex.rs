@@ -2,6 +2,7 @@
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Vertex {
     pub position: [f32; 3],
+    pub normal: [f32; 3],
     pub color: [f32; 3],
 }
 
@@ -21,7 +22,36 @@ impl Vertex {
                     shader_location: 1,
                     format: eframe::wgpu::VertexFormat::Float32x3,
                 },
+                eframe::wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 6]>() as eframe::wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: eframe::wgpu::VertexFormat::Float32x3,
+                },
             ],
         }
     }
 }
+
+/// Data that is the same for a whole mesh, given to each of its vertices by drawing the mesh as
+/// an instance.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Instance {
+    /// Multiplied with the color of each vertex.
+    pub base_color: [f32; 3],
+}
+
+impl Instance {
+    pub fn get_descriptor<'a>() -> eframe::wgpu::VertexBufferLayout<'a> {
+        eframe::wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Instance>() as eframe::wgpu::BufferAddress,
+            step_mode: eframe::wgpu::VertexStepMode::Instance,
+            attributes: &[eframe::wgpu::VertexAttribute {
+                offset: 0,
+                // After the attributes of `Vertex`.
+                shader_location: 3,
+                format: eframe::wgpu::VertexFormat::Float32x3,
+            }],
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! The central canvas can be split into several viewports, each looking at the scene through a
+//! camera of its own, with its own shading.
+
+use crate::{
+    camera::{Camera, ViewPreset},
+    rendering::drawing_stuff::DrawingStuff,
+};
+
+/// How the canvas is split. The viewports are laid out left to right, then top to bottom.
+#[derive(Clone, Copy, PartialEq)]
+pub enum ViewportLayout {
+    Single,
+    SideBySide,
+    Quad,
+}
+
+impl ViewportLayout {
+    /// The space between the viewports, in points.
+    const GAP: f32 = 2.0;
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            ViewportLayout::Single => "Single",
+            ViewportLayout::SideBySide => "Side by side",
+            ViewportLayout::Quad => "Quad",
+        }
+    }
+
+    pub fn get_viewport_count(&self) -> usize {
+        match self {
+            ViewportLayout::Single => 1,
+            ViewportLayout::SideBySide => 2,
+            ViewportLayout::Quad => 4,
+        }
+    }
+
+    /// Where each viewport goes in the canvas.
+    pub fn split(&self, rect: eframe::egui::Rect) -> Vec<eframe::egui::Rect> {
+        let (columns, rows) = match self {
+            ViewportLayout::Single => (1, 1),
+            ViewportLayout::SideBySide => (2, 1),
+            ViewportLayout::Quad => (2, 2),
+        };
+        let size = eframe::egui::vec2(
+            (rect.width() - Self::GAP * (columns - 1) as f32) / columns as f32,
+            (rect.height() - Self::GAP * (rows - 1) as f32) / rows as f32,
+        );
+
+        (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| (row, column)))
+            .map(|(row, column)| {
+                let min = rect.min
+                    + eframe::egui::vec2(
+                        column as f32 * (size.x + Self::GAP),
+                        row as f32 * (size.y + Self::GAP),
+                    );
+                eframe::egui::Rect::from_min_size(min, size)
+            })
+            .collect()
+    }
+}
+
+pub struct Viewport {
+    pub camera: Camera,
+    /// What gets drawn, shared with the other viewports, and how, which isn't.
+    pub drawing_stuff: DrawingStuff,
+    /// Where the drag turning the camera started, while one that started in this viewport
+    /// does.
+    pub turntable_origin: Option<eframe::egui::Pos2>,
+}
+
+impl Viewport {
+    pub fn new(preset: ViewPreset) -> Self {
+        let mut camera = Camera::initialize(0.0, 0.0);
+        camera.set_view_preset(preset);
+        let drawing_stuff = DrawingStuff::initialize(&camera);
+
+        Self {
+            camera,
+            drawing_stuff,
+            turntable_origin: None,
+        }
+    }
+
+    /// One viewport for each preset, so that the quad layout shows the scene from all sides.
+    pub fn create_all() -> Vec<Self> {
+        [
+            ViewPreset::Perspective,
+            ViewPreset::Top,
+            ViewPreset::Front,
+            ViewPreset::Right,
+        ]
+        .into_iter()
+        .map(Self::new)
+        .collect()
+    }
+}